@@ -7,7 +7,10 @@ use {
     solana_transaction_status::{EncodedTransactionWithStatusMeta, UiTransactionEncoding},
     std::{collections::HashMap, env, fmt, fs::File, sync::Arc, time::Duration},
     tokio::sync::Mutex,
-    yellowstone_grpc_client::{GeyserGrpcClient, GeyserGrpcClientError, Interceptor},
+    yellowstone_grpc_client::{
+        DecodedUpdate, GeyserGrpcBuilder, GeyserGrpcClient, GeyserGrpcClientError,
+        GeyserGrpcTransport, Interceptor,
+    },
     yellowstone_grpc_proto::prelude::{
         subscribe_request_filter_accounts_filter::Filter as AccountsFilterDataOneof,
         subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpOneof,
@@ -16,7 +19,8 @@ use {
         SubscribeRequestFilterAccountsFilter, SubscribeRequestFilterAccountsFilterMemcmp,
         SubscribeRequestFilterBlocks, SubscribeRequestFilterBlocksMeta,
         SubscribeRequestFilterEntry, SubscribeRequestFilterSlots,
-        SubscribeRequestFilterTransactions, SubscribeRequestPing, SubscribeUpdateAccount,
+        SubscribeRequestFilterTransactions, SubscribeRequestFilterTransactionsOmit,
+        SubscribeRequestFilterTransactionsProgram, SubscribeRequestPing, SubscribeUpdateAccount,
         SubscribeUpdateTransaction, SubscribeUpdateTransactionStatus,
     },
 };
@@ -43,6 +47,10 @@ struct Args {
     #[clap(long)]
     commitment: Option<ArgsCommitment>,
 
+    /// Accept zstd compressed messages
+    #[clap(long)]
+    accept_zstd: bool,
+
     #[command(subcommand)]
     action: Action,
 }
@@ -52,14 +60,11 @@ impl Args {
         Some(self.commitment.unwrap_or_default().into())
     }
 
-    async fn connect(&self) -> anyhow::Result<GeyserGrpcClient<impl Interceptor>> {
-        GeyserGrpcClient::build_from_shared(self.endpoint.clone())?
+    fn builder(&self) -> anyhow::Result<GeyserGrpcBuilder> {
+        Ok(GeyserGrpcClient::build_from_shared(self.endpoint.clone())?
             .x_token(self.x_token.clone())?
             .connect_timeout(Duration::from_secs(10))
-            .timeout(Duration::from_secs(10))
-            .connect()
-            .await
-            .map_err(Into::into)
+            .timeout(Duration::from_secs(10)))
     }
 }
 
@@ -81,6 +86,29 @@ impl From<ArgsCommitment> for CommitmentLevel {
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum ArgsTransactionsOmit {
+    LogMessages,
+    InnerInstructions,
+    TokenBalances,
+    Rewards,
+    ReturnData,
+    LoadedAddresses,
+}
+
+impl ArgsTransactionsOmit {
+    fn to_proto(fields: &[Self]) -> Option<SubscribeRequestFilterTransactionsOmit> {
+        (!fields.is_empty()).then(|| SubscribeRequestFilterTransactionsOmit {
+            log_messages: fields.contains(&Self::LogMessages),
+            inner_instructions: fields.contains(&Self::InnerInstructions),
+            token_balances: fields.contains(&Self::TokenBalances),
+            rewards: fields.contains(&Self::Rewards),
+            return_data: fields.contains(&Self::ReturnData),
+            loaded_addresses: fields.contains(&Self::LoadedAddresses),
+        })
+    }
+}
+
 #[derive(Debug, Clone, Subcommand)]
 enum Action {
     HealthCheck,
@@ -98,6 +126,14 @@ enum Action {
         blockhash: String,
     },
     GetVersion,
+    GetAccountInfo {
+        #[clap(long, short)]
+        pubkey: String,
+    },
+    GetMultipleAccounts {
+        #[clap(long, short)]
+        pubkeys: Vec<String>,
+    },
 }
 
 #[derive(Debug, Clone, clap::Args)]
@@ -130,6 +166,10 @@ struct ActionSubscribe {
     #[clap(long)]
     accounts_token_account_state: bool,
 
+    /// Receive current state of matched accounts before updates
+    #[clap(long)]
+    accounts_send_initial_state: bool,
+
     /// Receive only part of updated data account, format: `offset,size`
     #[clap(long)]
     accounts_data_slice: Vec<String>,
@@ -142,6 +182,10 @@ struct ActionSubscribe {
     #[clap(long)]
     slots_filter_by_commitment: bool,
 
+    /// Receive dead and abandoned slots
+    #[clap(long)]
+    slots_include_dead: bool,
+
     /// Subscribe on transactions updates
     #[clap(long)]
     transactions: bool,
@@ -170,6 +214,26 @@ struct ActionSubscribe {
     #[clap(long)]
     transactions_account_required: Vec<String>,
 
+    /// Filter invoked program in transactions
+    #[clap(long)]
+    transactions_program_invoked: Vec<String>,
+
+    /// Filter transactions by substring in log messages
+    #[clap(long)]
+    transactions_log_contains: Vec<String>,
+
+    /// Filter transactions by log messages prefix
+    #[clap(long)]
+    transactions_log_prefix: Vec<String>,
+
+    /// Strip log messages from transactions, deprecated: use `--transactions-omit log-messages`
+    #[clap(long)]
+    transactions_strip_logs: Option<bool>,
+
+    /// Omit fields from transactions meta
+    #[clap(long)]
+    transactions_omit: Vec<ArgsTransactionsOmit>,
+
     /// Subscribe on transactions_status updates
     #[clap(long)]
     transactions_status: bool,
@@ -198,6 +262,18 @@ struct ActionSubscribe {
     #[clap(long)]
     transactions_status_account_required: Vec<String>,
 
+    /// Filter invoked program in transactions for transactions_status
+    #[clap(long)]
+    transactions_status_program_invoked: Vec<String>,
+
+    /// Filter transactions by substring in log messages for transactions_status
+    #[clap(long)]
+    transactions_status_log_contains: Vec<String>,
+
+    /// Filter transactions by log messages prefix for transactions_status
+    #[clap(long)]
+    transactions_status_log_prefix: Vec<String>,
+
     #[clap(long)]
     entry: bool,
 
@@ -221,6 +297,14 @@ struct ActionSubscribe {
     #[clap(long)]
     blocks_include_entries: Option<bool>,
 
+    /// Stream block as fragments aligned to entries
+    #[clap(long)]
+    blocks_fragments: Option<bool>,
+
+    /// Set entry index for transactions in the block
+    #[clap(long)]
+    blocks_include_entry_index: Option<bool>,
+
     /// Subscribe on block meta updates (without transactions)
     #[clap(long)]
     blocks_meta: bool,
@@ -229,6 +313,18 @@ struct ActionSubscribe {
     #[clap(long)]
     ping: Option<i32>,
 
+    /// Replay stored messages starting from slot
+    #[clap(long)]
+    from_slot: Option<u64>,
+
+    /// Receive startup accounts matched by accounts filters before live updates
+    #[clap(long)]
+    snapshot: bool,
+
+    /// Receive accounts and transactions to roll back from dead and abandoned slots
+    #[clap(long)]
+    rollback: bool,
+
     // Resubscribe (only to slots) after
     #[clap(long)]
     resub: Option<usize>,
@@ -289,6 +385,7 @@ impl Action {
                             account: accounts_account,
                             owner: args.accounts_owner.clone(),
                             filters,
+                            send_initial_state: args.accounts_send_initial_state,
                         },
                     );
                 }
@@ -299,6 +396,7 @@ impl Action {
                         "client".to_owned(),
                         SubscribeRequestFilterSlots {
                             filter_by_commitment: Some(args.slots_filter_by_commitment),
+                            include_dead: Some(args.slots_include_dead),
                         },
                     );
                 }
@@ -314,6 +412,18 @@ impl Action {
                             account_include: args.transactions_account_include.clone(),
                             account_exclude: args.transactions_account_exclude.clone(),
                             account_required: args.transactions_account_required.clone(),
+                            program_invoked: args
+                                .transactions_program_invoked
+                                .iter()
+                                .map(|program_id| SubscribeRequestFilterTransactionsProgram {
+                                    program_id: program_id.clone(),
+                                    discriminators: vec![],
+                                })
+                                .collect(),
+                            log_contains: args.transactions_log_contains.clone(),
+                            log_prefix: args.transactions_log_prefix.clone(),
+                            strip_logs: args.transactions_strip_logs,
+                            omit: ArgsTransactionsOmit::to_proto(&args.transactions_omit),
                         },
                     );
                 }
@@ -329,6 +439,18 @@ impl Action {
                             account_include: args.transactions_status_account_include.clone(),
                             account_exclude: args.transactions_status_account_exclude.clone(),
                             account_required: args.transactions_status_account_required.clone(),
+                            program_invoked: args
+                                .transactions_status_program_invoked
+                                .iter()
+                                .map(|program_id| SubscribeRequestFilterTransactionsProgram {
+                                    program_id: program_id.clone(),
+                                    discriminators: vec![],
+                                })
+                                .collect(),
+                            log_contains: args.transactions_status_log_contains.clone(),
+                            log_prefix: args.transactions_status_log_prefix.clone(),
+                            strip_logs: None,
+                            omit: None,
                         },
                     );
                 }
@@ -347,6 +469,8 @@ impl Action {
                             include_transactions: args.blocks_include_transactions,
                             include_accounts: args.blocks_include_accounts,
                             include_entries: args.blocks_include_entries,
+                            fragments: args.blocks_fragments,
+                            include_entry_index: args.blocks_include_entry_index,
                         },
                     );
                 }
@@ -384,6 +508,10 @@ impl Action {
                         commitment: commitment.map(|x| x as i32),
                         accounts_data_slice,
                         ping,
+                        from_slot: args.from_slot,
+                        backpressure: None,
+                        snapshot: args.snapshot,
+                        rollback: args.rollback,
                     },
                     args.resub.unwrap_or(0),
                 ))
@@ -486,13 +614,24 @@ pub struct TransactionStatusPretty {
 
 impl From<SubscribeUpdateTransactionStatus> for TransactionStatusPretty {
     fn from(status: SubscribeUpdateTransactionStatus) -> Self {
-        Self {
-            slot: status.slot,
-            signature: Signature::try_from(status.signature.as_slice()).expect("valid signature"),
-            is_vote: status.is_vote,
-            index: status.index,
-            err: yellowstone_grpc_proto::convert_from::create_tx_error(status.err.as_ref())
-                .expect("valid tx err"),
+        match UpdateOneof::TransactionStatus(status)
+            .try_into()
+            .expect("valid tx status")
+        {
+            DecodedUpdate::TransactionStatus {
+                slot,
+                signature,
+                is_vote,
+                index,
+                err,
+            } => Self {
+                slot,
+                signature,
+                is_vote,
+                index,
+                err,
+            },
+            _ => unreachable!("decoded as transaction status"),
         }
     }
 }
@@ -524,61 +663,22 @@ async fn main() -> anyhow::Result<()> {
             }
             drop(zero_attempts);
 
-            let commitment = args.get_commitment();
-            let mut client = args.connect().await.map_err(backoff::Error::transient)?;
-            info!("Connected");
-
-            match &args.action {
-                Action::HealthCheck => client
-                    .health_check()
-                    .await
-                    .map_err(anyhow::Error::new)
-                    .map(|response| info!("response: {response:?}")),
-                Action::HealthWatch => geyser_health_watch(client).await,
-                Action::Subscribe(_) => {
-                    let (request, resub) = args
-                        .action
-                        .get_subscribe_request(commitment)
-                        .await
-                        .map_err(backoff::Error::Permanent)?
-                        .expect("expect subscribe action");
-
-                    geyser_subscribe(client, request, resub).await
-                }
-                Action::Ping { count } => client
-                    .ping(*count)
-                    .await
-                    .map_err(anyhow::Error::new)
-                    .map(|response| info!("response: {response:?}")),
-                Action::GetLatestBlockhash => client
-                    .get_latest_blockhash(commitment)
+            let builder = args.builder().map_err(backoff::Error::Permanent)?;
+            if args.accept_zstd {
+                let client = builder
+                    .connect_zstd()
                     .await
-                    .map_err(anyhow::Error::new)
-                    .map(|response| info!("response: {response:?}")),
-                Action::GetBlockHeight => client
-                    .get_block_height(commitment)
-                    .await
-                    .map_err(anyhow::Error::new)
-                    .map(|response| info!("response: {response:?}")),
-                Action::GetSlot => client
-                    .get_slot(commitment)
-                    .await
-                    .map_err(anyhow::Error::new)
-                    .map(|response| info!("response: {response:?}")),
-                Action::IsBlockhashValid { blockhash } => client
-                    .is_blockhash_valid(blockhash.clone(), commitment)
-                    .await
-                    .map_err(anyhow::Error::new)
-                    .map(|response| info!("response: {response:?}")),
-                Action::GetVersion => client
-                    .get_version()
+                    .map_err(|error| backoff::Error::transient(error.into()))?;
+                info!("Connected");
+                run_action(&args, client).await
+            } else {
+                let client = builder
+                    .connect()
                     .await
-                    .map_err(anyhow::Error::new)
-                    .map(|response| info!("response: {response:?}")),
+                    .map_err(|error| backoff::Error::transient(error.into()))?;
+                info!("Connected");
+                run_action(&args, client).await
             }
-            .map_err(backoff::Error::transient)?;
-
-            Ok::<(), backoff::Error<anyhow::Error>>(())
         }
         .inspect_err(|error| error!("failed to connect: {error}"))
     })
@@ -586,7 +686,75 @@ async fn main() -> anyhow::Result<()> {
     .map_err(Into::into)
 }
 
-async fn geyser_health_watch(mut client: GeyserGrpcClient<impl Interceptor>) -> anyhow::Result<()> {
+async fn run_action(
+    args: &Args,
+    mut client: GeyserGrpcClient<impl Interceptor, impl GeyserGrpcTransport>,
+) -> Result<(), backoff::Error<anyhow::Error>> {
+    let commitment = args.get_commitment();
+    match &args.action {
+        Action::HealthCheck => client
+            .health_check()
+            .await
+            .map_err(anyhow::Error::new)
+            .map(|response| info!("response: {response:?}")),
+        Action::HealthWatch => geyser_health_watch(client).await,
+        Action::Subscribe(_) => {
+            let (request, resub) = args
+                .action
+                .get_subscribe_request(commitment)
+                .await
+                .map_err(backoff::Error::Permanent)?
+                .expect("expect subscribe action");
+
+            geyser_subscribe(client, request, resub).await
+        }
+        Action::Ping { count } => client
+            .ping(*count)
+            .await
+            .map_err(anyhow::Error::new)
+            .map(|response| info!("response: {response:?}")),
+        Action::GetLatestBlockhash => client
+            .get_latest_blockhash(commitment)
+            .await
+            .map_err(anyhow::Error::new)
+            .map(|response| info!("response: {response:?}")),
+        Action::GetBlockHeight => client
+            .get_block_height(commitment)
+            .await
+            .map_err(anyhow::Error::new)
+            .map(|response| info!("response: {response:?}")),
+        Action::GetSlot => client
+            .get_slot(commitment)
+            .await
+            .map_err(anyhow::Error::new)
+            .map(|response| info!("response: {response:?}")),
+        Action::IsBlockhashValid { blockhash } => client
+            .is_blockhash_valid(blockhash.clone(), commitment)
+            .await
+            .map_err(anyhow::Error::new)
+            .map(|response| info!("response: {response:?}")),
+        Action::GetVersion => client
+            .get_version()
+            .await
+            .map_err(anyhow::Error::new)
+            .map(|response| info!("response: {response:?}")),
+        Action::GetAccountInfo { pubkey } => client
+            .get_account_info(pubkey.clone(), commitment, vec![])
+            .await
+            .map_err(anyhow::Error::new)
+            .map(|response| info!("response: {response:?}")),
+        Action::GetMultipleAccounts { pubkeys } => client
+            .get_multiple_accounts(pubkeys.clone(), commitment, vec![])
+            .await
+            .map_err(anyhow::Error::new)
+            .map(|response| info!("response: {response:?}")),
+    }
+    .map_err(backoff::Error::transient)
+}
+
+async fn geyser_health_watch(
+    mut client: GeyserGrpcClient<impl Interceptor, impl GeyserGrpcTransport>,
+) -> anyhow::Result<()> {
     let mut stream = client.health_watch().await?;
     info!("stream opened");
     while let Some(message) = stream.next().await {
@@ -597,7 +765,7 @@ async fn geyser_health_watch(mut client: GeyserGrpcClient<impl Interceptor>) ->
 }
 
 async fn geyser_subscribe(
-    mut client: GeyserGrpcClient<impl Interceptor>,
+    mut client: GeyserGrpcClient<impl Interceptor, impl GeyserGrpcTransport>,
     request: SubscribeRequest,
     resub: usize,
 ) -> anyhow::Result<()> {
@@ -671,6 +839,10 @@ async fn geyser_subscribe(
                     commitment: None,
                     accounts_data_slice: Vec::default(),
                     ping: None,
+                    from_slot: None,
+                    backpressure: None,
+                    snapshot: false,
+                    rollback: false,
                 })
                 .await
                 .map_err(GeyserGrpcClientError::SubscribeSendError)?;
@@ -1,14 +1,14 @@
 use {
     clap::Parser,
-    futures::{sink::SinkExt, stream::StreamExt},
-    log::info,
-    std::env,
-    tokio::time::{interval, Duration},
-    yellowstone_grpc_client::GeyserGrpcClient,
+    futures::stream::StreamExt,
+    log::{info, warn},
+    std::{env, time::Duration},
+    yellowstone_grpc_client::{
+        GeyserGrpcClient, ManagedSubscriptionConfig, ManagedSubscriptionUpdate,
+        SubscribeRequestBuilder,
+    },
     yellowstone_grpc_proto::prelude::{
-        subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
-        SubscribeRequestFilterSlots, SubscribeRequestPing, SubscribeUpdatePong,
-        SubscribeUpdateSlot,
+        subscribe_update::UpdateOneof, CommitmentLevel, SubscribeUpdateSlot,
     },
 };
 
@@ -33,55 +33,46 @@ async fn main() -> anyhow::Result<()> {
 
     let args = Args::parse();
 
-    let mut client = GeyserGrpcClient::build_from_shared(args.endpoint)?
+    let client = GeyserGrpcClient::build_from_shared(args.endpoint)?
         .x_token(args.x_token)?
         .connect()
         .await?;
-    let (mut subscribe_tx, mut stream) = client.subscribe().await?;
-
-    futures::try_join!(
-        async move {
-            subscribe_tx
-            .send(SubscribeRequest {
-                slots: maplit::hashmap! { "".to_owned() => SubscribeRequestFilterSlots { filter_by_commitment: Some(true) } },
-                commitment: Some(CommitmentLevel::Processed as i32),
-                ..Default::default()
-            })
-            .await?;
-
-            let mut timer = interval(Duration::from_secs(3));
-            let mut id = 0;
-            loop {
-                timer.tick().await;
-                id += 1;
-                subscribe_tx
-                    .send(SubscribeRequest {
-                        ping: Some(SubscribeRequestPing { id }),
-                        ..Default::default()
-                    })
-                    .await?;
-            }
-            #[allow(unreachable_code)]
-            Ok::<(), anyhow::Error>(())
+    let (_subscribe_tx, stream) = client.subscribe_managed(
+        SubscribeRequestBuilder::new()
+            .slots("", true)
+            .commitment(CommitmentLevel::Processed)
+            .build()?,
+        ManagedSubscriptionConfig {
+            ping_interval: Some(Duration::from_secs(3)),
+            ..Default::default()
         },
-        async move {
-            while let Some(message) = stream.next().await {
-                match message?.update_oneof.expect("valid message") {
+    );
+    tokio::pin!(stream);
+
+    while let Some(message) = stream.next().await {
+        match message? {
+            ManagedSubscriptionUpdate::Update(update) => {
+                match update.update_oneof.expect("valid message") {
                     UpdateOneof::Slot(SubscribeUpdateSlot { slot, .. }) => {
                         info!("slot received: {slot}");
                     }
                     UpdateOneof::Ping(_msg) => {
                         info!("ping received");
                     }
-                    UpdateOneof::Pong(SubscribeUpdatePong { id }) => {
-                        info!("pong received: id#{id}");
-                    }
                     msg => anyhow::bail!("received unexpected message: {msg:?}"),
                 }
             }
-            Ok::<(), anyhow::Error>(())
+            ManagedSubscriptionUpdate::Pong { id, rtt } => {
+                info!("pong received: id#{id}, rtt: {rtt:?}");
+            }
+            ManagedSubscriptionUpdate::Disconnected { error, delay, .. } => {
+                warn!("disconnected, reconnect in {delay:?}: {error}");
+            }
+            ManagedSubscriptionUpdate::Reconnected { last_slot, .. } => {
+                info!("reconnected, last slot: {last_slot:?}");
+            }
         }
-    )?;
+    }
 
     Ok(())
 }
@@ -102,6 +102,11 @@ async fn main() -> anyhow::Result<()> {
                 account_include: args.account_include,
                 account_exclude: args.account_exclude,
                 account_required: args.account_required,
+                program_invoked: vec![],
+                log_contains: vec![],
+                log_prefix: vec![],
+                strip_logs: None,
+                omit: None,
             } },
             entry: HashMap::new(),
             blocks: HashMap::new(),
@@ -109,6 +114,10 @@ async fn main() -> anyhow::Result<()> {
             commitment: Some(commitment as i32),
             accounts_data_slice: vec![],
             ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
         })
         .await?;
 
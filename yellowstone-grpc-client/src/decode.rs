@@ -0,0 +1,459 @@
+use {
+    crate::{GeyserGrpcClientResult, ManagedSubscriptionUpdate, MultiSubscriptionUpdate},
+    futures::stream::{Stream, StreamExt},
+    solana_sdk::{
+        account::Account,
+        pubkey::Pubkey,
+        signature::Signature,
+        transaction::{TransactionError, VersionedTransaction},
+    },
+    solana_transaction_status::{ConfirmedBlock, TransactionStatusMeta},
+    tonic::Status,
+    yellowstone_grpc_proto::{
+        convert_from::{self, ConvertError, ConvertResult},
+        prelude::{
+            subscribe_update::UpdateOneof, SlotStatus, SubscribeUpdate, SubscribeUpdateAccount,
+            SubscribeUpdateBlock, SubscribeUpdateSlot, SubscribeUpdateTransaction,
+            SubscribeUpdateTransactionStatus,
+        },
+    },
+};
+
+/// `SubscribeUpdate` with messages converted to `solana-sdk` types
+#[derive(Debug, Clone)]
+pub struct DecodedSubscribeUpdate {
+    pub filters: Vec<String>,
+    pub update: DecodedUpdate,
+}
+
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum DecodedUpdate {
+    Account {
+        slot: u64,
+        pubkey: Pubkey,
+        account: Account,
+        write_version: u64,
+        txn_signature: Option<Signature>,
+        is_startup: bool,
+    },
+    Slot {
+        slot: u64,
+        parent: Option<u64>,
+        status: SlotStatus,
+    },
+    Transaction {
+        slot: u64,
+        signature: Signature,
+        is_vote: bool,
+        index: u64,
+        transaction: VersionedTransaction,
+        meta: TransactionStatusMeta,
+    },
+    TransactionStatus {
+        slot: u64,
+        signature: Signature,
+        is_vote: bool,
+        index: u64,
+        err: Option<TransactionError>,
+    },
+    /// Accounts and entries of the block are not included
+    Block { slot: u64, block: ConfirmedBlock },
+    /// Messages without `solana-sdk` counterpart are passed as is
+    Other(UpdateOneof),
+}
+
+impl TryFrom<SubscribeUpdate> for DecodedSubscribeUpdate {
+    type Error = ConvertError;
+
+    fn try_from(update: SubscribeUpdate) -> ConvertResult<Self> {
+        let update_oneof = update
+            .update_oneof
+            .ok_or(ConvertError::missing("update_oneof"))?;
+        Ok(Self {
+            filters: update.filters,
+            update: update_oneof.try_into()?,
+        })
+    }
+}
+
+impl TryFrom<UpdateOneof> for DecodedUpdate {
+    type Error = ConvertError;
+
+    fn try_from(update: UpdateOneof) -> ConvertResult<Self> {
+        Ok(match update {
+            UpdateOneof::Account(msg) => decode_account(msg)?,
+            UpdateOneof::Slot(msg) => decode_slot(msg)?,
+            UpdateOneof::Transaction(msg) => decode_transaction(msg)?,
+            UpdateOneof::TransactionStatus(msg) => decode_transaction_status(msg)?,
+            UpdateOneof::Block(msg) => decode_block(msg)?,
+            update => Self::Other(update),
+        })
+    }
+}
+
+fn decode_account(msg: SubscribeUpdateAccount) -> ConvertResult<DecodedUpdate> {
+    let account = msg.account.ok_or(ConvertError::missing("account"))?;
+    let write_version = account.write_version;
+    let txn_signature = convert_from::create_account_txn_signature(&account)
+        .map_err(|error| error.in_field("account"))?;
+    let (pubkey, account) =
+        convert_from::create_account(account).map_err(|error| error.in_field("account"))?;
+    Ok(DecodedUpdate::Account {
+        slot: msg.slot,
+        pubkey,
+        account,
+        write_version,
+        txn_signature,
+        is_startup: msg.is_startup,
+    })
+}
+
+fn decode_slot(msg: SubscribeUpdateSlot) -> ConvertResult<DecodedUpdate> {
+    Ok(DecodedUpdate::Slot {
+        slot: msg.slot,
+        parent: msg.parent,
+        status: SlotStatus::try_from(msg.status).map_err(|_| ConvertError::invalid("status"))?,
+    })
+}
+
+fn decode_transaction(msg: SubscribeUpdateTransaction) -> ConvertResult<DecodedUpdate> {
+    let tx = msg
+        .transaction
+        .ok_or(ConvertError::missing("transaction"))?;
+    let transaction = tx
+        .transaction
+        .ok_or(ConvertError::missing("transaction.transaction"))?;
+    let meta = tx.meta.ok_or(ConvertError::missing("transaction.meta"))?;
+    Ok(DecodedUpdate::Transaction {
+        slot: msg.slot,
+        signature: convert_from::create_signature(&tx.signature)
+            .map_err(|error| error.in_field("transaction"))?,
+        is_vote: tx.is_vote,
+        index: tx.index,
+        transaction: convert_from::create_tx_versioned(transaction)
+            .map_err(|error| error.in_field("transaction.transaction"))?,
+        meta: convert_from::create_tx_meta(meta)
+            .map_err(|error| error.in_field("transaction.meta"))?,
+    })
+}
+
+fn decode_transaction_status(
+    msg: SubscribeUpdateTransactionStatus,
+) -> ConvertResult<DecodedUpdate> {
+    Ok(DecodedUpdate::TransactionStatus {
+        slot: msg.slot,
+        signature: convert_from::create_signature(&msg.signature)?,
+        is_vote: msg.is_vote,
+        index: msg.index,
+        err: convert_from::create_tx_error(msg.err.as_ref())?,
+    })
+}
+
+fn decode_block(msg: SubscribeUpdateBlock) -> ConvertResult<DecodedUpdate> {
+    Ok(DecodedUpdate::Block {
+        slot: msg.slot,
+        block: convert_from::create_block(msg)?,
+    })
+}
+
+/// Item of the subscription stream with `SubscribeUpdate`: stream of `subscribe`,
+/// `subscribe_managed` or `GeyserGrpcMultiClient::subscribe`
+pub trait DecodeStreamItem {
+    type Decoded;
+
+    fn decode(self) -> Self::Decoded;
+}
+
+impl DecodeStreamItem for Result<SubscribeUpdate, Status> {
+    type Decoded = GeyserGrpcClientResult<DecodedSubscribeUpdate>;
+
+    fn decode(self) -> Self::Decoded {
+        Ok(self?.try_into()?)
+    }
+}
+
+/// `Err` finishes managed and multi streams, so decode errors are returned in `Update`
+impl DecodeStreamItem for GeyserGrpcClientResult<ManagedSubscriptionUpdate> {
+    type Decoded =
+        GeyserGrpcClientResult<ManagedSubscriptionUpdate<ConvertResult<DecodedSubscribeUpdate>>>;
+
+    fn decode(self) -> Self::Decoded {
+        Ok(match self? {
+            ManagedSubscriptionUpdate::Update(update) => {
+                ManagedSubscriptionUpdate::Update(update.try_into())
+            }
+            ManagedSubscriptionUpdate::Pong { id, rtt } => {
+                ManagedSubscriptionUpdate::Pong { id, rtt }
+            }
+            ManagedSubscriptionUpdate::Disconnected {
+                error,
+                last_slot,
+                delay,
+            } => ManagedSubscriptionUpdate::Disconnected {
+                error,
+                last_slot,
+                delay,
+            },
+            ManagedSubscriptionUpdate::Reconnected {
+                attempts,
+                last_slot,
+            } => ManagedSubscriptionUpdate::Reconnected {
+                attempts,
+                last_slot,
+            },
+        })
+    }
+}
+
+impl DecodeStreamItem for MultiSubscriptionUpdate {
+    type Decoded = MultiSubscriptionUpdate<ConvertResult<DecodedSubscribeUpdate>>;
+
+    fn decode(self) -> Self::Decoded {
+        MultiSubscriptionUpdate {
+            endpoint: self.endpoint,
+            update: self.update.decode(),
+        }
+    }
+}
+
+/// Decode every update of the subscription stream, stream is not finished on decode errors
+pub fn decode_stream<S>(stream: S) -> impl Stream<Item = <S::Item as DecodeStreamItem>::Decoded>
+where
+    S: Stream,
+    S::Item: DecodeStreamItem,
+{
+    stream.map(DecodeStreamItem::decode)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{decode_stream, DecodedSubscribeUpdate, DecodedUpdate},
+        crate::ManagedSubscriptionUpdate,
+        futures::stream::{self, StreamExt},
+        solana_sdk::{pubkey::Pubkey, signature::Signature},
+        yellowstone_grpc_proto::{
+            convert_from::ConvertError,
+            prelude::{
+                subscribe_update::UpdateOneof, Message, MessageHeader, ReturnData, SlotStatus,
+                SubscribeUpdate, SubscribeUpdateAccount, SubscribeUpdateAccountInfo,
+                SubscribeUpdatePing, SubscribeUpdateSlot, SubscribeUpdateTransaction,
+                SubscribeUpdateTransactionInfo, SubscribeUpdateTransactionStatus, Transaction,
+                TransactionStatusMeta,
+            },
+        },
+    };
+
+    fn decode(update_oneof: UpdateOneof) -> Result<DecodedUpdate, ConvertError> {
+        DecodedSubscribeUpdate::try_from(SubscribeUpdate {
+            filters: vec!["client".to_owned()],
+            update_oneof: Some(update_oneof),
+        })
+        .map(|update| {
+            assert_eq!(update.filters, ["client"]);
+            update.update
+        })
+    }
+
+    #[test]
+    fn test_decode_account() {
+        let pubkey = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let signature = Signature::from([7; 64]);
+        let info = SubscribeUpdateAccountInfo {
+            pubkey: pubkey.to_bytes().to_vec(),
+            lamports: 42,
+            owner: owner.to_bytes().to_vec(),
+            executable: false,
+            rent_epoch: 1,
+            data: vec![1, 2, 3],
+            write_version: 10,
+            txn_signature: Some(signature.as_ref().to_vec()),
+        };
+
+        let decoded = decode(UpdateOneof::Account(SubscribeUpdateAccount {
+            account: Some(info.clone()),
+            slot: 100,
+            is_startup: false,
+        }));
+        let Ok(DecodedUpdate::Account {
+            slot,
+            pubkey: decoded_pubkey,
+            account,
+            write_version,
+            txn_signature,
+            is_startup,
+        }) = decoded
+        else {
+            panic!("expected decoded account");
+        };
+        assert_eq!(slot, 100);
+        assert_eq!(decoded_pubkey, pubkey);
+        assert_eq!(account.owner, owner);
+        assert_eq!(account.lamports, 42);
+        assert_eq!(account.data, [1, 2, 3]);
+        assert_eq!(write_version, 10);
+        assert_eq!(txn_signature, Some(signature));
+        assert!(!is_startup);
+
+        let decoded = decode(UpdateOneof::Account(SubscribeUpdateAccount {
+            account: Some(SubscribeUpdateAccountInfo {
+                owner: vec![1; 31],
+                ..info.clone()
+            }),
+            slot: 100,
+            is_startup: false,
+        }));
+        assert_eq!(decoded.unwrap_err(), ConvertError::invalid("account.owner"));
+
+        let decoded = decode(UpdateOneof::Account(SubscribeUpdateAccount {
+            account: Some(SubscribeUpdateAccountInfo {
+                txn_signature: Some(vec![7; 32]),
+                ..info
+            }),
+            slot: 100,
+            is_startup: false,
+        }));
+        assert_eq!(
+            decoded.unwrap_err(),
+            ConvertError::invalid("account.txn_signature")
+        );
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let decoded = decode(UpdateOneof::Slot(SubscribeUpdateSlot {
+            slot: 42,
+            parent: None,
+            status: SlotStatus::SlotConfirmed as i32,
+        }));
+        assert!(matches!(
+            decoded,
+            Ok(DecodedUpdate::Slot {
+                slot: 42,
+                status: SlotStatus::SlotConfirmed,
+                ..
+            })
+        ));
+
+        let decoded = decode(UpdateOneof::Slot(SubscribeUpdateSlot {
+            slot: 42,
+            parent: None,
+            status: -1,
+        }));
+        assert_eq!(decoded.unwrap_err(), ConvertError::invalid("status"));
+
+        let decoded = decode(UpdateOneof::TransactionStatus(
+            SubscribeUpdateTransactionStatus {
+                slot: 42,
+                signature: vec![0; 32],
+                is_vote: false,
+                index: 0,
+                err: None,
+            },
+        ));
+        let error = decoded.unwrap_err();
+        assert_eq!(error, ConvertError::invalid("signature"));
+        assert_eq!(error.to_string(), "failed to parse signature");
+
+        let decoded = decode(UpdateOneof::Ping(SubscribeUpdatePing {}));
+        assert!(matches!(
+            decoded,
+            Ok(DecodedUpdate::Other(UpdateOneof::Ping(_)))
+        ));
+
+        let decoded = DecodedSubscribeUpdate::try_from(SubscribeUpdate::default());
+        assert_eq!(decoded.unwrap_err(), ConvertError::missing("update_oneof"));
+    }
+
+    #[test]
+    fn test_decode_field_path() {
+        let transaction = SubscribeUpdateTransactionInfo {
+            signature: vec![7; 64],
+            is_vote: false,
+            transaction: Some(Transaction {
+                signatures: vec![vec![7; 64]],
+                message: Some(Message {
+                    header: Some(MessageHeader::default()),
+                    recent_blockhash: vec![0; 32],
+                    ..Default::default()
+                }),
+            }),
+            meta: Some(TransactionStatusMeta {
+                return_data: Some(ReturnData {
+                    program_id: vec![1; 31],
+                    data: vec![],
+                }),
+                ..Default::default()
+            }),
+            index: 0,
+            entry_index: None,
+        };
+        let decoded = decode(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(transaction.clone()),
+            slot: 42,
+        }));
+        let error = decoded.unwrap_err();
+        assert_eq!(
+            error,
+            ConvertError::invalid("transaction.meta.return_data.program_id")
+        );
+        assert_eq!(error.field(), "transaction.meta.return_data.program_id");
+
+        let decoded = decode(UpdateOneof::Transaction(SubscribeUpdateTransaction {
+            transaction: Some(SubscribeUpdateTransactionInfo {
+                meta: Some(TransactionStatusMeta {
+                    return_data_none: true,
+                    ..Default::default()
+                }),
+                ..transaction
+            }),
+            slot: 42,
+        }));
+        assert!(matches!(
+            decoded,
+            Ok(DecodedUpdate::Transaction { slot: 42, .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_decode_managed_stream() {
+        let updates = vec![
+            Ok(ManagedSubscriptionUpdate::Update(SubscribeUpdate {
+                filters: vec![],
+                update_oneof: Some(UpdateOneof::Ping(SubscribeUpdatePing {})),
+            })),
+            Ok(ManagedSubscriptionUpdate::Update(SubscribeUpdate::default())),
+            Ok(ManagedSubscriptionUpdate::Reconnected {
+                attempts: 1,
+                last_slot: Some(42),
+            }),
+        ];
+        let decoded = decode_stream(stream::iter(updates))
+            .collect::<Vec<_>>()
+            .await;
+        assert_eq!(decoded.len(), 3);
+        assert!(matches!(
+            &decoded[0],
+            Ok(ManagedSubscriptionUpdate::Update(Ok(
+                DecodedSubscribeUpdate {
+                    update: DecodedUpdate::Other(UpdateOneof::Ping(_)),
+                    ..
+                }
+            )))
+        ));
+        // decode error does not finish the stream
+        assert!(matches!(
+            &decoded[1],
+            Ok(ManagedSubscriptionUpdate::Update(Err(error))) if error.field() == "update_oneof"
+        ));
+        assert!(matches!(
+            &decoded[2],
+            Ok(ManagedSubscriptionUpdate::Reconnected {
+                attempts: 1,
+                last_slot: Some(42),
+            })
+        ));
+    }
+}
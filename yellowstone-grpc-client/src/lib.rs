@@ -1,5 +1,19 @@
-pub use tonic::service::Interceptor;
+#[cfg(feature = "decode")]
+mod decode;
+mod multi;
+#[cfg(feature = "request-builder")]
+mod request;
+mod subscription;
+
+#[cfg(feature = "decode")]
+pub use decode::{decode_stream, DecodeStreamItem, DecodedSubscribeUpdate, DecodedUpdate};
+#[cfg(feature = "request-builder")]
+pub use request::{
+    validate_subscribe_request, AccountsFilter, BlocksFilter, SubscribeRequestBuilder,
+    SubscribeRequestError, SubscribeRequestLimits, SubscribeRequestResult, TransactionsFilter,
+};
 use {
+    crate::subscription::ManagedSubscription,
     bytes::Bytes,
     futures::{
         channel::mpsc,
@@ -8,20 +22,34 @@ use {
     },
     std::time::Duration,
     tonic::{
+        body::BoxBody,
         codec::{CompressionEncoding, Streaming},
+        codegen::{http, Body, Service, StdError},
         metadata::{errors::InvalidMetadataValue, AsciiMetadataValue},
         service::interceptor::InterceptedService,
         transport::channel::{Channel, ClientTlsConfig, Endpoint},
         Request, Response, Status,
     },
     tonic_health::pb::{health_client::HealthClient, HealthCheckRequest, HealthCheckResponse},
-    yellowstone_grpc_proto::prelude::{
-        geyser_client::GeyserClient, CommitmentLevel, GetBlockHeightRequest,
-        GetBlockHeightResponse, GetLatestBlockhashRequest, GetLatestBlockhashResponse,
-        GetSlotRequest, GetSlotResponse, GetVersionRequest, GetVersionResponse,
-        IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest, PongResponse,
-        SubscribeRequest, SubscribeUpdate,
+    yellowstone_grpc_proto::{
+        compression::AcceptCompression,
+        prelude::{
+            geyser_client::GeyserClient, CommitmentLevel, GetAccountInfoRequest,
+            GetAccountInfoResponse, GetBlockHeightRequest, GetBlockHeightResponse,
+            GetLatestBlockhashRequest, GetLatestBlockhashResponse, GetMultipleAccountsRequest,
+            GetMultipleAccountsResponse, GetSlotRequest, GetSlotResponse, GetVersionRequest,
+            GetVersionResponse, IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest,
+            PongResponse, SubscribeRequest, SubscribeRequestAccountsDataSlice, SubscribeUpdate,
+        },
+    },
+};
+pub use {
+    multi::{
+        GeyserGrpcMultiClient, MultiEndpointStats, MultiSubscriptionConfig, MultiSubscriptionStats,
+        MultiSubscriptionUpdate,
     },
+    subscription::{ManagedSubscriptionConfig, ManagedSubscriptionUpdate},
+    tonic::service::Interceptor,
 };
 
 #[derive(Debug, Clone)]
@@ -50,13 +78,54 @@ pub enum GeyserGrpcClientError {
     TonicStatus(#[from] Status),
     #[error("Failed to send subscribe request: {0}")]
     SubscribeSendError(#[from] mpsc::SendError),
+    #[error("No messages received within {0:?}")]
+    IdleTimeout(Duration),
+    #[cfg(feature = "decode")]
+    #[error("Failed to decode update: {0}")]
+    DecodeError(#[from] yellowstone_grpc_proto::convert_from::ConvertError),
 }
 
 pub type GeyserGrpcClientResult<T> = Result<T, GeyserGrpcClientError>;
 
-pub struct GeyserGrpcClient<F> {
-    pub health: HealthClient<InterceptedService<Channel, F>>,
-    pub geyser: GeyserClient<InterceptedService<Channel, F>>,
+/// Transport of the client: `Channel`, or `AcceptCompression<Channel>` if zstd is accepted
+pub trait GeyserGrpcTransport:
+    Service<
+        http::Request<BoxBody>,
+        Response = http::Response<Self::ResponseBody>,
+        Error = Self::TransportError,
+        Future = Self::TransportFuture,
+    > + Clone
+    + Send
+    + 'static
+{
+    type ResponseBody: Body<Data = Bytes, Error = Self::ResponseBodyError>
+        + Default
+        + Send
+        + 'static;
+    type ResponseBodyError: Into<StdError> + Send;
+    type TransportError: Into<StdError> + Send + Sync;
+    type TransportFuture: Send;
+}
+
+impl<T, B> GeyserGrpcTransport for T
+where
+    T: Service<http::Request<BoxBody>, Response = http::Response<B>> + Clone + Send + 'static,
+    T::Error: Into<StdError> + Send + Sync,
+    T::Future: Send,
+    B: Body<Data = Bytes> + Default + Send + 'static,
+    B::Error: Into<StdError> + Send,
+{
+    type ResponseBody = B;
+    type ResponseBodyError = B::Error;
+    type TransportError = T::Error;
+    type TransportFuture = T::Future;
+}
+
+pub type GeyserGrpcChannel<F, C = Channel> = InterceptedService<C, F>;
+
+pub struct GeyserGrpcClient<F, C = Channel> {
+    pub health: HealthClient<GeyserGrpcChannel<F, C>>,
+    pub geyser: GeyserClient<GeyserGrpcChannel<F, C>>,
 }
 
 impl GeyserGrpcClient<()> {
@@ -71,10 +140,10 @@ impl GeyserGrpcClient<()> {
     }
 }
 
-impl<F: Interceptor> GeyserGrpcClient<F> {
+impl<F: Interceptor, C: GeyserGrpcTransport> GeyserGrpcClient<F, C> {
     pub fn new(
-        health: HealthClient<InterceptedService<Channel, F>>,
-        geyser: GeyserClient<InterceptedService<Channel, F>>,
+        health: HealthClient<GeyserGrpcChannel<F, C>>,
+        geyser: GeyserClient<GeyserGrpcChannel<F, C>>,
     ) -> Self {
         Self { health, geyser }
     }
@@ -136,6 +205,21 @@ impl<F: Interceptor> GeyserGrpcClient<F> {
             .map(|(_sink, stream)| stream)
     }
 
+    /// Subscription re-created with the last sent request on connection errors
+    pub fn subscribe_managed(
+        &self,
+        request: SubscribeRequest,
+        config: ManagedSubscriptionConfig,
+    ) -> (
+        impl Sink<SubscribeRequest, Error = mpsc::SendError>,
+        impl Stream<Item = GeyserGrpcClientResult<ManagedSubscriptionUpdate>>,
+    )
+    where
+        F: Clone + Send + 'static,
+    {
+        ManagedSubscription::create(self.geyser.clone(), request, config)
+    }
+
     // RPC calls
     pub async fn ping(&mut self, count: i32) -> GeyserGrpcClientResult<PongResponse> {
         let message = PingRequest { count };
@@ -195,6 +279,36 @@ impl<F: Interceptor> GeyserGrpcClient<F> {
         let response = self.geyser.get_version(request).await?;
         Ok(response.into_inner())
     }
+
+    pub async fn get_account_info(
+        &mut self,
+        pubkey: String,
+        commitment: Option<CommitmentLevel>,
+        data_slice: Vec<SubscribeRequestAccountsDataSlice>,
+    ) -> GeyserGrpcClientResult<GetAccountInfoResponse> {
+        let request = tonic::Request::new(GetAccountInfoRequest {
+            pubkey,
+            commitment: commitment.map(|value| value as i32),
+            data_slice,
+        });
+        let response = self.geyser.get_account_info(request).await?;
+        Ok(response.into_inner())
+    }
+
+    pub async fn get_multiple_accounts(
+        &mut self,
+        pubkeys: Vec<String>,
+        commitment: Option<CommitmentLevel>,
+        data_slice: Vec<SubscribeRequestAccountsDataSlice>,
+    ) -> GeyserGrpcClientResult<GetMultipleAccountsResponse> {
+        let request = tonic::Request::new(GetMultipleAccountsRequest {
+            pubkeys,
+            commitment: commitment.map(|value| value as i32),
+            data_slice,
+        });
+        let response = self.geyser.get_multiple_accounts(request).await?;
+        Ok(response.into_inner())
+    }
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -243,10 +357,10 @@ impl GeyserGrpcBuilder {
     }
 
     // Create client
-    fn build(
+    fn build<C: GeyserGrpcTransport>(
         self,
-        channel: Channel,
-    ) -> GeyserGrpcBuilderResult<GeyserGrpcClient<impl Interceptor>> {
+        channel: C,
+    ) -> GeyserGrpcBuilderResult<GeyserGrpcClient<InterceptorXToken, C>> {
         let interceptor: InterceptorXToken = self.x_token.into();
 
         let mut geyser = GeyserClient::with_interceptor(channel.clone(), interceptor.clone());
@@ -269,16 +383,44 @@ impl GeyserGrpcBuilder {
         ))
     }
 
-    pub async fn connect(self) -> GeyserGrpcBuilderResult<GeyserGrpcClient<impl Interceptor>> {
+    pub async fn connect(self) -> GeyserGrpcBuilderResult<GeyserGrpcClient<InterceptorXToken>> {
+        let channel = self.endpoint.connect().await?;
+        self.build(channel)
+    }
+
+    pub fn connect_lazy(self) -> GeyserGrpcBuilderResult<GeyserGrpcClient<InterceptorXToken>> {
+        let channel = self.endpoint.connect_lazy();
+        self.build(channel)
+    }
+
+    // Same as `connect`, but zstd compressed messages are accepted and decompressed before tonic decoder
+    pub async fn connect_zstd(
+        self,
+    ) -> GeyserGrpcBuilderResult<GeyserGrpcClient<InterceptorXToken, AcceptCompression<Channel>>>
+    {
         let channel = self.endpoint.connect().await?;
+        let channel = self.accept_compression(channel);
         self.build(channel)
     }
 
-    pub fn connect_lazy(self) -> GeyserGrpcBuilderResult<GeyserGrpcClient<impl Interceptor>> {
+    // Same as `connect_lazy`, but zstd compressed messages are accepted
+    pub fn connect_lazy_zstd(
+        self,
+    ) -> GeyserGrpcBuilderResult<GeyserGrpcClient<InterceptorXToken, AcceptCompression<Channel>>>
+    {
         let channel = self.endpoint.connect_lazy();
+        let channel = self.accept_compression(channel);
         self.build(channel)
     }
 
+    fn accept_compression(&self, channel: Channel) -> AcceptCompression<Channel> {
+        let channel = AcceptCompression::new(channel);
+        match self.max_decoding_message_size {
+            Some(limit) => channel.max_decoding_message_size(limit),
+            None => channel,
+        }
+    }
+
     // Set x-token
     pub fn x_token<T>(self, x_token: Option<T>) -> GeyserGrpcBuilderResult<Self>
     where
@@ -416,7 +558,16 @@ impl GeyserGrpcBuilder {
 
 #[cfg(test)]
 mod tests {
-    use super::{GeyserGrpcBuilderError, GeyserGrpcClient};
+    use {
+        super::{
+            GeyserGrpcBuilderError, GeyserGrpcClient, GeyserGrpcClientError,
+            ManagedSubscriptionConfig, ManagedSubscriptionUpdate,
+        },
+        backoff::ExponentialBackoff,
+        futures::stream::StreamExt,
+        std::time::Duration,
+        yellowstone_grpc_proto::prelude::SubscribeRequest,
+    };
 
     #[tokio::test]
     async fn test_channel_https_success() {
@@ -488,4 +639,34 @@ mod tests {
                 .to_owned()
         );
     }
+
+    #[tokio::test]
+    async fn test_subscribe_managed_reconnect() {
+        let client = GeyserGrpcClient::build_from_static("http://127.0.0.1:1")
+            .connect_lazy()
+            .unwrap();
+        let config = ManagedSubscriptionConfig {
+            backoff: ExponentialBackoff {
+                initial_interval: Duration::from_millis(1),
+                max_elapsed_time: Some(Duration::from_millis(50)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (_sink, stream) = client.subscribe_managed(SubscribeRequest::default(), config);
+        let updates = stream.collect::<Vec<_>>().await;
+
+        let (last, disconnects) = updates.split_last().unwrap();
+        assert!(!disconnects.is_empty());
+        for update in disconnects {
+            assert!(matches!(
+                update,
+                Ok(ManagedSubscriptionUpdate::Disconnected {
+                    last_slot: None,
+                    ..
+                })
+            ));
+        }
+        assert!(matches!(last, Err(GeyserGrpcClientError::TonicStatus(_))));
+    }
 }
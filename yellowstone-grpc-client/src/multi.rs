@@ -0,0 +1,427 @@
+use {
+    crate::{
+        subscription::ManagedSubscription, GeyserGrpcClient, GeyserGrpcClientResult,
+        GeyserGrpcTransport, ManagedSubscriptionConfig, ManagedSubscriptionUpdate,
+    },
+    futures::{
+        channel::mpsc,
+        sink::Sink,
+        stream::{self, BoxStream, SelectAll, Stream, StreamExt},
+    },
+    std::{
+        collections::{BTreeMap, HashMap},
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tonic::{service::Interceptor, transport::Channel},
+    yellowstone_grpc_proto::prelude::{
+        subscribe_update::UpdateOneof, SubscribeRequest, SubscribeUpdate,
+    },
+};
+
+#[derive(Debug, Clone)]
+pub struct MultiSubscriptionConfig {
+    /// Config of subscription to every endpoint
+    pub subscription: ManagedSubscriptionConfig,
+    /// Number of slots behind the highest seen slot with updates kept for deduplication,
+    /// older updates are dropped and counted as lag
+    pub dedup_slots: u64,
+}
+
+impl Default for MultiSubscriptionConfig {
+    fn default() -> Self {
+        Self {
+            subscription: ManagedSubscriptionConfig::default(),
+            dedup_slots: 150,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MultiSubscriptionUpdate<U = SubscribeUpdate> {
+    /// Index of the endpoint in `GeyserGrpcMultiClient`
+    pub endpoint: usize,
+    /// Only first copy of `Update` is forwarded, error finishes subscription to the endpoint
+    pub update: GeyserGrpcClientResult<ManagedSubscriptionUpdate<U>>,
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct MultiEndpointStats {
+    pub endpoint: String,
+    /// Number of received updates, including duplicates
+    pub received: u64,
+    /// Number of updates received before other endpoints
+    pub wins: u64,
+    /// Delay of updates received after other endpoints
+    pub lag_total: Duration,
+    pub lag_max: Duration,
+    pub last_slot: Option<u64>,
+    pub reconnects: u64,
+}
+
+impl MultiEndpointStats {
+    pub fn win_rate(&self) -> f64 {
+        if self.received == 0 {
+            0.0
+        } else {
+            self.wins as f64 / self.received as f64
+        }
+    }
+
+    pub fn lag_avg(&self) -> Duration {
+        match u32::try_from(self.received - self.wins) {
+            Ok(0) | Err(_) => Duration::ZERO,
+            Ok(count) => self.lag_total / count,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MultiSubscriptionStats {
+    endpoints: Arc<Mutex<Vec<MultiEndpointStats>>>,
+}
+
+impl MultiSubscriptionStats {
+    pub fn get(&self) -> Vec<MultiEndpointStats> {
+        self.endpoints.lock().expect("alive mutex").clone()
+    }
+}
+
+/// Client subscribed to many endpoints with the same request
+pub struct GeyserGrpcMultiClient<F, C = Channel> {
+    endpoints: Vec<(String, GeyserGrpcClient<F, C>)>,
+}
+
+impl<F, C> GeyserGrpcMultiClient<F, C>
+where
+    F: Interceptor + Clone + Send + 'static,
+    C: GeyserGrpcTransport,
+{
+    pub fn new(endpoints: Vec<(String, GeyserGrpcClient<F, C>)>) -> Self {
+        Self { endpoints }
+    }
+
+    /// Merged subscription, requests are sent to every endpoint and first copy of every update is forwarded
+    pub fn subscribe(
+        &self,
+        request: SubscribeRequest,
+        config: MultiSubscriptionConfig,
+    ) -> (
+        impl Sink<SubscribeRequest, Error = mpsc::SendError>,
+        impl Stream<Item = MultiSubscriptionUpdate>,
+        MultiSubscriptionStats,
+    ) {
+        let mut subscribe_txs = Vec::with_capacity(self.endpoints.len());
+        let mut streams = SelectAll::new();
+        let mut stats = Vec::with_capacity(self.endpoints.len());
+        for (index, (endpoint, client)) in self.endpoints.iter().enumerate() {
+            let (subscribe_tx, stream) = ManagedSubscription::create(
+                client.geyser.clone(),
+                request.clone(),
+                config.subscription.clone(),
+            );
+            subscribe_txs.push(subscribe_tx);
+            streams.push(stream.map(move |update| (index, update)).boxed());
+            stats.push(MultiEndpointStats {
+                endpoint: endpoint.clone(),
+                ..Default::default()
+            });
+        }
+        let stats = MultiSubscriptionStats {
+            endpoints: Arc::new(Mutex::new(stats)),
+        };
+
+        let (requests_tx, requests_rx) = mpsc::unbounded();
+        let subscription = MultiSubscription {
+            requests_rx,
+            requests_open: true,
+            subscribe_txs,
+            streams,
+            dedup: Dedup::new(config.dedup_slots),
+            stats: stats.clone(),
+        };
+        let stream = stream::unfold(subscription, |mut subscription| async move {
+            let item = subscription.next().await?;
+            Some((item, subscription))
+        });
+        (requests_tx, stream, stats)
+    }
+}
+
+type EndpointStream =
+    BoxStream<'static, (usize, GeyserGrpcClientResult<ManagedSubscriptionUpdate>)>;
+
+struct MultiSubscription {
+    requests_rx: mpsc::UnboundedReceiver<SubscribeRequest>,
+    requests_open: bool,
+    subscribe_txs: Vec<mpsc::UnboundedSender<SubscribeRequest>>,
+    streams: SelectAll<EndpointStream>,
+    dedup: Dedup,
+    stats: MultiSubscriptionStats,
+}
+
+impl MultiSubscription {
+    async fn next(&mut self) -> Option<MultiSubscriptionUpdate> {
+        loop {
+            tokio::select! {
+                request = self.requests_rx.next(), if self.requests_open => match request {
+                    Some(request) => {
+                        for subscribe_tx in self.subscribe_txs.iter() {
+                            let _ = subscribe_tx.unbounded_send(request.clone());
+                        }
+                    }
+                    None => self.requests_open = false,
+                },
+                item = self.streams.next() => {
+                    let (endpoint, update) = item?;
+                    if self.is_forwarded(endpoint, &update) {
+                        return Some(MultiSubscriptionUpdate { endpoint, update });
+                    }
+                }
+            }
+        }
+    }
+
+    fn is_forwarded(
+        &mut self,
+        endpoint: usize,
+        update: &GeyserGrpcClientResult<ManagedSubscriptionUpdate>,
+    ) -> bool {
+        let mut endpoints = self.stats.endpoints.lock().expect("alive mutex");
+        let stats = &mut endpoints[endpoint];
+        match update {
+            Ok(ManagedSubscriptionUpdate::Update(update)) => {
+                let Some((slot, key)) = get_dedup_key(update) else {
+                    return true;
+                };
+                stats.received += 1;
+                stats.last_slot = Some(stats.last_slot.map_or(slot, |last| last.max(slot)));
+                match self.dedup.insert(slot, key) {
+                    Some(lag) => {
+                        stats.lag_total += lag;
+                        stats.lag_max = stats.lag_max.max(lag);
+                        false
+                    }
+                    None => {
+                        stats.wins += 1;
+                        true
+                    }
+                }
+            }
+            Ok(ManagedSubscriptionUpdate::Reconnected { .. }) => {
+                stats.reconnects += 1;
+                true
+            }
+            _ => true,
+        }
+    }
+}
+
+/// Account updates are identified by the transaction signature, `write_version` is assigned
+/// by every validator independently and is used only for updates without transaction
+/// (startup, rewards), such updates are deduplicated only between plugins on the same validator
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+enum DedupKey {
+    Account {
+        pubkey: Vec<u8>,
+        txn_signature: Option<Vec<u8>>,
+        write_version: Option<u64>,
+    },
+    Slot {
+        status: i32,
+    },
+    Transaction {
+        signature: Vec<u8>,
+    },
+    TransactionStatus {
+        signature: Vec<u8>,
+    },
+    Block,
+    BlockMeta,
+    Entry {
+        index: u64,
+    },
+    BlockFragment {
+        entry_index: Option<u64>,
+    },
+    Gap {
+        to_slot: u64,
+    },
+    Rollback {
+        status: i32,
+    },
+}
+
+/// Slot and key of the update, pings and pongs are not deduplicated
+fn get_dedup_key(update: &SubscribeUpdate) -> Option<(u64, DedupKey)> {
+    Some(match update.update_oneof.as_ref()? {
+        UpdateOneof::Account(msg) => {
+            let account = msg.account.as_ref()?;
+            let key = DedupKey::Account {
+                pubkey: account.pubkey.clone(),
+                txn_signature: account.txn_signature.clone(),
+                write_version: match account.txn_signature {
+                    Some(_) => None,
+                    None => Some(account.write_version),
+                },
+            };
+            (msg.slot, key)
+        }
+        UpdateOneof::Slot(msg) => (msg.slot, DedupKey::Slot { status: msg.status }),
+        UpdateOneof::Transaction(msg) => {
+            let signature = msg.transaction.as_ref()?.signature.clone();
+            (msg.slot, DedupKey::Transaction { signature })
+        }
+        UpdateOneof::TransactionStatus(msg) => {
+            let signature = msg.signature.clone();
+            (msg.slot, DedupKey::TransactionStatus { signature })
+        }
+        UpdateOneof::Block(msg) => (msg.slot, DedupKey::Block),
+        UpdateOneof::BlockMeta(msg) => (msg.slot, DedupKey::BlockMeta),
+        UpdateOneof::Entry(msg) => (msg.slot, DedupKey::Entry { index: msg.index }),
+        UpdateOneof::BlockFragment(msg) => {
+            let entry_index = msg.entry.as_ref().map(|entry| entry.index);
+            (msg.slot, DedupKey::BlockFragment { entry_index })
+        }
+        UpdateOneof::Gap(msg) => (
+            msg.from_slot,
+            DedupKey::Gap {
+                to_slot: msg.to_slot,
+            },
+        ),
+        UpdateOneof::Rollback(msg) => (msg.slot, DedupKey::Rollback { status: msg.status }),
+        UpdateOneof::Ping(_) | UpdateOneof::Pong(_) => return None,
+    })
+}
+
+/// Receive time of the first copy of updates by slot
+#[derive(Debug)]
+struct Dedup {
+    slots: BTreeMap<u64, HashMap<DedupKey, Instant>>,
+    max_slots: u64,
+    max_slot: u64,
+    max_slot_ts: Instant,
+}
+
+impl Dedup {
+    fn new(max_slots: u64) -> Self {
+        Self {
+            slots: BTreeMap::new(),
+            max_slots,
+            max_slot: 0,
+            max_slot_ts: Instant::now(),
+        }
+    }
+
+    /// Returns delay after the first copy, `None` for the first copy.
+    /// Updates older than the dedup window are dropped, delay is counted from the last move
+    /// of the window (lower bound, the first copy was already evicted)
+    fn insert(&mut self, slot: u64, key: DedupKey) -> Option<Duration> {
+        let min_slot = self.max_slot.saturating_sub(self.max_slots);
+        if slot < min_slot {
+            return Some(self.max_slot_ts.elapsed());
+        }
+
+        if slot > self.max_slot {
+            self.max_slot = slot;
+            self.max_slot_ts = Instant::now();
+            let min_slot = slot.saturating_sub(self.max_slots);
+            while let Some(entry) = self.slots.first_entry() {
+                if *entry.key() >= min_slot {
+                    break;
+                }
+                entry.remove();
+            }
+        }
+
+        let keys = self.slots.entry(slot).or_default();
+        match keys.get(&key) {
+            Some(ts) => Some(ts.elapsed()),
+            None => {
+                keys.insert(key, Instant::now());
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{get_dedup_key, Dedup, DedupKey},
+        yellowstone_grpc_proto::prelude::{
+            subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateAccount,
+            SubscribeUpdateAccountInfo, SubscribeUpdatePing, SubscribeUpdateSlot,
+        },
+    };
+
+    #[test]
+    fn test_dedup_window() {
+        let mut dedup = Dedup::new(10);
+        assert!(dedup.insert(100, DedupKey::Block).is_none());
+        assert!(dedup.insert(100, DedupKey::Block).is_some());
+        assert!(dedup.insert(100, DedupKey::BlockMeta).is_none());
+
+        // slot 100 is kept while in the window
+        assert!(dedup.insert(110, DedupKey::Block).is_none());
+        assert!(dedup.insert(100, DedupKey::Block).is_some());
+
+        // slot 100 is removed, old updates are dropped
+        assert!(dedup.insert(111, DedupKey::Block).is_none());
+        assert_eq!(dedup.slots.keys().copied().collect::<Vec<_>>(), [110, 111]);
+        assert!(dedup.insert(100, DedupKey::Block).is_some());
+        assert!(dedup.insert(100, DedupKey::BlockMeta).is_some());
+        assert_eq!(dedup.slots.keys().copied().collect::<Vec<_>>(), [110, 111]);
+    }
+
+    #[test]
+    fn test_dedup_key() {
+        let update = |update_oneof| SubscribeUpdate {
+            filters: vec![],
+            update_oneof: Some(update_oneof),
+        };
+        let slot = |status| {
+            update(UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot: 42,
+                parent: None,
+                status,
+            }))
+        };
+
+        assert_eq!(
+            get_dedup_key(&slot(0)),
+            Some((42, DedupKey::Slot { status: 0 }))
+        );
+        assert_ne!(get_dedup_key(&slot(0)), get_dedup_key(&slot(1)));
+        assert_eq!(
+            get_dedup_key(&update(UpdateOneof::Ping(SubscribeUpdatePing {}))),
+            None
+        );
+
+        let account = |write_version, txn_signature| {
+            update(UpdateOneof::Account(SubscribeUpdateAccount {
+                account: Some(SubscribeUpdateAccountInfo {
+                    pubkey: vec![1; 32],
+                    write_version,
+                    txn_signature,
+                    ..Default::default()
+                }),
+                slot: 42,
+                is_startup: false,
+            }))
+        };
+        // write_version differs between validators, signature is the same
+        assert_eq!(
+            get_dedup_key(&account(10, Some(vec![7; 64]))),
+            get_dedup_key(&account(20, Some(vec![7; 64])))
+        );
+        assert_ne!(
+            get_dedup_key(&account(10, Some(vec![7; 64]))),
+            get_dedup_key(&account(10, Some(vec![8; 64])))
+        );
+        assert_ne!(
+            get_dedup_key(&account(10, None)),
+            get_dedup_key(&account(20, None))
+        );
+    }
+}
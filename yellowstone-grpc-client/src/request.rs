@@ -0,0 +1,664 @@
+use {
+    solana_sdk::{pubkey::Pubkey, signature::Signature},
+    std::str::FromStr,
+    yellowstone_grpc_proto::{
+        filter::{
+            validate_data_slices, validate_transactions_filter, AccountsFilterData,
+            AccountsFilterDataError, DataSlicesError, TransactionsFilterError,
+        },
+        prelude::{
+            subscribe_request_filter_accounts_filter::Filter as AccountsFilterDataOneof,
+            subscribe_request_filter_accounts_filter_lamports::Cmp as AccountsFilterLamports,
+            subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpOneof,
+            BackpressurePolicy, CommitmentLevel, SubscribeRequest,
+            SubscribeRequestAccountsDataSlice, SubscribeRequestFilterAccounts,
+            SubscribeRequestFilterAccountsFilter, SubscribeRequestFilterAccountsFilterLamports,
+            SubscribeRequestFilterAccountsFilterMemcmp, SubscribeRequestFilterBlocks,
+            SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterEntry,
+            SubscribeRequestFilterSlots, SubscribeRequestFilterTransactions,
+            SubscribeRequestFilterTransactionsOmit, SubscribeRequestFilterTransactionsProgram,
+        },
+    },
+};
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum SubscribeRequestError {
+    #[error("filter {filter:?}: invalid pubkey {pubkey:?}")]
+    InvalidPubkey { filter: String, pubkey: String },
+    #[error("filter {filter:?}: invalid signature {signature:?}")]
+    InvalidSignature { filter: String, signature: String },
+    #[error("filter {filter:?}: too many data filters, max {max}")]
+    TooManyDataFilters { filter: String, max: usize },
+    #[error("filter {filter:?}: too many lamports filters, max {max}")]
+    TooManyLamportsFilters { filter: String, max: usize },
+    #[error("filter {filter:?}: data filter should be defined")]
+    DataFilterMissing { filter: String },
+    #[error("filter {filter:?}: data for memcmp should be defined")]
+    MemcmpDataMissing { filter: String },
+    #[error("filter {filter:?}: memcmp data too large")]
+    MemcmpDataTooLarge { filter: String },
+    #[error("filter {filter:?}: invalid base58 memcmp data")]
+    MemcmpInvalidBase58 { filter: String },
+    #[error("filter {filter:?}: invalid base64 memcmp data")]
+    MemcmpInvalidBase64 { filter: String },
+    #[error("filter {filter:?}: datasize used more than once")]
+    DuplicateDatasize { filter: String },
+    #[error("filter {filter:?}: executable used more than once")]
+    DuplicateExecutable { filter: String },
+    #[error("filter {filter:?}: token_account_state only allowed to be true")]
+    TokenAccountStateFalse { filter: String },
+    #[error("filter {filter:?}: cmp for lamports should be defined")]
+    LamportsCmpMissing { filter: String },
+    #[error("filter {filter:?}: log pattern should not be empty")]
+    EmptyLogPattern { filter: String },
+    #[error("filter {filter:?}: discriminator should not be empty")]
+    EmptyDiscriminator { filter: String },
+    #[error("invalid commitment level {0}")]
+    InvalidCommitment(i32),
+    #[error("invalid backpressure policy {0}")]
+    InvalidBackpressure(i32),
+    #[error("data slices out of order")]
+    DataSlicesOutOfOrder,
+    #[error("data slices overlap")]
+    DataSlicesOverlap,
+}
+
+impl SubscribeRequestError {
+    fn from_accounts_data(filter: &str, error: AccountsFilterDataError) -> Self {
+        let filter = filter.to_owned();
+        match error {
+            AccountsFilterDataError::TooManyFilters(max) => {
+                Self::TooManyDataFilters { filter, max }
+            }
+            AccountsFilterDataError::TooManyLamportsFilters(max) => {
+                Self::TooManyLamportsFilters { filter, max }
+            }
+            AccountsFilterDataError::FilterMissing => Self::DataFilterMissing { filter },
+            AccountsFilterDataError::MemcmpDataMissing => Self::MemcmpDataMissing { filter },
+            AccountsFilterDataError::MemcmpDataTooLarge => Self::MemcmpDataTooLarge { filter },
+            AccountsFilterDataError::MemcmpInvalidBase58 => Self::MemcmpInvalidBase58 { filter },
+            AccountsFilterDataError::MemcmpInvalidBase64 => Self::MemcmpInvalidBase64 { filter },
+            AccountsFilterDataError::DuplicateDatasize => Self::DuplicateDatasize { filter },
+            AccountsFilterDataError::DuplicateExecutable => Self::DuplicateExecutable { filter },
+            AccountsFilterDataError::TokenAccountStateFalse => {
+                Self::TokenAccountStateFalse { filter }
+            }
+            AccountsFilterDataError::LamportsCmpMissing => Self::LamportsCmpMissing { filter },
+        }
+    }
+}
+
+pub type SubscribeRequestResult<T> = Result<T, SubscribeRequestError>;
+
+/// Limits configurable in the plugin, defaults are the same as in the plugin
+#[derive(Debug, Clone, Copy)]
+pub struct SubscribeRequestLimits {
+    /// Max amount of data filters (`memcmp`, `datasize`, `lamports`, etc) per accounts filter
+    pub accounts_filters_max: usize,
+    /// Max amount of `lamports` comparators per accounts filter
+    pub accounts_lamports_max: usize,
+}
+
+impl Default for SubscribeRequestLimits {
+    fn default() -> Self {
+        Self {
+            accounts_filters_max: 4,
+            accounts_lamports_max: usize::MAX,
+        }
+    }
+}
+
+/// Check request with the same rules as the plugin, limits of the server can be stricter
+pub fn validate_subscribe_request(
+    request: &SubscribeRequest,
+    limits: &SubscribeRequestLimits,
+) -> SubscribeRequestResult<()> {
+    for (name, filter) in request.accounts.iter() {
+        validate_pubkeys(name, filter.account.iter().chain(filter.owner.iter()))?;
+        validate_accounts_data(name, &filter.filters, limits)?;
+    }
+    for (name, filter) in request
+        .transactions
+        .iter()
+        .chain(request.transactions_status.iter())
+    {
+        if let Some(signature) = &filter.signature {
+            if Signature::from_str(signature).is_err() {
+                return Err(SubscribeRequestError::InvalidSignature {
+                    filter: name.clone(),
+                    signature: signature.clone(),
+                });
+            }
+        }
+        validate_pubkeys(
+            name,
+            filter
+                .account_include
+                .iter()
+                .chain(filter.account_exclude.iter())
+                .chain(filter.account_required.iter())
+                .chain(filter.program_invoked.iter().map(|p| &p.program_id)),
+        )?;
+        validate_transactions_filter(filter).map_err(|error| {
+            let filter = name.clone();
+            match error {
+                TransactionsFilterError::EmptyLogPattern => {
+                    SubscribeRequestError::EmptyLogPattern { filter }
+                }
+                TransactionsFilterError::EmptyDiscriminator => {
+                    SubscribeRequestError::EmptyDiscriminator { filter }
+                }
+            }
+        })?;
+    }
+    for (name, filter) in request.blocks.iter() {
+        validate_pubkeys(name, filter.account_include.iter())?;
+    }
+
+    if let Some(commitment) = request.commitment {
+        CommitmentLevel::try_from(commitment)
+            .map_err(|_| SubscribeRequestError::InvalidCommitment(commitment))?;
+    }
+    if let Some(backpressure) = request.backpressure {
+        BackpressurePolicy::try_from(backpressure)
+            .map_err(|_| SubscribeRequestError::InvalidBackpressure(backpressure))?;
+    }
+    validate_data_slices(&request.accounts_data_slice).map_err(|error| match error {
+        DataSlicesError::OutOfOrder => SubscribeRequestError::DataSlicesOutOfOrder,
+        DataSlicesError::Overlap => SubscribeRequestError::DataSlicesOverlap,
+    })
+}
+
+fn validate_pubkeys<'a>(
+    filter: &str,
+    mut pubkeys: impl Iterator<Item = &'a String>,
+) -> SubscribeRequestResult<()> {
+    match pubkeys.find(|pubkey| Pubkey::from_str(pubkey).is_err()) {
+        Some(pubkey) => Err(SubscribeRequestError::InvalidPubkey {
+            filter: filter.to_owned(),
+            pubkey: pubkey.clone(),
+        }),
+        None => Ok(()),
+    }
+}
+
+fn validate_accounts_data(
+    name: &str,
+    filters: &[SubscribeRequestFilterAccountsFilter],
+    limits: &SubscribeRequestLimits,
+) -> SubscribeRequestResult<()> {
+    AccountsFilterData::new(
+        filters,
+        limits.accounts_filters_max,
+        limits.accounts_lamports_max,
+    )
+    .map(|_data| ())
+    .map_err(|error| SubscribeRequestError::from_accounts_data(name, error))
+}
+
+/// Builder of `SubscribeRequest`, request is validated on `build`
+#[derive(Debug, Default, Clone)]
+pub struct SubscribeRequestBuilder {
+    request: SubscribeRequest,
+    limits: SubscribeRequestLimits,
+}
+
+impl SubscribeRequestBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn build(self) -> SubscribeRequestResult<SubscribeRequest> {
+        validate_subscribe_request(&self.request, &self.limits)?;
+        Ok(self.request)
+    }
+
+    pub fn limits(self, limits: SubscribeRequestLimits) -> Self {
+        Self { limits, ..self }
+    }
+
+    pub fn accounts(mut self, name: impl Into<String>, filter: AccountsFilter) -> Self {
+        self.request.accounts.insert(name.into(), filter.0);
+        self
+    }
+
+    pub fn slots(mut self, name: impl Into<String>, filter_by_commitment: bool) -> Self {
+        let filter = SubscribeRequestFilterSlots {
+            filter_by_commitment: Some(filter_by_commitment),
+            ..Default::default()
+        };
+        self.request.slots.insert(name.into(), filter);
+        self
+    }
+
+    pub fn transactions(mut self, name: impl Into<String>, filter: TransactionsFilter) -> Self {
+        self.request.transactions.insert(name.into(), filter.0);
+        self
+    }
+
+    pub fn transactions_status(
+        mut self,
+        name: impl Into<String>,
+        filter: TransactionsFilter,
+    ) -> Self {
+        self.request
+            .transactions_status
+            .insert(name.into(), filter.0);
+        self
+    }
+
+    pub fn blocks(mut self, name: impl Into<String>, filter: BlocksFilter) -> Self {
+        self.request.blocks.insert(name.into(), filter.0);
+        self
+    }
+
+    pub fn blocks_meta(mut self, name: impl Into<String>) -> Self {
+        let filter = SubscribeRequestFilterBlocksMeta::default();
+        self.request.blocks_meta.insert(name.into(), filter);
+        self
+    }
+
+    pub fn entry(mut self, name: impl Into<String>) -> Self {
+        let filter = SubscribeRequestFilterEntry::default();
+        self.request.entry.insert(name.into(), filter);
+        self
+    }
+
+    pub fn commitment(mut self, commitment: CommitmentLevel) -> Self {
+        self.request.commitment = Some(commitment as i32);
+        self
+    }
+
+    /// Slices should be added in order and should not overlap
+    pub fn accounts_data_slice(mut self, offset: u64, length: u64) -> Self {
+        let slice = SubscribeRequestAccountsDataSlice { offset, length };
+        self.request.accounts_data_slice.push(slice);
+        self
+    }
+
+    pub fn from_slot(mut self, slot: u64) -> Self {
+        self.request.from_slot = Some(slot);
+        self
+    }
+
+    pub fn backpressure(mut self, backpressure: BackpressurePolicy) -> Self {
+        self.request.backpressure = Some(backpressure as i32);
+        self
+    }
+
+    pub fn snapshot(mut self, enabled: bool) -> Self {
+        self.request.snapshot = enabled;
+        self
+    }
+
+    pub fn rollback(mut self, enabled: bool) -> Self {
+        self.request.rollback = enabled;
+        self
+    }
+}
+
+fn to_strings(pubkeys: impl IntoIterator<Item = Pubkey>) -> impl Iterator<Item = String> {
+    pubkeys.into_iter().map(|pubkey| pubkey.to_string())
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct AccountsFilter(SubscribeRequestFilterAccounts);
+
+impl AccountsFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn account(self, pubkey: Pubkey) -> Self {
+        self.accounts([pubkey])
+    }
+
+    pub fn accounts(mut self, pubkeys: impl IntoIterator<Item = Pubkey>) -> Self {
+        self.0.account.extend(to_strings(pubkeys));
+        self
+    }
+
+    pub fn owner(self, pubkey: Pubkey) -> Self {
+        self.owners([pubkey])
+    }
+
+    pub fn owners(mut self, pubkeys: impl IntoIterator<Item = Pubkey>) -> Self {
+        self.0.owner.extend(to_strings(pubkeys));
+        self
+    }
+
+    fn filter(mut self, filter: AccountsFilterDataOneof) -> Self {
+        self.0.filters.push(SubscribeRequestFilterAccountsFilter {
+            filter: Some(filter),
+        });
+        self
+    }
+
+    pub fn memcmp(self, offset: u64, data: impl Into<Vec<u8>>) -> Self {
+        self.filter(AccountsFilterDataOneof::Memcmp(
+            SubscribeRequestFilterAccountsFilterMemcmp {
+                offset,
+                data: Some(AccountsFilterMemcmpOneof::Bytes(data.into())),
+            },
+        ))
+    }
+
+    pub fn datasize(self, datasize: u64) -> Self {
+        self.filter(AccountsFilterDataOneof::Datasize(datasize))
+    }
+
+    pub fn token_account_state(self) -> Self {
+        self.filter(AccountsFilterDataOneof::TokenAccountState(true))
+    }
+
+    pub fn lamports(self, cmp: AccountsFilterLamports) -> Self {
+        self.filter(AccountsFilterDataOneof::Lamports(
+            SubscribeRequestFilterAccountsFilterLamports { cmp: Some(cmp) },
+        ))
+    }
+
+    pub fn executable(self, executable: bool) -> Self {
+        self.filter(AccountsFilterDataOneof::Executable(executable))
+    }
+
+    pub fn send_initial_state(mut self, enabled: bool) -> Self {
+        self.0.send_initial_state = enabled;
+        self
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct TransactionsFilter(SubscribeRequestFilterTransactions);
+
+impl TransactionsFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn vote(mut self, vote: bool) -> Self {
+        self.0.vote = Some(vote);
+        self
+    }
+
+    pub fn failed(mut self, failed: bool) -> Self {
+        self.0.failed = Some(failed);
+        self
+    }
+
+    pub fn signature(mut self, signature: Signature) -> Self {
+        self.0.signature = Some(signature.to_string());
+        self
+    }
+
+    pub fn account_include(mut self, pubkeys: impl IntoIterator<Item = Pubkey>) -> Self {
+        self.0.account_include.extend(to_strings(pubkeys));
+        self
+    }
+
+    pub fn account_exclude(mut self, pubkeys: impl IntoIterator<Item = Pubkey>) -> Self {
+        self.0.account_exclude.extend(to_strings(pubkeys));
+        self
+    }
+
+    pub fn account_required(mut self, pubkeys: impl IntoIterator<Item = Pubkey>) -> Self {
+        self.0.account_required.extend(to_strings(pubkeys));
+        self
+    }
+
+    /// Empty `discriminators` match any instruction of the program
+    pub fn program_invoked(mut self, program_id: Pubkey, discriminators: Vec<Vec<u8>>) -> Self {
+        self.0
+            .program_invoked
+            .push(SubscribeRequestFilterTransactionsProgram {
+                program_id: program_id.to_string(),
+                discriminators,
+            });
+        self
+    }
+
+    pub fn log_contains(mut self, pattern: impl Into<String>) -> Self {
+        self.0.log_contains.push(pattern.into());
+        self
+    }
+
+    pub fn log_prefix(mut self, pattern: impl Into<String>) -> Self {
+        self.0.log_prefix.push(pattern.into());
+        self
+    }
+
+    #[deprecated(note = "use `omit` with `log_messages`")]
+    pub fn strip_logs(mut self, enabled: bool) -> Self {
+        self.0.strip_logs = Some(enabled);
+        self
+    }
+
+    pub fn omit(mut self, omit: SubscribeRequestFilterTransactionsOmit) -> Self {
+        self.0.omit = Some(omit);
+        self
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct BlocksFilter(SubscribeRequestFilterBlocks);
+
+impl BlocksFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn account_include(mut self, pubkeys: impl IntoIterator<Item = Pubkey>) -> Self {
+        self.0.account_include.extend(to_strings(pubkeys));
+        self
+    }
+
+    pub fn include_transactions(mut self, enabled: bool) -> Self {
+        self.0.include_transactions = Some(enabled);
+        self
+    }
+
+    pub fn include_accounts(mut self, enabled: bool) -> Self {
+        self.0.include_accounts = Some(enabled);
+        self
+    }
+
+    pub fn include_entries(mut self, enabled: bool) -> Self {
+        self.0.include_entries = Some(enabled);
+        self
+    }
+
+    pub fn fragments(mut self, enabled: bool) -> Self {
+        self.0.fragments = Some(enabled);
+        self
+    }
+
+    pub fn include_entry_index(mut self, enabled: bool) -> Self {
+        self.0.include_entry_index = Some(enabled);
+        self
+    }
+}
+
+impl From<AccountsFilter> for SubscribeRequestFilterAccounts {
+    fn from(filter: AccountsFilter) -> Self {
+        filter.0
+    }
+}
+
+impl From<TransactionsFilter> for SubscribeRequestFilterTransactions {
+    fn from(filter: TransactionsFilter) -> Self {
+        filter.0
+    }
+}
+
+impl From<BlocksFilter> for SubscribeRequestFilterBlocks {
+    fn from(filter: BlocksFilter) -> Self {
+        filter.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            validate_subscribe_request, AccountsFilter, AccountsFilterLamports,
+            SubscribeRequestBuilder, SubscribeRequestError, SubscribeRequestLimits,
+            TransactionsFilter,
+        },
+        solana_sdk::pubkey::Pubkey,
+        yellowstone_grpc_proto::prelude::{
+            subscribe_request_filter_accounts_filter::Filter as AccountsFilterDataOneof,
+            subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpOneof,
+            CommitmentLevel, SubscribeRequest, SubscribeRequestFilterAccounts,
+            SubscribeRequestFilterAccountsFilter, SubscribeRequestFilterAccountsFilterMemcmp,
+        },
+    };
+
+    fn memcmp_base58(data: &str) -> SubscribeRequestFilterAccountsFilter {
+        SubscribeRequestFilterAccountsFilter {
+            filter: Some(AccountsFilterDataOneof::Memcmp(
+                SubscribeRequestFilterAccountsFilterMemcmp {
+                    offset: 0,
+                    data: Some(AccountsFilterMemcmpOneof::Base58(data.to_owned())),
+                },
+            )),
+        }
+    }
+
+    #[test]
+    fn test_builder() {
+        let owner = Pubkey::new_unique();
+        let request = SubscribeRequestBuilder::new()
+            .accounts(
+                "client",
+                AccountsFilter::new()
+                    .owner(owner)
+                    .datasize(165)
+                    .memcmp(32, owner.to_bytes())
+                    .lamports(AccountsFilterLamports::Gt(0)),
+            )
+            .transactions(
+                "client",
+                TransactionsFilter::new()
+                    .vote(false)
+                    .account_include([owner]),
+            )
+            .commitment(CommitmentLevel::Confirmed)
+            .accounts_data_slice(0, 32)
+            .accounts_data_slice(64, 8)
+            .build()
+            .expect("valid request");
+
+        assert_eq!(request.accounts["client"].owner, [owner.to_string()]);
+        assert_eq!(request.accounts["client"].filters.len(), 3);
+        assert_eq!(
+            request.transactions["client"].account_include,
+            [owner.to_string()]
+        );
+        assert_eq!(request.commitment, Some(CommitmentLevel::Confirmed as i32));
+        assert_eq!(request.accounts_data_slice.len(), 2);
+    }
+
+    #[test]
+    fn test_builder_validation() {
+        let error = SubscribeRequestBuilder::new()
+            .accounts("client", AccountsFilter::new().datasize(1).datasize(2))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            SubscribeRequestError::DuplicateDatasize {
+                filter: "client".to_owned()
+            }
+        );
+
+        let filter = (0..5).fold(AccountsFilter::new(), |filter, offset| {
+            filter.memcmp(offset, [1])
+        });
+        let builder = SubscribeRequestBuilder::new().accounts("client", filter);
+        assert_eq!(
+            builder.clone().build().unwrap_err(),
+            SubscribeRequestError::TooManyDataFilters {
+                filter: "client".to_owned(),
+                max: 4
+            }
+        );
+        let limits = SubscribeRequestLimits {
+            accounts_filters_max: 5,
+            ..Default::default()
+        };
+        assert!(builder.limits(limits).build().is_ok());
+
+        let error = SubscribeRequestBuilder::new()
+            .accounts("client", AccountsFilter::new().memcmp(0, vec![0; 129]))
+            .build()
+            .unwrap_err();
+        assert_eq!(
+            error,
+            SubscribeRequestError::MemcmpDataTooLarge {
+                filter: "client".to_owned()
+            }
+        );
+
+        let error = SubscribeRequestBuilder::new()
+            .accounts_data_slice(0, 32)
+            .accounts_data_slice(16, 32)
+            .build()
+            .unwrap_err();
+        assert_eq!(error, SubscribeRequestError::DataSlicesOverlap);
+
+        let error = SubscribeRequestBuilder::new()
+            .accounts_data_slice(64, 8)
+            .accounts_data_slice(0, 32)
+            .build()
+            .unwrap_err();
+        assert_eq!(error, SubscribeRequestError::DataSlicesOutOfOrder);
+    }
+
+    #[test]
+    fn test_validate_raw_request() {
+        let limits = SubscribeRequestLimits::default();
+        let request = |account: &str, filters| SubscribeRequest {
+            accounts: [(
+                "client".to_owned(),
+                SubscribeRequestFilterAccounts {
+                    account: vec![account.to_owned()],
+                    filters,
+                    ..Default::default()
+                },
+            )]
+            .into(),
+            ..Default::default()
+        };
+        let pubkey = Pubkey::new_unique().to_string();
+
+        assert!(validate_subscribe_request(&request(&pubkey, vec![]), &limits).is_ok());
+        assert_eq!(
+            validate_subscribe_request(&request("abc", vec![]), &limits).unwrap_err(),
+            SubscribeRequestError::InvalidPubkey {
+                filter: "client".to_owned(),
+                pubkey: "abc".to_owned()
+            }
+        );
+
+        let filters = vec![memcmp_base58(&"1".repeat(176))];
+        assert!(matches!(
+            validate_subscribe_request(&request(&pubkey, filters), &limits),
+            Err(SubscribeRequestError::MemcmpDataTooLarge { .. })
+        ));
+        let filters = vec![memcmp_base58("0OIl")];
+        assert!(matches!(
+            validate_subscribe_request(&request(&pubkey, filters), &limits),
+            Err(SubscribeRequestError::MemcmpInvalidBase58 { .. })
+        ));
+        let filters = vec![memcmp_base58(&pubkey)];
+        assert!(validate_subscribe_request(&request(&pubkey, filters), &limits).is_ok());
+
+        let request = SubscribeRequest {
+            commitment: Some(42),
+            ..Default::default()
+        };
+        assert_eq!(
+            validate_subscribe_request(&request, &limits).unwrap_err(),
+            SubscribeRequestError::InvalidCommitment(42)
+        );
+    }
+}
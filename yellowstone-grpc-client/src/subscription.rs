@@ -0,0 +1,463 @@
+use {
+    crate::{
+        GeyserGrpcChannel, GeyserGrpcClientError, GeyserGrpcClientResult, GeyserGrpcTransport,
+    },
+    backoff::{backoff::Backoff, ExponentialBackoff},
+    futures::{
+        channel::mpsc,
+        future::pending,
+        stream::{self, Stream, StreamExt},
+    },
+    std::{collections::VecDeque, time::Duration},
+    tokio::time::{interval_at, sleep_until, Instant, Interval},
+    tonic::{service::Interceptor, Code, Status, Streaming},
+    yellowstone_grpc_proto::prelude::{
+        geyser_client::GeyserClient, subscribe_update::UpdateOneof, SubscribeRequest,
+        SubscribeRequestPing, SubscribeUpdate, SubscribeUpdatePong,
+    },
+};
+
+/// Maximum number of pings waiting for pong
+const PINGS_IN_FLIGHT_MAX: usize = 16;
+
+#[derive(Debug, Clone)]
+pub struct ManagedSubscriptionConfig {
+    /// Delay between reconnect attempts, reset after every successful reconnect.
+    /// Stream is finished with the last error once `max_elapsed_time` is reached
+    pub backoff: ExponentialBackoff,
+    /// Reply to pings from the server, keeps connection open behind load balancers
+    pub reply_ping: bool,
+    /// Send own pings with interval
+    pub ping_interval: Option<Duration>,
+    /// Reconnect with `IdleTimeout` error if nothing received within timeout,
+    /// the server sends pings every 10 seconds
+    pub idle_timeout: Option<Duration>,
+}
+
+impl Default for ManagedSubscriptionConfig {
+    fn default() -> Self {
+        Self {
+            backoff: ExponentialBackoff::default(),
+            reply_ping: true,
+            ping_interval: None,
+            idle_timeout: Some(Duration::from_secs(30)),
+        }
+    }
+}
+
+/// `U` is `Result<DecodedSubscribeUpdate, ConvertError>` for streams wrapped with `decode_stream`
+#[derive(Debug)]
+#[allow(clippy::large_enum_variant)]
+pub enum ManagedSubscriptionUpdate<U = SubscribeUpdate> {
+    Update(U),
+    /// Pong for ping sent by the subscription, replaces `Pong` update
+    Pong {
+        id: i32,
+        rtt: Duration,
+    },
+    /// Connection is lost, new subscription would be created after `delay`
+    Disconnected {
+        error: GeyserGrpcClientError,
+        last_slot: Option<u64>,
+        delay: Duration,
+    },
+    /// Subscription is re-created with the last request, updates after `last_slot`
+    /// and before the first received update can be missed. If the request has `from_slot`,
+    /// it is replaced with `last_slot` (or removed if nothing was received)
+    Reconnected {
+        attempts: usize,
+        last_slot: Option<u64>,
+    },
+}
+
+/// Send time of pings waiting for pong
+#[derive(Debug, Default)]
+struct PingsInFlight {
+    pings: VecDeque<(i32, Instant)>,
+}
+
+impl PingsInFlight {
+    fn push(&mut self, id: i32) {
+        if self.pings.len() == PINGS_IN_FLIGHT_MAX {
+            self.pings.pop_front();
+        }
+        self.pings.push_back((id, Instant::now()));
+    }
+
+    fn get_rtt(&mut self, id: i32) -> Option<Duration> {
+        let index = self.pings.iter().position(|(ping_id, _)| *ping_id == id)?;
+        let (_id, ts) = self.pings.remove(index)?;
+        Some(ts.elapsed())
+    }
+}
+
+struct ManagedSubscriptionConnection {
+    subscribe_tx: mpsc::UnboundedSender<SubscribeRequest>,
+    stream: Streaming<SubscribeUpdate>,
+    ping_timer: Option<Interval>,
+    pings: PingsInFlight,
+    last_received: Instant,
+}
+
+impl ManagedSubscriptionConnection {
+    fn send_ping(&mut self, id: i32) {
+        self.pings.push(id);
+        let _ = self.subscribe_tx.unbounded_send(SubscribeRequest {
+            ping: Some(SubscribeRequestPing { id }),
+            ..Default::default()
+        });
+    }
+}
+
+pub(crate) struct ManagedSubscription<F, C> {
+    geyser: GeyserClient<GeyserGrpcChannel<F, C>>,
+    config: ManagedSubscriptionConfig,
+    request: SubscribeRequest,
+    requests_rx: mpsc::UnboundedReceiver<SubscribeRequest>,
+    requests_open: bool,
+    connection: Option<ManagedSubscriptionConnection>,
+    /// Message received with the current connection, backoff is reset on disconnect
+    received: bool,
+    ping_id: i32,
+    reconnect_delay: Option<Duration>,
+    attempts: usize,
+    last_slot: Option<u64>,
+    /// Subscription was created at least once, `from_slot` is not used as is after it
+    connected: bool,
+    finished: bool,
+}
+
+impl<F, C> ManagedSubscription<F, C>
+where
+    F: Interceptor + Send + 'static,
+    C: GeyserGrpcTransport,
+{
+    pub(crate) fn create(
+        geyser: GeyserClient<GeyserGrpcChannel<F, C>>,
+        request: SubscribeRequest,
+        config: ManagedSubscriptionConfig,
+    ) -> (
+        mpsc::UnboundedSender<SubscribeRequest>,
+        impl Stream<Item = GeyserGrpcClientResult<ManagedSubscriptionUpdate>>,
+    ) {
+        let (requests_tx, requests_rx) = mpsc::unbounded();
+        let subscription = Self::new(geyser, request, config, requests_rx);
+        let stream = stream::unfold(subscription, |mut subscription| async move {
+            let item = subscription.next().await?;
+            Some((item, subscription))
+        });
+        (requests_tx, stream)
+    }
+
+    fn new(
+        geyser: GeyserClient<GeyserGrpcChannel<F, C>>,
+        request: SubscribeRequest,
+        mut config: ManagedSubscriptionConfig,
+        requests_rx: mpsc::UnboundedReceiver<SubscribeRequest>,
+    ) -> Self {
+        config.backoff.reset();
+        Self {
+            geyser,
+            config,
+            request,
+            requests_rx,
+            requests_open: true,
+            connection: None,
+            received: false,
+            ping_id: 0,
+            reconnect_delay: None,
+            attempts: 0,
+            last_slot: None,
+            connected: false,
+            finished: false,
+        }
+    }
+
+    async fn next(&mut self) -> Option<GeyserGrpcClientResult<ManagedSubscriptionUpdate>> {
+        loop {
+            if self.finished {
+                return None;
+            }
+
+            let Some(connection) = self.connection.as_mut() else {
+                if let Some(delay) = self.reconnect_delay.take() {
+                    tokio::time::sleep(delay).await;
+                }
+                // filters can be updated while we were disconnected
+                while self.requests_open {
+                    match self.requests_rx.try_next() {
+                        Ok(Some(request)) => self.update_request(request),
+                        Ok(None) => self.requests_open = false,
+                        Err(_empty) => break,
+                    }
+                }
+
+                let (subscribe_tx, subscribe_rx) = mpsc::unbounded();
+                let _ = subscribe_tx.unbounded_send(self.subscribe_request());
+                match self.geyser.subscribe(subscribe_rx).await {
+                    Ok(response) => {
+                        self.connected = true;
+                        self.connection = Some(ManagedSubscriptionConnection {
+                            subscribe_tx,
+                            stream: response.into_inner(),
+                            ping_timer: self
+                                .config
+                                .ping_interval
+                                .map(|period| interval_at(Instant::now() + period, period)),
+                            pings: PingsInFlight::default(),
+                            last_received: Instant::now(),
+                        });
+                        self.received = false;
+                        if self.attempts > 0 {
+                            let attempts = self.attempts;
+                            self.attempts = 0;
+                            return Some(Ok(ManagedSubscriptionUpdate::Reconnected {
+                                attempts,
+                                last_slot: self.last_slot,
+                            }));
+                        }
+                        continue;
+                    }
+                    Err(status) => return Some(self.disconnect(status.into())),
+                }
+            };
+
+            let idle_deadline = self
+                .config
+                .idle_timeout
+                .map(|timeout| connection.last_received + timeout);
+
+            // biased: messages already buffered in the stream are consumed before
+            // the idle deadline is checked, so a late poll does not disconnect
+            tokio::select! {
+                biased;
+
+                request = self.requests_rx.next(), if self.requests_open => match request {
+                    Some(request) => {
+                        let _ = connection.subscribe_tx.unbounded_send(request.clone());
+                        self.update_request(request);
+                    }
+                    None => self.requests_open = false,
+                },
+                _ = async {
+                    match connection.ping_timer.as_mut() {
+                        Some(timer) => timer.tick().await,
+                        None => pending().await,
+                    }
+                } => {
+                    self.ping_id = self.ping_id.wrapping_add(1);
+                    connection.send_ping(self.ping_id);
+                }
+                message = connection.stream.next() => match message {
+                    Some(Ok(update)) => {
+                        connection.last_received = Instant::now();
+                        self.received = true;
+                        match &update.update_oneof {
+                            Some(UpdateOneof::Ping(_)) if self.config.reply_ping => {
+                                self.ping_id = self.ping_id.wrapping_add(1);
+                                connection.send_ping(self.ping_id);
+                            }
+                            Some(UpdateOneof::Pong(SubscribeUpdatePong { id })) => {
+                                if let Some(rtt) = connection.pings.get_rtt(*id) {
+                                    let id = *id;
+                                    return Some(Ok(ManagedSubscriptionUpdate::Pong { id, rtt }));
+                                }
+                            }
+                            _ => {}
+                        }
+                        if let Some(slot) = get_update_slot(&update) {
+                            self.last_slot =
+                                Some(self.last_slot.map_or(slot, |last| last.max(slot)));
+                        }
+                        return Some(Ok(ManagedSubscriptionUpdate::Update(update)));
+                    }
+                    Some(Err(status)) => return Some(self.disconnect(status.into())),
+                    None => {
+                        let status = Status::unavailable("subscription stream finished");
+                        return Some(self.disconnect(status.into()));
+                    }
+                },
+                () = async {
+                    match idle_deadline {
+                        Some(deadline) => sleep_until(deadline).await,
+                        None => pending().await,
+                    }
+                } => {
+                    let timeout = self.config.idle_timeout.unwrap_or_default();
+                    return Some(self.disconnect(GeyserGrpcClientError::IdleTimeout(timeout)));
+                }
+            }
+        }
+    }
+
+    /// Stored slots are removed on the server with time, so after reconnect `from_slot`
+    /// of the original request can be not available anymore (`InvalidArgument` is permanent)
+    fn subscribe_request(&self) -> SubscribeRequest {
+        let mut request = self.request.clone();
+        if self.connected && request.from_slot.is_some() {
+            request.from_slot = self.last_slot;
+        }
+        request
+    }
+
+    fn update_request(&mut self, request: SubscribeRequest) {
+        // ping requests do not replace filters on the server
+        if request.ping.is_none() {
+            self.request = request;
+        }
+    }
+
+    #[allow(clippy::result_large_err)]
+    fn disconnect(
+        &mut self,
+        error: GeyserGrpcClientError,
+    ) -> GeyserGrpcClientResult<ManagedSubscriptionUpdate> {
+        // `max_elapsed_time` of the backoff is counted from the last healthy connection
+        self.connection = None;
+        if std::mem::take(&mut self.received) {
+            self.config.backoff.reset();
+        }
+
+        let permanent = matches!(
+            &error,
+            GeyserGrpcClientError::TonicStatus(status) if matches!(
+                status.code(),
+                Code::InvalidArgument | Code::Unauthenticated | Code::PermissionDenied
+            )
+        );
+        match self.config.backoff.next_backoff() {
+            Some(delay) if !permanent => {
+                self.attempts += 1;
+                self.reconnect_delay = Some(delay);
+                Ok(ManagedSubscriptionUpdate::Disconnected {
+                    error,
+                    last_slot: self.last_slot,
+                    delay,
+                })
+            }
+            _ => {
+                self.finished = true;
+                Err(error)
+            }
+        }
+    }
+}
+
+const fn get_update_slot(update: &SubscribeUpdate) -> Option<u64> {
+    match &update.update_oneof {
+        Some(UpdateOneof::Account(msg)) => Some(msg.slot),
+        Some(UpdateOneof::Slot(msg)) => Some(msg.slot),
+        Some(UpdateOneof::Transaction(msg)) => Some(msg.slot),
+        Some(UpdateOneof::TransactionStatus(msg)) => Some(msg.slot),
+        Some(UpdateOneof::Block(msg)) => Some(msg.slot),
+        Some(UpdateOneof::BlockMeta(msg)) => Some(msg.slot),
+        Some(UpdateOneof::Entry(msg)) => Some(msg.slot),
+        Some(UpdateOneof::Gap(msg)) => Some(msg.to_slot),
+        Some(UpdateOneof::Rollback(msg)) => Some(msg.slot),
+        Some(UpdateOneof::BlockFragment(msg)) => Some(msg.slot),
+        Some(UpdateOneof::Ping(_)) | Some(UpdateOneof::Pong(_)) | None => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            ManagedSubscription, ManagedSubscriptionConfig, ManagedSubscriptionUpdate,
+            PingsInFlight, PINGS_IN_FLIGHT_MAX,
+        },
+        crate::{GeyserGrpcClient, GeyserGrpcClientError},
+        backoff::ExponentialBackoff,
+        futures::channel::mpsc,
+        std::time::Duration,
+        tonic::Status,
+        yellowstone_grpc_proto::prelude::SubscribeRequest,
+    };
+
+    #[tokio::test(start_paused = true)]
+    async fn test_pings_in_flight() {
+        let mut pings = PingsInFlight::default();
+        pings.push(1);
+        tokio::time::advance(Duration::from_millis(20)).await;
+        pings.push(2);
+        tokio::time::advance(Duration::from_millis(5)).await;
+
+        assert_eq!(pings.get_rtt(2), Some(Duration::from_millis(5)));
+        assert_eq!(pings.get_rtt(2), None);
+        assert_eq!(pings.get_rtt(3), None);
+        assert_eq!(pings.get_rtt(1), Some(Duration::from_millis(25)));
+
+        for id in 0..=PINGS_IN_FLIGHT_MAX as i32 {
+            pings.push(id);
+        }
+        assert_eq!(pings.get_rtt(0), None);
+        assert!(pings.get_rtt(PINGS_IN_FLIGHT_MAX as i32).is_some());
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_after_max_elapsed_time() {
+        let client = GeyserGrpcClient::build_from_static("http://127.0.0.1:1")
+            .connect_lazy()
+            .unwrap();
+        let config = ManagedSubscriptionConfig {
+            backoff: ExponentialBackoff {
+                initial_interval: Duration::from_millis(1),
+                max_elapsed_time: Some(Duration::from_millis(20)),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let (_requests_tx, requests_rx) = mpsc::unbounded();
+        let mut subscription = ManagedSubscription::new(
+            client.geyser,
+            SubscribeRequest::default(),
+            config,
+            requests_rx,
+        );
+        let error = || GeyserGrpcClientError::TonicStatus(Status::unavailable("test"));
+
+        // healthy connection lived longer than `max_elapsed_time`
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        subscription.received = true;
+        assert!(matches!(
+            subscription.disconnect(error()),
+            Ok(ManagedSubscriptionUpdate::Disconnected { .. })
+        ));
+
+        // nothing received after reconnect, attempts are limited by `max_elapsed_time`
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert!(subscription.disconnect(error()).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_from_slot() {
+        let client = GeyserGrpcClient::build_from_static("http://127.0.0.1:1")
+            .connect_lazy()
+            .unwrap();
+        let (_requests_tx, requests_rx) = mpsc::unbounded();
+        let request = SubscribeRequest {
+            from_slot: Some(10),
+            ..Default::default()
+        };
+        let mut subscription = ManagedSubscription::new(
+            client.geyser,
+            request,
+            ManagedSubscriptionConfig::default(),
+            requests_rx,
+        );
+
+        // original `from_slot` is used until the first subscription
+        assert_eq!(subscription.subscribe_request().from_slot, Some(10));
+
+        // replaced with the last received slot or removed on reconnect
+        subscription.connected = true;
+        assert_eq!(subscription.subscribe_request().from_slot, None);
+        subscription.last_slot = Some(20);
+        assert_eq!(subscription.subscribe_request().from_slot, Some(20));
+        assert_eq!(subscription.request.from_slot, Some(10));
+
+        // request without `from_slot` is not changed
+        subscription.update_request(SubscribeRequest::default());
+        assert_eq!(subscription.subscribe_request().from_slot, None);
+    }
+}
@@ -1,4 +1,8 @@
-use {cargo_lock::Lockfile, std::collections::HashSet};
+use {
+    cargo_lock::Lockfile,
+    std::collections::HashSet,
+    tonic_build::manual::{Builder, Method, Service},
+};
 
 fn main() -> anyhow::Result<()> {
     vergen::Emitter::default()
@@ -23,9 +27,142 @@ fn main() -> anyhow::Result<()> {
         get_pkg_version(&lockfile, "yellowstone-grpc-proto")
     );
 
+    generate_grpc_geyser()
+}
+
+const GEYSER_PROTO: &str = "../yellowstone-grpc-proto/proto/geyser.proto";
+
+// Unary methods with `ProstCodec`: name, route name, input type and output type
+const UNARY_METHODS: &[(&str, &str, &str, &str)] = &[
+    ("ping", "Ping", "PingRequest", "PongResponse"),
+    (
+        "get_latest_blockhash",
+        "GetLatestBlockhash",
+        "GetLatestBlockhashRequest",
+        "GetLatestBlockhashResponse",
+    ),
+    (
+        "get_block_height",
+        "GetBlockHeight",
+        "GetBlockHeightRequest",
+        "GetBlockHeightResponse",
+    ),
+    ("get_slot", "GetSlot", "GetSlotRequest", "GetSlotResponse"),
+    (
+        "is_blockhash_valid",
+        "IsBlockhashValid",
+        "IsBlockhashValidRequest",
+        "IsBlockhashValidResponse",
+    ),
+    (
+        "get_version",
+        "GetVersion",
+        "GetVersionRequest",
+        "GetVersionResponse",
+    ),
+    (
+        "get_account_info",
+        "GetAccountInfo",
+        "GetAccountInfoRequest",
+        "GetAccountInfoResponse",
+    ),
+    (
+        "get_multiple_accounts",
+        "GetMultipleAccounts",
+        "GetMultipleAccountsRequest",
+        "GetMultipleAccountsResponse",
+    ),
+];
+
+// Same service as in `yellowstone-grpc-proto` but with custom codec for `Subscribe`
+fn generate_grpc_geyser() -> anyhow::Result<()> {
+    check_proto_methods()?;
+
+    let mut geyser_service = Service::builder().name("Geyser").package("geyser").method(
+        Method::builder()
+            .name("subscribe")
+            .route_name("Subscribe")
+            .input_type("yellowstone_grpc_proto::prelude::SubscribeRequest")
+            .output_type("crate::codec::FilteredUpdate")
+            .codec_path("crate::codec::SubscribeCodec")
+            .client_streaming()
+            .server_streaming()
+            .build(),
+    );
+    for (name, route_name, input_type, output_type) in UNARY_METHODS {
+        geyser_service =
+            geyser_service.method(unary_method(name, route_name, input_type, output_type));
+    }
+
+    Builder::new()
+        .build_client(false)
+        .compile(&[geyser_service.build()]);
+
     Ok(())
 }
 
+// Service is declared by hand, so methods should be the same as in the proto file
+fn check_proto_methods() -> anyhow::Result<()> {
+    println!("cargo:rerun-if-changed={GEYSER_PROTO}");
+    let proto = std::fs::read_to_string(GEYSER_PROTO)?;
+    let mut proto_methods = proto
+        .lines()
+        .map(str::trim)
+        .skip_while(|line| *line != "service Geyser {")
+        .skip(1)
+        .take_while(|line| *line != "}")
+        .filter_map(|line| line.strip_prefix("rpc "))
+        .map(|rpc| parse_rpc(rpc).ok_or_else(|| anyhow::anyhow!("failed to parse rpc: {rpc}")))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    proto_methods.sort();
+
+    let mut methods = UNARY_METHODS
+        .iter()
+        .map(|(_name, route_name, input_type, output_type)| {
+            (
+                route_name.to_string(),
+                input_type.to_string(),
+                output_type.to_string(),
+            )
+        })
+        .chain(std::iter::once((
+            "Subscribe".to_owned(),
+            "stream SubscribeRequest".to_owned(),
+            "stream SubscribeUpdate".to_owned(),
+        )))
+        .collect::<Vec<_>>();
+    methods.sort();
+
+    anyhow::ensure!(
+        methods == proto_methods,
+        "Geyser methods in build.rs do not match {GEYSER_PROTO}: {methods:?} vs {proto_methods:?}"
+    );
+    Ok(())
+}
+
+// Parses `Name(Input) returns (Output) {}` into route name, input type and output type
+fn parse_rpc(rpc: &str) -> Option<(String, String, String)> {
+    let (route_name, rest) = rpc.split_once('(')?;
+    let (input_type, rest) = rest.split_once(')')?;
+    let (_, rest) = rest.split_once('(')?;
+    let (output_type, _) = rest.split_once(')')?;
+    Some((
+        route_name.trim().to_owned(),
+        input_type.trim().to_owned(),
+        output_type.trim().to_owned(),
+    ))
+}
+
+fn unary_method(name: &str, route_name: &str, input_type: &str, output_type: &str) -> Method {
+    Method::builder()
+        .name(name)
+        .route_name(route_name)
+        .input_type(format!("yellowstone_grpc_proto::prelude::{input_type}"))
+        .output_type(format!("yellowstone_grpc_proto::prelude::{output_type}"))
+        .codec_path("tonic::codec::ProstCodec")
+        .build()
+}
+
 fn get_pkg_version(lockfile: &Lockfile, pkg_name: &str) -> String {
     lockfile
         .packages
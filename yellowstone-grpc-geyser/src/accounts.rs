@@ -0,0 +1,541 @@
+use {
+    crate::{
+        config::ConfigGrpcAccountsCache,
+        filters::{Filter, FilterAccountsDataSlice},
+        grpc::{Message, MessageAccount},
+    },
+    log::error,
+    solana_sdk::pubkey::Pubkey,
+    std::{
+        collections::{BTreeMap, HashMap, HashSet},
+        sync::Arc,
+    },
+    tokio::sync::{mpsc, oneshot, RwLock, Semaphore},
+    tonic::Status,
+    yellowstone_grpc_proto::prelude::{
+        CommitmentLevel, SubscribeRequestAccountsDataSlice, SubscribeUpdateAccount,
+    },
+};
+
+#[derive(Debug)]
+pub enum AccountsStorageMessage {
+    Message(Arc<Message>),
+    /// Notify when all previous messages are applied
+    Barrier(oneshot::Sender<()>),
+}
+
+#[derive(Debug)]
+struct AccountVersion(Arc<Message>);
+
+impl AccountVersion {
+    fn get(&self) -> &MessageAccount {
+        match self.0.as_ref() {
+            Message::Account(msg) => msg,
+            msg => unreachable!("invalid message in AccountVersion: {}", msg.kind()),
+        }
+    }
+
+    fn slot(&self) -> u64 {
+        self.get().slot
+    }
+}
+
+#[derive(Debug)]
+struct SlotInfo {
+    status: CommitmentLevel,
+    parent: Option<u64>,
+    /// Accounts updated in the slot
+    pubkeys: HashSet<Pubkey>,
+}
+
+impl Default for SlotInfo {
+    fn default() -> Self {
+        Self {
+            status: CommitmentLevel::Processed,
+            parent: None,
+            pubkeys: HashSet::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct AccountsStorageInner {
+    /// Versions ordered by slot, the first one is the latest finalized (if known)
+    accounts: HashMap<Pubkey, Vec<AccountVersion>>,
+    /// Not finalized slots, accounts in slots missed here are finalized
+    slots: BTreeMap<u64, SlotInfo>,
+    processed: Option<u64>,
+    confirmed: Option<u64>,
+    finalized: Option<u64>,
+}
+
+impl AccountsStorageInner {
+    fn update_account(&mut self, config: &ConfigGrpcAccountsCache, message: Arc<Message>) {
+        let Message::Account(msg) = message.as_ref() else {
+            return;
+        };
+        let pubkey = msg.account.pubkey;
+        if !config.is_match(&pubkey, &msg.account.owner) && !self.accounts.contains_key(&pubkey) {
+            return;
+        }
+
+        let slot = msg.slot;
+        let write_version = msg.account.write_version;
+        let is_startup = msg.is_startup;
+        let versions = self.accounts.entry(pubkey).or_default();
+        // Keep only the latest write in every slot
+        match versions.binary_search_by_key(&(slot, write_version), |version| {
+            (version.slot(), version.get().account.write_version)
+        }) {
+            Ok(_index) => return,
+            Err(index) if index < versions.len() && versions[index].slot() == slot => return,
+            Err(index) if index > 0 && versions[index - 1].slot() == slot => {
+                versions[index - 1] = AccountVersion(message);
+            }
+            Err(index) => versions.insert(index, AccountVersion(message)),
+        }
+
+        if !is_startup && !matches!(self.finalized, Some(finalized) if slot <= finalized) {
+            self.slots.entry(slot).or_default().pubkeys.insert(pubkey);
+        }
+
+        Self::compact(&self.slots, versions);
+    }
+
+    fn update_slot(&mut self, slot: u64, parent: Option<u64>, status: CommitmentLevel) {
+        let finalized = self.finalized;
+        match status {
+            CommitmentLevel::Processed => &mut self.processed,
+            CommitmentLevel::Confirmed => &mut self.confirmed,
+            CommitmentLevel::Finalized => &mut self.finalized,
+        }
+        .replace(slot);
+        if matches!(finalized, Some(finalized) if slot <= finalized) {
+            return;
+        }
+
+        let info = self.slots.entry(slot).or_default();
+        if parent.is_some() {
+            info.parent = parent;
+        }
+
+        // Confirmed / finalized slot confirms / finalizes known parents
+        let mut current = Some(slot);
+        while let Some(slot) = current {
+            match self.slots.get_mut(&slot) {
+                Some(info) if info.status < status => {
+                    info.status = status;
+                    current = info.parent;
+                }
+                _ => break,
+            }
+        }
+
+        if status == CommitmentLevel::Finalized {
+            let slots = self.slots.split_off(&(slot + 1));
+            let mut pubkeys = HashSet::new();
+            let mut dead_slots = HashSet::new();
+            for (slot, info) in std::mem::replace(&mut self.slots, slots) {
+                if info.status != CommitmentLevel::Finalized {
+                    dead_slots.insert(slot);
+                }
+                pubkeys.extend(info.pubkeys);
+            }
+
+            for pubkey in pubkeys {
+                if let Some(versions) = self.accounts.get_mut(&pubkey) {
+                    versions.retain(|version| !dead_slots.contains(&version.slot()));
+                    Self::compact(&self.slots, versions);
+                    if versions.is_empty() {
+                        self.accounts.remove(&pubkey);
+                    }
+                }
+            }
+        }
+    }
+
+    // Remove versions older than the latest finalized
+    fn compact(slots: &BTreeMap<u64, SlotInfo>, versions: &mut Vec<AccountVersion>) {
+        if let Some(index) = versions
+            .iter()
+            .rposition(|version| !slots.contains_key(&version.slot()))
+        {
+            versions.drain(..index);
+        }
+    }
+
+    fn get_version<'a>(
+        &self,
+        versions: &'a [AccountVersion],
+        commitment: CommitmentLevel,
+    ) -> Option<&'a AccountVersion> {
+        versions.iter().rev().find(|version| {
+            let status = self
+                .slots
+                .get(&version.slot())
+                .map(|info| info.status)
+                .unwrap_or(CommitmentLevel::Finalized);
+            status >= commitment
+        })
+    }
+
+    fn get(&self, pubkey: &Pubkey, commitment: CommitmentLevel) -> Option<&MessageAccount> {
+        self.accounts
+            .get(pubkey)
+            .and_then(|versions| self.get_version(versions, commitment))
+            .map(AccountVersion::get)
+    }
+}
+
+/// Accounts matched by `accounts_cache` config, used by `GetAccountInfo` and `GetMultipleAccounts`
+#[derive(Debug)]
+pub struct AccountsStorage {
+    config: Arc<ConfigGrpcAccountsCache>,
+    read_sem: Semaphore,
+    inner: Arc<RwLock<AccountsStorageInner>>,
+    barrier_tx: mpsc::WeakUnboundedSender<AccountsStorageMessage>,
+}
+
+impl AccountsStorage {
+    pub fn new(
+        config: ConfigGrpcAccountsCache,
+        unary_concurrency_limit: usize,
+    ) -> (Self, mpsc::UnboundedSender<AccountsStorageMessage>) {
+        let config = Arc::new(config);
+        let inner = Arc::new(RwLock::new(AccountsStorageInner::default()));
+        let (tx, mut rx) = mpsc::unbounded_channel();
+
+        let storage = Arc::clone(&inner);
+        let storage_config = Arc::clone(&config);
+        tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                let message = match message {
+                    AccountsStorageMessage::Message(message) => message,
+                    AccountsStorageMessage::Barrier(tx) => {
+                        let _ = tx.send(());
+                        continue;
+                    }
+                };
+
+                let mut storage = storage.write().await;
+                match message.as_ref() {
+                    Message::Slot(msg) => {
+                        if let Some(commitment) = msg.status.commitment() {
+                            storage.update_slot(msg.slot, msg.parent, commitment);
+                        }
+                    }
+                    Message::Account(_) => storage.update_account(&storage_config, message),
+                    msg => error!("invalid message in AccountsStorage: {msg:?}"),
+                }
+            }
+        });
+
+        (
+            Self {
+                config,
+                read_sem: Semaphore::new(unary_concurrency_limit),
+                inner,
+                barrier_tx: tx.downgrade(),
+            },
+            tx,
+        )
+    }
+
+    /// Latest versions of accounts for the commitment level. Forks are not resolved for
+    /// `processed`: version from the highest slot is returned even if the slot is not on the fork
+    /// of the latest processed slot, versions from dead forks are removed only on finalization
+    pub async fn get_accounts(
+        &self,
+        pubkeys: &[String],
+        commitment: CommitmentLevel,
+        data_slice: &[SubscribeRequestAccountsDataSlice],
+    ) -> Result<(u64, Vec<Option<SubscribeUpdateAccount>>), Status> {
+        let max = self.config.get_multiple_accounts_max;
+        if pubkeys.len() > max {
+            return Err(Status::invalid_argument(format!(
+                "max number of pubkeys reached, only {max} allowed"
+            )));
+        }
+        let pubkeys = pubkeys
+            .iter()
+            .map(|pubkey| {
+                pubkey
+                    .parse::<Pubkey>()
+                    .map_err(|_error| Status::invalid_argument(format!("invalid pubkey {pubkey}")))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        let data_slice = FilterAccountsDataSlice::create(data_slice)
+            .map_err(|error| Status::invalid_argument(error.to_string()))?;
+
+        let _permit = self.read_sem.acquire().await;
+        let storage = self.inner.read().await;
+
+        let slot = match commitment {
+            CommitmentLevel::Processed => storage.processed,
+            CommitmentLevel::Confirmed => storage.confirmed,
+            CommitmentLevel::Finalized => storage.finalized,
+        }
+        .ok_or_else(|| {
+            Status::unavailable(format!(
+                "accounts cache is not ready, {commitment:?} slot is not received yet"
+            ))
+        })?;
+
+        let accounts = pubkeys
+            .iter()
+            .map(|pubkey| {
+                storage
+                    .get(pubkey, commitment)
+                    .map(|account| account.to_proto(&data_slice))
+            })
+            .collect();
+        Ok((slot, accounts))
+    }
+
+    /// Accounts for filters with `send_initial_state`, all updates received by the plugin
+    /// before the call are applied, so the client would not miss any update after it
+    pub async fn get_initial_state(&self, filter: &Filter) -> Result<Vec<Arc<Message>>, Status> {
+        filter
+            .check_initial_state(&self.config)
+            .map_err(|error| Status::invalid_argument(error.to_string()))?;
+
+        let (tx, rx) = oneshot::channel();
+        self.barrier_tx
+            .upgrade()
+            .and_then(|barrier_tx| barrier_tx.send(AccountsStorageMessage::Barrier(tx)).ok())
+            .ok_or_else(|| Status::internal("accounts cache is closed"))?;
+        rx.await
+            .map_err(|_error| Status::internal("accounts cache is closed"))?;
+
+        let commitment = filter.get_commitment_level();
+        let _permit = self.read_sem.acquire().await;
+        let storage = self.inner.read().await;
+        Ok(storage
+            .accounts
+            .values()
+            .filter_map(|versions| storage.get_version(versions, commitment))
+            .filter(|version| filter.is_initial_state_match(version.get()))
+            .map(|version| Arc::clone(&version.0))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{AccountsStorage, AccountsStorageInner, AccountsStorageMessage},
+        crate::{
+            codec::EncodedCache,
+            config::{ConfigGrpcAccountsCache, ConfigGrpcFilters},
+            filters::Filter,
+            grpc::{Message, MessageAccount, MessageAccountInfo, MessageSlot},
+        },
+        solana_sdk::pubkey::Pubkey,
+        std::{
+            collections::{HashMap, HashSet},
+            sync::Arc,
+        },
+        tokio::sync::oneshot,
+        tonic::Code,
+        yellowstone_grpc_proto::prelude::{
+            CommitmentLevel, SlotStatus, SubscribeRequest, SubscribeRequestFilterAccounts,
+        },
+    };
+
+    fn create_config(owner: Pubkey) -> ConfigGrpcAccountsCache {
+        ConfigGrpcAccountsCache {
+            owners: HashSet::from([owner]),
+            pubkeys: HashSet::new(),
+            seed_from_snapshot: false,
+            get_multiple_accounts_max: 100,
+        }
+    }
+
+    fn create_message(pubkey: Pubkey, owner: Pubkey, slot: u64, lamports: u64) -> Arc<Message> {
+        Arc::new(Message::Account(MessageAccount {
+            account: MessageAccountInfo {
+                pubkey,
+                lamports,
+                owner,
+                executable: false,
+                rent_epoch: 0,
+                data: vec![],
+                write_version: slot,
+                txn_signature: None,
+            },
+            slot,
+            is_startup: false,
+            encoded: EncodedCache::default(),
+        }))
+    }
+
+    fn get_lamports(
+        storage: &AccountsStorageInner,
+        pubkey: &Pubkey,
+        commitment: CommitmentLevel,
+    ) -> Option<u64> {
+        storage
+            .get(pubkey, commitment)
+            .map(|msg| msg.account.lamports)
+    }
+
+    #[tokio::test]
+    async fn test_accounts_storage_unavailable() {
+        let owner = Pubkey::new_unique();
+        let pubkeys = [Pubkey::new_unique().to_string()];
+        let (storage, tx) = AccountsStorage::new(create_config(owner), 1);
+
+        let error = storage
+            .get_accounts(&pubkeys, CommitmentLevel::Processed, &[])
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), Code::Unavailable);
+
+        let (barrier_tx, barrier_rx) = oneshot::channel();
+        for message in [
+            AccountsStorageMessage::Message(Arc::new(Message::Slot(MessageSlot {
+                slot: 1,
+                parent: Some(0),
+                status: SlotStatus::SlotProcessed,
+            }))),
+            AccountsStorageMessage::Barrier(barrier_tx),
+        ] {
+            tx.send(message).unwrap();
+        }
+        barrier_rx.await.unwrap();
+
+        let (slot, accounts) = storage
+            .get_accounts(&pubkeys, CommitmentLevel::Processed, &[])
+            .await
+            .unwrap();
+        assert_eq!(slot, 1);
+        assert!(accounts[0].is_none());
+        let error = storage
+            .get_accounts(&pubkeys, CommitmentLevel::Confirmed, &[])
+            .await
+            .unwrap_err();
+        assert_eq!(error.code(), Code::Unavailable);
+    }
+
+    #[test]
+    fn test_accounts_commitment() {
+        let owner = Pubkey::new_unique();
+        let pubkey = Pubkey::new_unique();
+        let config = create_config(owner);
+        let mut storage = AccountsStorageInner::default();
+
+        // not matched account is ignored
+        storage.update_account(
+            &config,
+            create_message(Pubkey::new_unique(), Pubkey::new_unique(), 1, 1),
+        );
+        assert!(storage.accounts.is_empty());
+
+        storage.update_account(&config, create_message(pubkey, owner, 1, 10));
+        storage.update_slot(1, Some(0), CommitmentLevel::Processed);
+        storage.update_account(&config, create_message(pubkey, owner, 2, 20));
+        storage.update_slot(2, Some(1), CommitmentLevel::Processed);
+        assert_eq!(
+            get_lamports(&storage, &pubkey, CommitmentLevel::Processed),
+            Some(20)
+        );
+        assert_eq!(
+            get_lamports(&storage, &pubkey, CommitmentLevel::Confirmed),
+            None
+        );
+
+        // confirmed slot 2 confirms parent slot 1
+        storage.update_slot(2, None, CommitmentLevel::Confirmed);
+        assert_eq!(
+            get_lamports(&storage, &pubkey, CommitmentLevel::Confirmed),
+            Some(20)
+        );
+        storage.update_slot(1, Some(0), CommitmentLevel::Finalized);
+        assert_eq!(
+            get_lamports(&storage, &pubkey, CommitmentLevel::Finalized),
+            Some(10)
+        );
+        assert_eq!(storage.accounts[&pubkey].len(), 2);
+
+        // account owner changed, but updates are still tracked
+        storage.update_account(&config, create_message(pubkey, Pubkey::new_unique(), 3, 30));
+        storage.update_slot(3, Some(2), CommitmentLevel::Processed);
+        storage.update_slot(3, Some(2), CommitmentLevel::Finalized);
+        assert_eq!(
+            get_lamports(&storage, &pubkey, CommitmentLevel::Finalized),
+            Some(30)
+        );
+        assert_eq!(storage.accounts[&pubkey].len(), 1);
+        assert!(storage.slots.is_empty());
+    }
+
+    #[test]
+    fn test_accounts_dead_fork() {
+        let owner = Pubkey::new_unique();
+        let pubkey = Pubkey::new_unique();
+        let config = create_config(owner);
+        let mut storage = AccountsStorageInner::default();
+
+        storage.update_account(&config, create_message(pubkey, owner, 1, 10));
+        storage.update_slot(1, Some(0), CommitmentLevel::Processed);
+        // fork: slot 2 and slot 3 are children of slot 1
+        storage.update_account(&config, create_message(pubkey, owner, 2, 20));
+        storage.update_slot(2, Some(1), CommitmentLevel::Processed);
+        storage.update_account(&config, create_message(pubkey, owner, 3, 30));
+        storage.update_slot(3, Some(1), CommitmentLevel::Processed);
+        storage.update_slot(4, Some(3), CommitmentLevel::Processed);
+
+        storage.update_slot(4, Some(3), CommitmentLevel::Finalized);
+        assert_eq!(
+            get_lamports(&storage, &pubkey, CommitmentLevel::Processed),
+            Some(30)
+        );
+        assert_eq!(
+            get_lamports(&storage, &pubkey, CommitmentLevel::Finalized),
+            Some(30)
+        );
+        assert_eq!(storage.accounts[&pubkey].len(), 1);
+        assert!(storage.slots.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_accounts_initial_state() {
+        let owner = Pubkey::new_unique();
+        let owner2 = Pubkey::new_unique();
+        let pubkey = Pubkey::new_unique();
+        let mut config = create_config(owner);
+        config.owners.insert(owner2);
+        let (storage, tx) = AccountsStorage::new(config, 1);
+        tx.send(AccountsStorageMessage::Message(create_message(
+            pubkey, owner, 1, 10,
+        )))
+        .unwrap();
+        tx.send(AccountsStorageMessage::Message(create_message(
+            Pubkey::new_unique(),
+            owner2,
+            1,
+            20,
+        )))
+        .unwrap();
+
+        // only accounts matched by filters with `send_initial_state` are returned
+        let request = SubscribeRequest {
+            accounts: HashMap::from([(
+                "state".to_owned(),
+                SubscribeRequestFilterAccounts {
+                    owner: vec![owner.to_string()],
+                    send_initial_state: true,
+                    ..Default::default()
+                },
+            )]),
+            ..Default::default()
+        };
+        let filter = Filter::new(&request, &ConfigGrpcFilters::default()).unwrap();
+        let messages = storage.get_initial_state(&filter).await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert!(matches!(
+            messages[0].as_ref(),
+            Message::Account(message) if message.account.pubkey == pubkey
+        ));
+    }
+}
@@ -0,0 +1,195 @@
+use {
+    crate::filters::FilterAccountsDataSlice,
+    std::sync::Mutex,
+    tonic::{
+        codec::{Codec, EncodeBuf, Encoder, ProstCodec},
+        Status,
+    },
+    yellowstone_grpc_proto::{
+        convert_to::TransactionMetaOmit,
+        prelude::{subscribe_update::UpdateOneof, SubscribeRequest, SubscribeUpdate},
+        prost::{
+            bytes::{BufMut, Bytes},
+            encoding::string,
+            Message as _,
+        },
+    },
+};
+
+/// Update ready to be sent to the client
+#[derive(Debug, Clone)]
+#[allow(clippy::large_enum_variant)]
+pub enum FilteredUpdate {
+    Update(SubscribeUpdate),
+    /// `update_oneof` already encoded to protobuf and shared between clients
+    Encoded {
+        filters: Vec<String>,
+        update: Bytes,
+    },
+}
+
+impl From<SubscribeUpdate> for FilteredUpdate {
+    fn from(update: SubscribeUpdate) -> Self {
+        Self::Update(update)
+    }
+}
+
+impl FilteredUpdate {
+    // `filters` is the first field of `SubscribeUpdate` and all other fields are in `update_oneof`,
+    // so concatenation produce exactly the same bytes as encoding of the whole message
+    pub fn encode(&self, buf: &mut impl BufMut) {
+        match self {
+            Self::Update(update) => update
+                .encode(buf)
+                .expect("Message only errors if not enough space"),
+            Self::Encoded { filters, update } => {
+                for filter in filters {
+                    string::encode(1, filter, buf);
+                }
+                buf.put_slice(update);
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum EncodedCacheKey {
+    Account(Vec<FilterAccountsDataSlice>),
+    Transaction(TransactionMetaOmit),
+    TransactionStatus,
+}
+
+/// Encoded `update_oneof` for every combination of data slices / omitted fields requested by clients
+///
+/// `Clone` returns an empty cache: copy of the message can be modified (e.g. `is_startup` for
+/// the initial state), so encoded updates of the original message can not be reused
+#[derive(Debug, Default)]
+pub struct EncodedCache {
+    // number of different keys is low, so linear search is fine
+    inner: Mutex<Vec<(EncodedCacheKey, Bytes)>>,
+}
+
+impl Clone for EncodedCache {
+    fn clone(&self) -> Self {
+        Self::default()
+    }
+}
+
+impl EncodedCache {
+    pub fn get_or_encode(
+        &self,
+        key: EncodedCacheKey,
+        update_oneof: impl FnOnce() -> UpdateOneof,
+    ) -> Bytes {
+        let mut inner = self.inner.lock().expect("poisoned mutex");
+        if let Some((_key, update)) = inner.iter().find(|(cached, _update)| cached == &key) {
+            return update.clone();
+        }
+
+        let update: Bytes = SubscribeUpdate {
+            filters: vec![],
+            update_oneof: Some(update_oneof()),
+        }
+        .encode_to_vec()
+        .into();
+        inner.push((key, update.clone()));
+        update
+    }
+}
+
+/// Codec for `Subscribe` method, writes pre-encoded updates as is
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SubscribeCodec;
+
+impl Codec for SubscribeCodec {
+    type Encode = FilteredUpdate;
+    type Decode = SubscribeRequest;
+
+    type Encoder = SubscribeEncoder;
+    type Decoder = <ProstCodec<SubscribeUpdate, SubscribeRequest> as Codec>::Decoder;
+
+    fn encoder(&mut self) -> Self::Encoder {
+        SubscribeEncoder
+    }
+
+    fn decoder(&mut self) -> Self::Decoder {
+        ProstCodec::<SubscribeUpdate, SubscribeRequest>::default().decoder()
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SubscribeEncoder;
+
+impl Encoder for SubscribeEncoder {
+    type Item = FilteredUpdate;
+    type Error = Status;
+
+    fn encode(&mut self, item: Self::Item, buf: &mut EncodeBuf<'_>) -> Result<(), Self::Error> {
+        item.encode(buf);
+        Ok(())
+    }
+}
+
+// `Geyser` service with `SubscribeCodec` for `Subscribe`, generated in `build.rs`
+mod generated {
+    #![allow(
+        clippy::clone_on_ref_ptr,
+        clippy::default_constructed_unit_structs,
+        clippy::missing_const_for_fn
+    )]
+
+    include!(concat!(env!("OUT_DIR"), "/geyser.Geyser.rs"));
+}
+
+pub use generated::geyser_server;
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{EncodedCache, EncodedCacheKey, FilteredUpdate},
+        yellowstone_grpc_proto::{
+            prelude::{subscribe_update::UpdateOneof, SubscribeUpdate, SubscribeUpdateSlot},
+            prost::Message,
+        },
+    };
+
+    #[test]
+    fn test_encoded_same_as_prost() {
+        let update_oneof = UpdateOneof::Slot(SubscribeUpdateSlot {
+            slot: 42,
+            parent: Some(41),
+            status: 1,
+        });
+        let filters = vec!["a".to_owned(), "bc".to_owned()];
+
+        let cache = EncodedCache::default();
+        let update =
+            cache.get_or_encode(EncodedCacheKey::TransactionStatus, || update_oneof.clone());
+        let cached = cache.get_or_encode(EncodedCacheKey::TransactionStatus, || {
+            unreachable!("should be cached")
+        });
+        assert_eq!(update, cached);
+
+        let mut encoded = false;
+        cache
+            .clone()
+            .get_or_encode(EncodedCacheKey::TransactionStatus, || {
+                encoded = true;
+                update_oneof.clone()
+            });
+        assert!(encoded, "cloned cache should be empty");
+
+        let mut buf = vec![];
+        FilteredUpdate::Encoded {
+            filters: filters.clone(),
+            update,
+        }
+        .encode(&mut buf);
+        let expected = SubscribeUpdate {
+            filters,
+            update_oneof: Some(update_oneof),
+        };
+        assert_eq!(buf, expected.encode_to_vec());
+        assert_eq!(SubscribeUpdate::decode(buf.as_slice()).unwrap(), expected);
+    }
+}
@@ -1,12 +1,22 @@
 use {
+    crate::tenant::DEFAULT_TENANT,
     agave_geyser_plugin_interface::geyser_plugin_interface::{
         GeyserPluginError, Result as PluginResult,
     },
     serde::{de, Deserialize, Deserializer},
     solana_sdk::pubkey::Pubkey,
-    std::{collections::HashSet, fs::read_to_string, net::SocketAddr, path::Path},
+    std::{
+        collections::HashSet,
+        fs::read_to_string,
+        net::SocketAddr,
+        path::{Path, PathBuf},
+    },
     tokio::sync::Semaphore,
     tonic::codec::CompressionEncoding,
+    yellowstone_grpc_proto::{
+        compression::CompressionEncoding as SendCompressionEncoding,
+        prelude::{BackpressurePolicy, CommitmentLevel},
+    },
 };
 
 #[derive(Debug, Clone, Deserialize)]
@@ -24,13 +34,27 @@ pub struct Config {
     /// Collect client filters, processed slot and make it available on prometheus port `/debug_clients`
     #[serde(default)]
     pub debug_clients_http: bool,
+    /// Check config file for changes with this interval (in milliseconds) and apply new
+    /// `grpc.filters`, `grpc.x_token`, `grpc.tenants` and per-connection settings without restart,
+    /// changes of `grpc.address`, `grpc.tls_config` and `grpc.listeners` are ignored with warning
+    #[serde(default, deserialize_with = "deserialize_usize_str_maybe")]
+    pub reload_interval_ms: Option<usize>,
 }
 
 impl Config {
     fn load_from_str(config: &str) -> PluginResult<Self> {
-        serde_json::from_str(config).map_err(|error| GeyserPluginError::ConfigFileReadError {
-            msg: error.to_string(),
-        })
+        let config: Self = serde_json::from_str(config).map_err(|error| {
+            GeyserPluginError::ConfigFileReadError {
+                msg: error.to_string(),
+            }
+        })?;
+        config
+            .grpc
+            .validate_tenants()
+            .map_err(|error| GeyserPluginError::ConfigFileReadError {
+                msg: error.to_string(),
+            })?;
+        Ok(config)
     }
 
     pub fn load_from_file<P: AsRef<Path>>(file: P) -> PluginResult<Self> {
@@ -68,6 +92,9 @@ pub struct ConfigGrpc {
     pub address: SocketAddr,
     /// TLS config
     pub tls_config: Option<ConfigGrpcServerTls>,
+    /// Additional listeners (without TLS) served with the same filters and auth
+    #[serde(default)]
+    pub listeners: Vec<ConfigGrpcListener>,
     /// Possible compression options
     #[serde(default)]
     pub compression: ConfigGrpcCompression,
@@ -79,6 +106,7 @@ pub struct ConfigGrpc {
     pub max_decoding_message_size: usize,
     /// Capacity of the channel used for accounts from snapshot,
     /// on reaching the limit Sender block validator startup.
+    /// Received accounts are kept in memory (up to `snapshot_bytes_max`) and sent to every client requested snapshot.
     #[serde(
         default = "ConfigGrpc::snapshot_plugin_channel_capacity_default",
         deserialize_with = "deserialize_usize_str_maybe"
@@ -90,12 +118,41 @@ pub struct ConfigGrpc {
         deserialize_with = "deserialize_usize_str"
     )]
     pub snapshot_client_channel_capacity: usize,
+    /// Time (in milliseconds) to keep accounts from snapshot after the end of startup,
+    /// accounts are kept while clients receive them, later requests with `snapshot` are rejected
+    #[serde(
+        default = "ConfigGrpc::snapshot_retention_ms_default",
+        deserialize_with = "deserialize_usize_str"
+    )]
+    pub snapshot_retention_ms: usize,
+    /// Max approximate size of accounts from snapshot kept in memory, on reaching the limit
+    /// all accounts are dropped and requests with `snapshot` are rejected
+    #[serde(
+        default = "ConfigGrpc::snapshot_bytes_max_default",
+        deserialize_with = "deserialize_usize_str"
+    )]
+    pub snapshot_bytes_max: usize,
+    /// Keep only matched accounts from snapshot, all accounts are kept if not set
+    #[serde(default)]
+    pub snapshot_accounts: Option<ConfigGrpcSnapshotAccounts>,
     /// Capacity of the channel per connection
     #[serde(
         default = "ConfigGrpc::channel_capacity_default",
         deserialize_with = "deserialize_usize_str"
     )]
     pub channel_capacity: usize,
+    /// Number of slots stored per commitment level for `from_slot` replay, 0 disables replay
+    #[serde(
+        default = "ConfigGrpc::replay_stored_slots_default",
+        deserialize_with = "deserialize_usize_str"
+    )]
+    pub replay_stored_slots: usize,
+    /// Action on slow clients, policy can be overridden with `backpressure` in `SubscribeRequest`
+    #[serde(default)]
+    pub backpressure: ConfigGrpcBackpressure,
+    /// Limits for messages buffered for block reconstruction
+    #[serde(default)]
+    pub block_reconstruction: ConfigGrpcBlockReconstruction,
     /// Concurrency limit for unary requests
     #[serde(
         default = "ConfigGrpc::unary_concurrency_limit_default",
@@ -105,11 +162,18 @@ pub struct ConfigGrpc {
     /// Enable/disable unary methods
     #[serde(default)]
     pub unary_disabled: bool,
+    /// Accounts cache for `GetAccountInfo` and `GetMultipleAccounts`, disabled if not set
+    #[serde(default)]
+    pub accounts_cache: Option<ConfigGrpcAccountsCache>,
     /// Limits for possible filters
     #[serde(default)]
     pub filters: ConfigGrpcFilters,
     /// x_token to enforce on connections
     pub x_token: Option<String>,
+    /// Tenants with own tokens and limits, other connections are checked with `x_token`
+    /// and rejected if `x_token` is not set
+    #[serde(default)]
+    pub tenants: Vec<ConfigGrpcTenant>,
 }
 
 impl ConfigGrpc {
@@ -125,42 +189,223 @@ impl ConfigGrpc {
         50_000_000
     }
 
+    const fn snapshot_retention_ms_default() -> usize {
+        300_000
+    }
+
+    const fn snapshot_bytes_max_default() -> usize {
+        16 * 1024 * 1024 * 1024
+    }
+
     const fn channel_capacity_default() -> usize {
         250_000
     }
 
+    const fn replay_stored_slots_default() -> usize {
+        0
+    }
+
     const fn unary_concurrency_limit_default() -> usize {
         Semaphore::MAX_PERMITS
     }
+
+    /// Tenant names, tokens and identities should be unique, every tenant should be reachable
+    fn validate_tenants(&self) -> anyhow::Result<()> {
+        let mut names = HashSet::new();
+        let mut x_tokens = HashSet::new();
+        let mut identities = HashSet::new();
+        for tenant in self.tenants.iter() {
+            anyhow::ensure!(
+                tenant.name != DEFAULT_TENANT,
+                "tenant name `{DEFAULT_TENANT}` is reserved"
+            );
+            anyhow::ensure!(
+                names.insert(tenant.name.as_str()),
+                "duplicate tenant name `{}`",
+                tenant.name
+            );
+            anyhow::ensure!(
+                tenant.x_token.is_some() || !tenant.identities.is_empty(),
+                "tenant `{}` should have `x_token` or `identities`",
+                tenant.name
+            );
+            if let Some(x_token) = &tenant.x_token {
+                anyhow::ensure!(
+                    x_tokens.insert(x_token.as_str()),
+                    "tenant `{}` has `x_token` of another tenant",
+                    tenant.name
+                );
+            }
+            for identity in tenant.identities.iter() {
+                anyhow::ensure!(
+                    identities.insert(identity.as_str()),
+                    "tenant `{}` has identity `{identity}` of another tenant",
+                    tenant.name
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Changed fields which are applied only on plugin start and ignored on reload
+    pub fn restart_required_fields(&self, new: &Self) -> Vec<&'static str> {
+        let mut fields = vec![];
+        macro_rules! check {
+            ($($field:ident),*) => {
+                $(
+                    if self.$field != new.$field {
+                        fields.push(stringify!($field));
+                    }
+                )*
+            };
+        }
+        check!(
+            address,
+            tls_config,
+            listeners,
+            compression,
+            max_decoding_message_size,
+            snapshot_plugin_channel_capacity,
+            snapshot_retention_ms,
+            snapshot_bytes_max,
+            snapshot_accounts,
+            channel_capacity,
+            replay_stored_slots,
+            block_reconstruction,
+            unary_concurrency_limit,
+            unary_disabled,
+            accounts_cache
+        );
+        fields
+    }
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigGrpcSnapshotAccounts {
+    /// Keep accounts owned by these programs
+    #[serde(default, deserialize_with = "deserialize_pubkey_set")]
+    pub owners: HashSet<Pubkey>,
+    /// Keep these accounts
+    #[serde(default, deserialize_with = "deserialize_pubkey_set")]
+    pub pubkeys: HashSet<Pubkey>,
+}
+
+impl ConfigGrpcSnapshotAccounts {
+    pub fn is_match(&self, pubkey: &Pubkey, owner: &Pubkey) -> bool {
+        self.pubkeys.contains(pubkey) || self.owners.contains(owner)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigGrpcAccountsCache {
+    /// Cache accounts owned by these programs
+    #[serde(default, deserialize_with = "deserialize_pubkey_set")]
+    pub owners: HashSet<Pubkey>,
+    /// Cache these accounts
+    #[serde(default, deserialize_with = "deserialize_pubkey_set")]
+    pub pubkeys: HashSet<Pubkey>,
+    /// Fill cache with matched accounts from the startup snapshot
+    #[serde(default)]
+    pub seed_from_snapshot: bool,
+    /// Max number of pubkeys in `GetMultipleAccounts` request
+    #[serde(
+        default = "ConfigGrpcAccountsCache::get_multiple_accounts_max_default",
+        deserialize_with = "deserialize_usize_str"
+    )]
+    pub get_multiple_accounts_max: usize,
+}
+
+impl ConfigGrpcAccountsCache {
+    const fn get_multiple_accounts_max_default() -> usize {
+        100
+    }
+
+    pub fn is_match(&self, pubkey: &Pubkey, owner: &Pubkey) -> bool {
+        self.pubkeys.contains(pubkey) || self.owners.contains(owner)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+#[serde(deny_unknown_fields, tag = "type", rename_all = "lowercase")]
+pub enum ConfigGrpcListener {
+    Unix {
+        path: PathBuf,
+        /// File permissions of the socket in octal form, e.g. "660"
+        #[serde(
+            default,
+            deserialize_with = "ConfigGrpcListener::deserialize_permissions"
+        )]
+        permissions: Option<u32>,
+    },
+}
+
+impl ConfigGrpcListener {
+    fn deserialize_permissions<'de, D>(deserializer: D) -> Result<Option<u32>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Option::<&str>::deserialize(deserializer)?
+            .map(|value| u32::from_str_radix(value, 8).map_err(de::Error::custom))
+            .transpose()
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
 #[serde(deny_unknown_fields)]
 pub struct ConfigGrpcServerTls {
     pub cert_path: String,
     pub key_path: String,
+    /// CA certificates for mutual TLS, clients without certificate signed by CA are rejected
+    #[serde(default)]
+    pub client_ca_path: Option<String>,
+    /// Interval (in milliseconds) to check `cert_path` and `key_path` for changes,
+    /// new certificate is used for new connections
+    #[serde(
+        default = "ConfigGrpcServerTls::reload_interval_ms_default",
+        deserialize_with = "deserialize_usize_str"
+    )]
+    pub reload_interval_ms: usize,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+impl ConfigGrpcServerTls {
+    const fn reload_interval_ms_default() -> usize {
+        60_000
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct ConfigGrpcCompression {
+    /// Encodings of requests from clients, only `gzip` is supported
+    /// (`zstd` is available only for sent messages)
     #[serde(
         deserialize_with = "ConfigGrpcCompression::deserialize_compression",
         default = "ConfigGrpcCompression::default_compression"
     )]
     pub accept: Vec<CompressionEncoding>,
+    /// Encodings in order of preference, first accepted by the client is used
     #[serde(
-        deserialize_with = "ConfigGrpcCompression::deserialize_compression",
-        default = "ConfigGrpcCompression::default_compression"
+        deserialize_with = "ConfigGrpcCompression::deserialize_send_compression",
+        default = "ConfigGrpcCompression::default_send_compression"
     )]
-    pub send: Vec<CompressionEncoding>,
+    pub send: Vec<SendCompressionEncoding>,
+    /// Compression level for gzip, from 0 to 9
+    #[serde(default = "ConfigGrpcCompression::gzip_level_default")]
+    pub gzip_level: u32,
+    /// Compression level for zstd, from 1 to 22
+    #[serde(default = "ConfigGrpcCompression::zstd_level_default")]
+    pub zstd_level: i32,
 }
 
 impl Default for ConfigGrpcCompression {
     fn default() -> Self {
         Self {
             accept: Self::default_compression(),
-            send: Self::default_compression(),
+            send: Self::default_send_compression(),
+            gzip_level: Self::gzip_level_default(),
+            zstd_level: Self::zstd_level_default(),
         }
     }
 }
@@ -176,6 +421,9 @@ impl ConfigGrpcCompression {
             .into_iter()
             .map(|value| match value {
                 "gzip" => Ok(CompressionEncoding::Gzip),
+                "zstd" => Err(de::Error::custom(
+                    "zstd is supported only in `send`, requests are accepted only with gzip",
+                )),
                 value => Err(de::Error::custom(format!(
                     "Unknown compression format: {value}"
                 ))),
@@ -186,6 +434,164 @@ impl ConfigGrpcCompression {
     fn default_compression() -> Vec<CompressionEncoding> {
         vec![CompressionEncoding::Gzip]
     }
+
+    fn deserialize_send_compression<'de, D>(
+        deserializer: D,
+    ) -> Result<Vec<SendCompressionEncoding>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<&str>::deserialize(deserializer)?
+            .into_iter()
+            .map(|value| value.parse().map_err(de::Error::custom))
+            .collect::<Result<_, _>>()
+    }
+
+    fn default_send_compression() -> Vec<SendCompressionEncoding> {
+        vec![SendCompressionEncoding::Gzip]
+    }
+
+    const fn gzip_level_default() -> u32 {
+        6
+    }
+
+    const fn zstd_level_default() -> i32 {
+        3
+    }
+}
+
+#[derive(Debug, Clone, Copy, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigGrpcBackpressure {
+    pub policy: ConfigGrpcBackpressurePolicy,
+    /// Max number of queued updates per client for `dropOldest` and `holdProcessed` policies.
+    /// Worst case memory per client is `queue_capacity` multiplied by the max update size
+    /// (account updates carry up to 10MiB of data), in addition to `channel_capacity`
+    #[serde(deserialize_with = "deserialize_usize_str")]
+    pub queue_capacity: usize,
+}
+
+impl Default for ConfigGrpcBackpressure {
+    fn default() -> Self {
+        Self {
+            policy: ConfigGrpcBackpressurePolicy::default(),
+            queue_capacity: 10_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigGrpcBlockReconstruction {
+    /// Max number of slots with buffered messages, the oldest slots are removed with messages
+    #[serde(deserialize_with = "deserialize_usize_str")]
+    pub slots_max: usize,
+    /// Max size of buffered messages per slot, block is not reconstructed after the limit,
+    /// but messages are still buffered for confirmed and finalized commitment levels
+    #[serde(deserialize_with = "deserialize_usize_str")]
+    pub slot_bytes_max: usize,
+}
+
+impl Default for ConfigGrpcBlockReconstruction {
+    fn default() -> Self {
+        Self {
+            slots_max: 512,
+            slot_bytes_max: 512 * 1024 * 1024,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigGrpcBackpressurePolicy {
+    /// Disconnect client on full channel
+    #[default]
+    Disconnect,
+    /// Drop the oldest queued updates and send `gap` update with skipped slots
+    DropOldest,
+    /// Queue updates and keep sending slot status, client is never disconnected:
+    /// on full queue the oldest accounts and transactions are dropped and `gap` update is sent.
+    /// Slot status is sent ahead of queued updates, so it can be received
+    /// before accounts and transactions of the slot
+    HoldProcessed,
+}
+
+impl From<ConfigGrpcBackpressurePolicy> for BackpressurePolicy {
+    fn from(policy: ConfigGrpcBackpressurePolicy) -> Self {
+        match policy {
+            ConfigGrpcBackpressurePolicy::Disconnect => Self::Disconnect,
+            ConfigGrpcBackpressurePolicy::DropOldest => Self::DropOldest,
+            ConfigGrpcBackpressurePolicy::HoldProcessed => Self::HoldProcessed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct ConfigGrpcTenant {
+    /// Tenant name used in logs and metrics
+    pub name: String,
+    /// Value of `x-token` header
+    #[serde(default)]
+    pub x_token: Option<String>,
+    /// Identities of clients authenticated with mutual TLS (certificate SAN or CN),
+    /// `x-token` is not required for these clients
+    #[serde(default)]
+    pub identities: Vec<String>,
+    /// Limits for filters, `grpc.filters` used if not set
+    #[serde(default)]
+    pub filters: Option<ConfigGrpcFilters>,
+    /// Max number of concurrent subscriptions
+    #[serde(
+        default = "ConfigGrpcTenant::subscriptions_max_default",
+        deserialize_with = "deserialize_usize_str"
+    )]
+    pub subscriptions_max: usize,
+    /// Max number of unary requests per second
+    #[serde(
+        default = "ConfigGrpcTenant::unary_rps_max_default",
+        deserialize_with = "deserialize_usize_str"
+    )]
+    pub unary_rps_max: usize,
+    /// Allowed commitment levels for subscriptions and unary requests
+    #[serde(default = "ConfigGrpcTenant::commitments_default")]
+    pub commitments: Vec<ConfigGrpcCommitment>,
+}
+
+impl ConfigGrpcTenant {
+    const fn subscriptions_max_default() -> usize {
+        usize::MAX
+    }
+
+    const fn unary_rps_max_default() -> usize {
+        usize::MAX
+    }
+
+    fn commitments_default() -> Vec<ConfigGrpcCommitment> {
+        vec![
+            ConfigGrpcCommitment::Processed,
+            ConfigGrpcCommitment::Confirmed,
+            ConfigGrpcCommitment::Finalized,
+        ]
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub enum ConfigGrpcCommitment {
+    Processed,
+    Confirmed,
+    Finalized,
+}
+
+impl From<ConfigGrpcCommitment> for CommitmentLevel {
+    fn from(commitment: ConfigGrpcCommitment) -> Self {
+        match commitment {
+            ConfigGrpcCommitment::Processed => Self::Processed,
+            ConfigGrpcCommitment::Confirmed => Self::Confirmed,
+            ConfigGrpcCommitment::Finalized => Self::Finalized,
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone, Deserialize)]
@@ -248,6 +654,12 @@ pub struct ConfigGrpcFiltersAccounts {
     pub owner_max: usize,
     #[serde(deserialize_with = "deserialize_pubkey_set")]
     pub owner_reject: HashSet<Pubkey>,
+    /// Max amount of data filters (`memcmp`, `datasize`, `lamports`, etc) per filter
+    #[serde(deserialize_with = "deserialize_usize_str")]
+    pub filters_max: usize,
+    /// Max amount of `lamports` comparators per filter
+    #[serde(deserialize_with = "deserialize_usize_str")]
+    pub lamports_max: usize,
 }
 
 impl Default for ConfigGrpcFiltersAccounts {
@@ -259,6 +671,8 @@ impl Default for ConfigGrpcFiltersAccounts {
             account_reject: HashSet::new(),
             owner_max: usize::MAX,
             owner_reject: HashSet::new(),
+            filters_max: 4,
+            lamports_max: usize::MAX,
         }
     }
 }
@@ -290,6 +704,19 @@ pub struct ConfigGrpcFiltersTransactions {
     pub account_exclude_max: usize,
     #[serde(deserialize_with = "deserialize_usize_str")]
     pub account_required_max: usize,
+    #[serde(deserialize_with = "deserialize_usize_str")]
+    pub program_invoked_max: usize,
+    #[serde(deserialize_with = "deserialize_pubkey_set")]
+    pub program_invoked_reject: HashSet<Pubkey>,
+    /// Max amount of instruction data discriminators per program
+    #[serde(deserialize_with = "deserialize_usize_str")]
+    pub program_invoked_discriminators_max: usize,
+    /// Max amount of `log_contains` and `log_prefix` patterns
+    #[serde(deserialize_with = "deserialize_usize_str")]
+    pub log_patterns_max: usize,
+    /// Max length of `log_contains` and `log_prefix` pattern
+    #[serde(deserialize_with = "deserialize_usize_str")]
+    pub log_pattern_length_max: usize,
 }
 
 impl Default for ConfigGrpcFiltersTransactions {
@@ -301,6 +728,11 @@ impl Default for ConfigGrpcFiltersTransactions {
             account_include_reject: HashSet::new(),
             account_exclude_max: usize::MAX,
             account_required_max: usize::MAX,
+            program_invoked_max: usize::MAX,
+            program_invoked_reject: HashSet::new(),
+            program_invoked_discriminators_max: usize::MAX,
+            log_patterns_max: usize::MAX,
+            log_pattern_length_max: usize::MAX,
         }
     }
 }
@@ -435,3 +867,95 @@ where
         })
         .collect::<Result<_, _>>()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+
+    fn load_with_tenants(tenants: &str) -> Result<Config, String> {
+        Config::load_from_str(&format!(
+            r#"{{"libpath": "", "grpc": {{"address": "127.0.0.1:10000", "tenants": {tenants}}}}}"#
+        ))
+        .map_err(|error| error.to_string())
+    }
+
+    #[test]
+    fn test_tenants_validation() {
+        let config = load_with_tenants(
+            r#"[{"name": "a", "x_token": "a"}, {"name": "b", "identities": ["b", "c"]}]"#,
+        )
+        .unwrap();
+        assert_eq!(config.grpc.tenants.len(), 2);
+
+        for (tenants, error) in [
+            (
+                r#"[{"name": "default", "x_token": "a"}]"#,
+                "tenant name `default` is reserved",
+            ),
+            (
+                r#"[{"name": "a", "x_token": "a"}, {"name": "a", "x_token": "b"}]"#,
+                "duplicate tenant name `a`",
+            ),
+            (
+                r#"[{"name": "a", "x_token": "a"}, {"name": "b", "x_token": "a"}]"#,
+                "tenant `b` has `x_token` of another tenant",
+            ),
+            (
+                r#"[{"name": "a", "identities": ["a"]}, {"name": "b", "identities": ["a"]}]"#,
+                "tenant `b` has identity `a` of another tenant",
+            ),
+            (
+                r#"[{"name": "a", "identities": []}]"#,
+                "tenant `a` should have `x_token` or `identities`",
+            ),
+        ] {
+            let result = load_with_tenants(tenants);
+            assert!(
+                matches!(&result, Err(message) if message.contains(error)),
+                "{tenants}: {result:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_compression_accept() {
+        let load = |compression: &str| {
+            Config::load_from_str(&format!(
+                r#"{{"libpath": "", "grpc": {{"address": "127.0.0.1:10000", "compression": {compression}}}}}"#
+            ))
+            .map_err(|error| error.to_string())
+        };
+
+        let config = load(r#"{"accept": ["gzip"], "send": ["zstd"]}"#).unwrap();
+        assert_eq!(config.grpc.compression.accept.len(), 1);
+        let error = load(r#"{"accept": ["zstd"]}"#).unwrap_err();
+        assert!(
+            error.contains("requests are accepted only with gzip"),
+            "{error}"
+        );
+    }
+
+    #[test]
+    fn test_restart_required_fields() {
+        let load = |grpc: &str| {
+            Config::load_from_str(&format!(
+                r#"{{"libpath": "", "grpc": {{"address": "127.0.0.1:10000"{grpc}}}}}"#
+            ))
+            .unwrap()
+            .grpc
+        };
+
+        let initial = load("");
+        assert!(initial
+            .restart_required_fields(&load(
+                r#", "x_token": "a", "snapshot_client_channel_capacity": 1"#
+            ))
+            .is_empty());
+        assert_eq!(
+            initial.restart_required_fields(&load(
+                r#", "compression": {"send": []}, "channel_capacity": 1, "unary_disabled": true, "accounts_cache": {}"#
+            )),
+            ["compression", "channel_capacity", "unary_disabled", "accounts_cache"]
+        );
+    }
+}
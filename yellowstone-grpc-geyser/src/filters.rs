@@ -1,30 +1,35 @@
 use {
     crate::{
+        codec::FilteredUpdate,
         config::{
-            ConfigGrpcFilters, ConfigGrpcFiltersAccounts, ConfigGrpcFiltersBlocks,
-            ConfigGrpcFiltersBlocksMeta, ConfigGrpcFiltersEntry, ConfigGrpcFiltersSlots,
-            ConfigGrpcFiltersTransactions,
+            ConfigGrpcAccountsCache, ConfigGrpcFilters, ConfigGrpcFiltersAccounts,
+            ConfigGrpcFiltersBlocks, ConfigGrpcFiltersBlocksMeta, ConfigGrpcFiltersEntry,
+            ConfigGrpcFiltersSlots, ConfigGrpcFiltersTransactions,
         },
         grpc::{
-            Message, MessageAccount, MessageBlock, MessageBlockMeta, MessageEntry, MessageRef,
-            MessageSlot, MessageTransaction,
+            Message, MessageAccount, MessageAccountInfo, MessageBlock, MessageBlockFragment,
+            MessageBlockFragmentRef, MessageBlockMeta, MessageBlockRef, MessageEntry, MessageRef,
+            MessageSlot, MessageTransaction, MessageTransactionInfo,
         },
     },
-    base64::{engine::general_purpose::STANDARD as base64_engine, Engine},
     solana_sdk::{pubkey::Pubkey, signature::Signature},
     spl_token_2022::{generic_token_account::GenericTokenAccount, state::Account as TokenAccount},
     std::{
         collections::{HashMap, HashSet},
         str::FromStr,
     },
-    yellowstone_grpc_proto::prelude::{
-        subscribe_request_filter_accounts_filter::Filter as AccountsFilterDataOneof,
-        subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpOneof,
-        subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
-        SubscribeRequestAccountsDataSlice, SubscribeRequestFilterAccounts,
-        SubscribeRequestFilterAccountsFilter, SubscribeRequestFilterBlocks,
-        SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterEntry, SubscribeRequestFilterSlots,
-        SubscribeRequestFilterTransactions, SubscribeUpdate, SubscribeUpdatePong,
+    yellowstone_grpc_proto::{
+        convert_to::TransactionMetaOmit,
+        filter::{validate_data_slices, validate_transactions_filter, AccountsFilterData},
+        prelude::{
+            subscribe_request_filter_accounts_filter_lamports::Cmp as AccountsFilterLamportsCmp,
+            subscribe_update::UpdateOneof, BackpressurePolicy, CommitmentLevel, SubscribeRequest,
+            SubscribeRequestAccountsDataSlice, SubscribeRequestFilterAccounts,
+            SubscribeRequestFilterAccountsFilter, SubscribeRequestFilterBlocks,
+            SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterEntry,
+            SubscribeRequestFilterSlots, SubscribeRequestFilterTransactions, SubscribeUpdate,
+            SubscribeUpdatePong,
+        },
     },
 };
 
@@ -40,13 +45,17 @@ pub struct Filter {
     commitment: CommitmentLevel,
     accounts_data_slice: Vec<FilterAccountsDataSlice>,
     ping: Option<i32>,
+    from_slot: Option<u64>,
+    backpressure: Option<BackpressurePolicy>,
+    snapshot: bool,
+    rollback: bool,
 }
 
 impl Filter {
     pub fn new(config: &SubscribeRequest, limit: &ConfigGrpcFilters) -> anyhow::Result<Self> {
         Ok(Self {
             accounts: FilterAccounts::new(&config.accounts, &limit.accounts)?,
-            slots: FilterSlots::new(&config.slots, &limit.slots)?,
+            slots: FilterSlots::new(&config.slots, &limit.slots, config.commitment)?,
             transactions: FilterTransactions::new(
                 &config.transactions,
                 &limit.transactions,
@@ -63,6 +72,10 @@ impl Filter {
             commitment: Self::decode_commitment(config.commitment)?,
             accounts_data_slice: FilterAccountsDataSlice::create(&config.accounts_data_slice)?,
             ping: config.ping.as_ref().map(|msg| msg.id),
+            from_slot: config.from_slot,
+            backpressure: Self::decode_backpressure(config.backpressure)?,
+            snapshot: config.snapshot,
+            rollback: config.rollback,
         })
     }
 
@@ -73,6 +86,18 @@ impl Filter {
         })
     }
 
+    fn decode_backpressure(
+        backpressure: Option<i32>,
+    ) -> anyhow::Result<Option<BackpressurePolicy>> {
+        backpressure
+            .map(|backpressure| {
+                BackpressurePolicy::try_from(backpressure).map_err(|_error| {
+                    anyhow::anyhow!("failed to create BackpressurePolicy from {backpressure:?}")
+                })
+            })
+            .transpose()
+    }
+
     fn decode_pubkeys<'a>(
         pubkeys: &'a [String],
         limit: &'a HashSet<Pubkey>,
@@ -140,6 +165,7 @@ impl Filter {
             ),
             Message::Entry(message) => self.entry.get_filters(message),
             Message::Block(message) => self.blocks.get_filters(message),
+            Message::BlockFragment(message) => self.blocks.get_fragment_filters(message),
             Message::BlockMeta(message) => self.blocks_meta.get_filters(message),
         }
     }
@@ -148,22 +174,95 @@ impl Filter {
         &'a self,
         message: &'a Message,
         commitment: Option<CommitmentLevel>,
-    ) -> Box<dyn Iterator<Item = SubscribeUpdate> + Send + 'a> {
+    ) -> Box<dyn Iterator<Item = FilteredUpdate> + Send + 'a> {
         Box::new(
             self.get_filters(message, commitment)
                 .filter_map(|(filters, message)| {
                     if filters.is_empty() {
                         None
                     } else {
-                        Some(SubscribeUpdate {
-                            filters,
-                            update_oneof: Some(message.to_proto(&self.accounts_data_slice)),
-                        })
+                        Some(message.to_filtered_update(filters, &self.accounts_data_slice))
                     }
                 }),
         )
     }
 
+    pub const fn get_from_slot(&self) -> Option<u64> {
+        self.from_slot
+    }
+
+    pub const fn get_backpressure(&self) -> Option<BackpressurePolicy> {
+        self.backpressure
+    }
+
+    pub const fn get_snapshot(&self) -> bool {
+        self.snapshot
+    }
+
+    /// Rollback notifications are sent only on processed commitment
+    pub const fn get_rollback(&self) -> bool {
+        self.rollback && matches!(self.commitment, CommitmentLevel::Processed)
+    }
+
+    /// Block fragments are built by the geyser loop only if at least one filter requests them
+    pub fn has_blocks_fragments(&self) -> bool {
+        self.blocks.filters.values().any(|inner| inner.fragments)
+    }
+
+    pub fn has_initial_state(&self) -> bool {
+        !self.accounts.initial_state.is_empty()
+    }
+
+    /// Initial state is sent again only if filters with `send_initial_state`
+    /// or commitment level are changed
+    pub fn is_initial_state_changed(&self, prev: &Self) -> bool {
+        self.commitment != prev.commitment
+            || self.accounts.initial_state != prev.accounts.initial_state
+    }
+
+    pub fn is_initial_state_match(&self, message: &MessageAccount) -> bool {
+        !self.accounts.get_initial_state_filters(message).is_empty()
+    }
+
+    /// Initial state is served from the accounts cache,
+    /// so owners (or accounts if owners are not set) should be cached
+    pub fn check_initial_state(
+        &self,
+        accounts_cache: &ConfigGrpcAccountsCache,
+    ) -> anyhow::Result<()> {
+        for (name, state) in self.accounts.initial_state.iter() {
+            let is_cached = if state.owner.is_empty() {
+                !state.account.is_empty()
+                    && state
+                        .account
+                        .iter()
+                        .all(|pubkey| accounts_cache.pubkeys.contains(pubkey))
+            } else {
+                state
+                    .owner
+                    .iter()
+                    .all(|owner| accounts_cache.owners.contains(owner))
+            };
+            anyhow::ensure!(
+                is_cached,
+                "initial state for filter {name} is not available, accounts are not cached"
+            );
+        }
+        Ok(())
+    }
+
+    /// Account update with `is_startup` flag for filters with `send_initial_state`
+    pub fn get_initial_state_update(&self, message: &MessageAccount) -> Option<FilteredUpdate> {
+        let filters = self.accounts.get_initial_state_filters(message);
+        (!filters.is_empty()).then(|| {
+            let message = MessageAccount {
+                is_startup: true,
+                ..message.clone()
+            };
+            MessageRef::Account(&message).to_filtered_update(filters, &self.accounts_data_slice)
+        })
+    }
+
     pub fn get_pong_msg(&self) -> Option<SubscribeUpdate> {
         self.ping.map(|id| SubscribeUpdate {
             filters: vec![],
@@ -179,6 +278,14 @@ struct FilterAccounts {
     account_required: HashSet<String>,
     owner: HashMap<Pubkey, HashSet<String>>,
     owner_required: HashSet<String>,
+    initial_state: HashMap<String, FilterAccountsInitialState>,
+}
+
+#[derive(Debug, Default, Clone, PartialEq)]
+struct FilterAccountsInitialState {
+    account: Vec<Pubkey>,
+    owner: Vec<Pubkey>,
+    filters: Vec<SubscribeRequestFilterAccountsFilter>,
 }
 
 impl FilterAccounts {
@@ -211,8 +318,24 @@ impl FilterAccounts {
                 Filter::decode_pubkeys(&filter.owner, &limit.owner_reject),
             )?;
 
-            this.filters
-                .push((name.clone(), FilterAccountsData::new(&filter.filters)?));
+            this.filters.push((
+                name.clone(),
+                FilterAccountsData::new(&filter.filters, limit)?,
+            ));
+
+            if filter.send_initial_state {
+                this.initial_state.insert(
+                    name.clone(),
+                    FilterAccountsInitialState {
+                        account: Filter::decode_pubkeys_into_vec(
+                            &filter.account,
+                            &limit.account_reject,
+                        )?,
+                        owner: Filter::decode_pubkeys_into_vec(&filter.owner, &limit.owner_reject)?,
+                        filters: filter.filters.clone(),
+                    },
+                );
+            }
         }
         Ok(this)
     }
@@ -243,12 +366,22 @@ impl FilterAccounts {
         let mut filter = FilterAccountsMatch::new(self);
         filter.match_account(&message.account.pubkey);
         filter.match_owner(&message.account.owner);
-        filter.match_data(&message.account.data);
+        filter.match_data(&message.account);
         Box::new(std::iter::once((
             filter.get_filters(),
             MessageRef::Account(message),
         )))
     }
+
+    fn get_initial_state_filters(&self, message: &MessageAccount) -> Vec<String> {
+        let mut filter = FilterAccountsMatch::new(self);
+        filter.match_account(&message.account.pubkey);
+        filter.match_owner(&message.account.owner);
+        filter.match_data(&message.account);
+        let mut filters = filter.get_filters();
+        filters.retain(|name| self.initial_state.contains_key(name));
+        filters
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -256,66 +389,45 @@ struct FilterAccountsData {
     memcmp: Vec<(usize, Vec<u8>)>,
     datasize: Option<usize>,
     token_account_state: bool,
+    lamports: Vec<FilterAccountsLamports>,
+    executable: Option<bool>,
 }
 
 impl FilterAccountsData {
-    fn new(filters: &[SubscribeRequestFilterAccountsFilter]) -> anyhow::Result<Self> {
-        const MAX_FILTERS: usize = 4;
-        const MAX_DATA_SIZE: usize = 128;
-        const MAX_DATA_BASE58_SIZE: usize = 175;
-        const MAX_DATA_BASE64_SIZE: usize = 172;
-
-        anyhow::ensure!(
-            filters.len() <= MAX_FILTERS,
-            "Too many filters provided; max {MAX_FILTERS}"
-        );
-
-        let mut this = Self::default();
-        for filter in filters {
-            match &filter.filter {
-                Some(AccountsFilterDataOneof::Memcmp(memcmp)) => {
-                    let data = match &memcmp.data {
-                        Some(AccountsFilterMemcmpOneof::Bytes(data)) => data.clone(),
-                        Some(AccountsFilterMemcmpOneof::Base58(data)) => {
-                            anyhow::ensure!(data.len() <= MAX_DATA_BASE58_SIZE, "data too large");
-                            bs58::decode(data)
-                                .into_vec()
-                                .map_err(|_| anyhow::anyhow!("invalid base58"))?
-                        }
-                        Some(AccountsFilterMemcmpOneof::Base64(data)) => {
-                            anyhow::ensure!(data.len() <= MAX_DATA_BASE64_SIZE, "data too large");
-                            base64_engine
-                                .decode(data)
-                                .map_err(|_| anyhow::anyhow!("invalid base64"))?
-                        }
-                        None => anyhow::bail!("data for memcmp should be defined"),
-                    };
-                    anyhow::ensure!(data.len() <= MAX_DATA_SIZE, "data too large");
-                    this.memcmp.push((memcmp.offset as usize, data));
-                }
-                Some(AccountsFilterDataOneof::Datasize(datasize)) => {
-                    anyhow::ensure!(
-                        this.datasize.replace(*datasize as usize).is_none(),
-                        "datasize used more than once",
-                    );
-                }
-                Some(AccountsFilterDataOneof::TokenAccountState(value)) => {
-                    anyhow::ensure!(value, "token_account_state only allowed to be true");
-                    this.token_account_state = true;
-                }
-                None => {
-                    anyhow::bail!("filter should be defined");
-                }
-            }
-        }
-        Ok(this)
+    fn new(
+        filters: &[SubscribeRequestFilterAccountsFilter],
+        limit: &ConfigGrpcFiltersAccounts,
+    ) -> anyhow::Result<Self> {
+        let data = AccountsFilterData::new(filters, limit.filters_max, limit.lamports_max)?;
+        Ok(Self {
+            memcmp: data.memcmp,
+            datasize: data.datasize,
+            token_account_state: data.token_account_state,
+            lamports: data.lamports.iter().map(Into::into).collect(),
+            executable: data.executable,
+        })
     }
 
     fn is_empty(&self) -> bool {
-        self.memcmp.is_empty() && self.datasize.is_none() && !self.token_account_state
+        self.memcmp.is_empty()
+            && self.datasize.is_none()
+            && !self.token_account_state
+            && self.lamports.is_empty()
+            && self.executable.is_none()
     }
 
-    fn is_match(&self, data: &[u8]) -> bool {
+    fn is_match(&self, account: &MessageAccountInfo) -> bool {
+        if matches!(self.executable, Some(executable) if account.executable != executable) {
+            return false;
+        }
+        if !self
+            .lamports
+            .iter()
+            .all(|lamports| lamports.is_match(account.lamports))
+        {
+            return false;
+        }
+        let data = account.data.as_slice();
         if matches!(self.datasize, Some(datasize) if data.len() != datasize) {
             return false;
         }
@@ -335,6 +447,36 @@ impl FilterAccountsData {
     }
 }
 
+#[derive(Debug, Clone, Copy)]
+enum FilterAccountsLamports {
+    Eq(u64),
+    Ne(u64),
+    Lt(u64),
+    Gt(u64),
+}
+
+impl From<&AccountsFilterLamportsCmp> for FilterAccountsLamports {
+    fn from(cmp: &AccountsFilterLamportsCmp) -> Self {
+        match cmp {
+            AccountsFilterLamportsCmp::Eq(value) => Self::Eq(*value),
+            AccountsFilterLamportsCmp::Ne(value) => Self::Ne(*value),
+            AccountsFilterLamportsCmp::Lt(value) => Self::Lt(*value),
+            AccountsFilterLamportsCmp::Gt(value) => Self::Gt(*value),
+        }
+    }
+}
+
+impl FilterAccountsLamports {
+    const fn is_match(self, lamports: u64) -> bool {
+        match self {
+            Self::Eq(value) => lamports == value,
+            Self::Ne(value) => lamports != value,
+            Self::Lt(value) => lamports < value,
+            Self::Gt(value) => lamports > value,
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct FilterAccountsMatch<'a> {
     filter: &'a FilterAccounts,
@@ -369,9 +511,9 @@ impl<'a> FilterAccountsMatch<'a> {
         Self::extend(&mut self.owner, &self.filter.owner, pubkey)
     }
 
-    pub fn match_data(&mut self, data: &[u8]) {
+    pub fn match_data(&mut self, account: &MessageAccountInfo) {
         for (name, filter) in self.filter.filters.iter() {
-            if filter.is_match(data) {
+            if filter.is_match(account) {
                 self.data.insert(name);
             }
         }
@@ -405,12 +547,14 @@ impl<'a> FilterAccountsMatch<'a> {
 #[derive(Debug, Default, Clone, Copy)]
 struct FilterSlotsInner {
     filter_by_commitment: bool,
+    include_dead: bool,
 }
 
 impl FilterSlotsInner {
     fn new(filter: &SubscribeRequestFilterSlots) -> Self {
         Self {
             filter_by_commitment: filter.filter_by_commitment.unwrap_or_default(),
+            include_dead: filter.include_dead.unwrap_or_default(),
         }
     }
 }
@@ -424,8 +568,18 @@ impl FilterSlots {
     fn new(
         configs: &HashMap<String, SubscribeRequestFilterSlots>,
         limit: &ConfigGrpcFiltersSlots,
+        commitment: Option<i32>,
     ) -> anyhow::Result<Self> {
         ConfigGrpcFilters::check_max(configs.len(), limit.max)?;
+        // dead and abandoned slots are detected on finalized slot and sent only on processed
+        let is_processed = Filter::decode_commitment(commitment)? == CommitmentLevel::Processed;
+        anyhow::ensure!(
+            is_processed
+                || !configs
+                    .values()
+                    .any(|filter| filter.include_dead.unwrap_or_default()),
+            "`include_dead` is supported only with processed commitment"
+        );
 
         Ok(Self {
             filters: configs
@@ -444,11 +598,11 @@ impl FilterSlots {
             self.filters
                 .iter()
                 .filter_map(|(name, inner)| {
-                    if !inner.filter_by_commitment || commitment == Some(message.status) {
-                        Some(name.clone())
-                    } else {
-                        None
-                    }
+                    let is_match = match message.status.commitment() {
+                        Some(status) => !inner.filter_by_commitment || commitment == Some(status),
+                        None => inner.include_dead,
+                    };
+                    is_match.then(|| name.clone())
                 })
                 .collect(),
             MessageRef::Slot(message),
@@ -470,6 +624,51 @@ pub struct FilterTransactionsInner {
     account_include: Vec<Pubkey>,
     account_exclude: Vec<Pubkey>,
     account_required: Vec<Pubkey>,
+    program_invoked: HashMap<Pubkey, Vec<Vec<u8>>>,
+    log_contains: Vec<String>,
+    log_prefix: Vec<String>,
+    omit: TransactionMetaOmit,
+}
+
+impl FilterTransactionsInner {
+    fn is_log_match(&self, transaction: &MessageTransactionInfo) -> bool {
+        transaction.meta.log_messages.iter().flatten().any(|log| {
+            self.log_contains
+                .iter()
+                .any(|pattern| log.contains(pattern.as_str()))
+                || self
+                    .log_prefix
+                    .iter()
+                    .any(|pattern| log.starts_with(pattern.as_str()))
+        })
+    }
+
+    fn is_program_invoked(&self, transaction: &MessageTransactionInfo) -> bool {
+        let message = transaction.transaction.message();
+        let account_keys = message.account_keys();
+        let inner_instructions = transaction
+            .meta
+            .inner_instructions
+            .iter()
+            .flatten()
+            .flat_map(|inner| inner.instructions.iter().map(|ix| &ix.instruction));
+
+        message
+            .instructions()
+            .iter()
+            .chain(inner_instructions)
+            .any(|ix| {
+                match account_keys
+                    .get(ix.program_id_index as usize)
+                    .and_then(|program_id| self.program_invoked.get(program_id))
+                {
+                    Some(discriminators) => discriminators
+                        .iter()
+                        .any(|discriminator| ix.data.starts_with(discriminator)),
+                    None => false,
+                }
+            })
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -493,7 +692,10 @@ impl FilterTransactions {
                     && filter.failed.is_none()
                     && filter.account_include.is_empty()
                     && filter.account_exclude.is_empty()
-                    && filter.account_required.is_empty(),
+                    && filter.account_required.is_empty()
+                    && filter.program_invoked.is_empty()
+                    && filter.log_contains.is_empty()
+                    && filter.log_prefix.is_empty(),
                 limit.any,
             )?;
             ConfigGrpcFilters::check_pubkey_max(
@@ -508,7 +710,48 @@ impl FilterTransactions {
                 filter.account_required.len(),
                 limit.account_required_max,
             )?;
+            ConfigGrpcFilters::check_pubkey_max(
+                filter.program_invoked.len(),
+                limit.program_invoked_max,
+            )?;
+
+            validate_transactions_filter(filter)?;
+            anyhow::ensure!(
+                filter.log_contains.len() + filter.log_prefix.len() <= limit.log_patterns_max,
+                "Max amount of log patterns reached, only {} allowed",
+                limit.log_patterns_max
+            );
+            for pattern in filter.log_contains.iter().chain(filter.log_prefix.iter()) {
+                anyhow::ensure!(
+                    pattern.len() <= limit.log_pattern_length_max,
+                    "Max length of log pattern reached, only {} allowed",
+                    limit.log_pattern_length_max
+                );
+            }
+
+            let mut program_invoked: HashMap<Pubkey, Vec<Vec<u8>>> = HashMap::new();
+            for program in filter.program_invoked.iter() {
+                ConfigGrpcFilters::check_max(
+                    program.discriminators.len(),
+                    limit.program_invoked_discriminators_max,
+                )?;
+
+                let program_id = Pubkey::from_str(&program.program_id)?;
+                ConfigGrpcFilters::check_pubkey_reject(&program_id, &limit.program_invoked_reject)?;
+                // empty discriminator match any instruction data
+                let discriminators = program_invoked.entry(program_id).or_default();
+                if program.discriminators.is_empty() {
+                    discriminators.push(vec![]);
+                } else {
+                    discriminators.extend(program.discriminators.iter().cloned());
+                }
+            }
 
+            let omit = filter
+                .omit
+                .as_ref()
+                .map(TransactionMetaOmit::from)
+                .unwrap_or_default();
             filters.insert(
                 name.clone(),
                 FilterTransactionsInner {
@@ -535,6 +778,14 @@ impl FilterTransactions {
                         &filter.account_required,
                         &HashSet::new(),
                     )?,
+                    program_invoked,
+                    log_contains: filter.log_contains.clone(),
+                    log_prefix: filter.log_prefix.clone(),
+                    omit: TransactionMetaOmit {
+                        // `strip_logs` is deprecated alias of `omit.log_messages`
+                        log_messages: filter.strip_logs.unwrap_or_default() || omit.log_messages,
+                        ..omit
+                    },
                 },
             );
         }
@@ -548,6 +799,8 @@ impl FilterTransactions {
         &'a self,
         message: &'a MessageTransaction,
     ) -> Box<dyn Iterator<Item = (Vec<String>, MessageRef<'a>)> + Send + 'a> {
+        // field omitted only if all matched filters omit it
+        let mut omit: Option<TransactionMetaOmit> = None;
         let filters = self
             .filters
             .iter()
@@ -618,11 +871,29 @@ impl FilterTransactions {
                     }
                 }
 
+                if !inner.program_invoked.is_empty()
+                    && !inner.is_program_invoked(&message.transaction)
+                {
+                    return None;
+                }
+
+                if (!inner.log_contains.is_empty() || !inner.log_prefix.is_empty())
+                    && !inner.is_log_match(&message.transaction)
+                {
+                    return None;
+                }
+
+                omit = Some(match omit {
+                    Some(omit) => omit.intersection(inner.omit),
+                    None => inner.omit,
+                });
                 Some(name.clone())
             })
             .collect();
         let message = match self.filter_type {
-            FilterTransactionsType::Transaction => MessageRef::Transaction(message),
+            FilterTransactionsType::Transaction => {
+                MessageRef::Transaction(message, omit.unwrap_or_default())
+            }
             FilterTransactionsType::TransactionStatus => MessageRef::TransactionStatus(message),
         };
         Box::new(std::iter::once((filters, message)))
@@ -667,6 +938,49 @@ pub struct FilterBlocksInner {
     include_transactions: Option<bool>,
     include_accounts: Option<bool>,
     include_entries: Option<bool>,
+    fragments: bool,
+    include_entry_index: bool,
+}
+
+impl FilterBlocksInner {
+    fn filter_transactions<'a>(
+        &self,
+        transactions: &'a [MessageTransactionInfo],
+    ) -> Vec<&'a MessageTransactionInfo> {
+        if matches!(self.include_transactions, None | Some(true)) {
+            transactions
+                .iter()
+                .filter(|tx| {
+                    self.account_include.is_empty()
+                        || tx
+                            .transaction
+                            .message()
+                            .account_keys()
+                            .iter()
+                            .any(|pubkey| self.account_include.binary_search(pubkey).is_ok())
+                })
+                .collect()
+        } else {
+            vec![]
+        }
+    }
+
+    fn filter_accounts<'a>(
+        &self,
+        accounts: &'a [MessageAccountInfo],
+    ) -> Vec<&'a MessageAccountInfo> {
+        if self.include_accounts == Some(true) {
+            accounts
+                .iter()
+                .filter(|account| {
+                    self.account_include.is_empty()
+                        || self.account_include.binary_search(&account.pubkey).is_ok()
+                })
+                .collect()
+        } else {
+            vec![]
+        }
+    }
 }
 
 #[derive(Debug, Default, Clone)]
@@ -714,6 +1028,8 @@ impl FilterBlocks {
                     include_transactions: filter.include_transactions,
                     include_accounts: filter.include_accounts,
                     include_entries: filter.include_entries,
+                    fragments: filter.fragments.unwrap_or_default(),
+                    include_entry_index: filter.include_entry_index.unwrap_or_default(),
                 },
             );
         }
@@ -724,64 +1040,51 @@ impl FilterBlocks {
         &'a self,
         message: &'a MessageBlock,
     ) -> Box<dyn Iterator<Item = (Vec<String>, MessageRef<'a>)> + Send + 'a> {
-        Box::new(self.filters.iter().map(move |(filter, inner)| {
-            #[allow(clippy::unnecessary_filter_map)]
-            let transactions = if matches!(inner.include_transactions, None | Some(true)) {
-                message
-                    .transactions
-                    .iter()
-                    .filter_map(|tx| {
-                        if !inner.account_include.is_empty()
-                            && tx
-                                .transaction
-                                .message()
-                                .account_keys()
-                                .iter()
-                                .all(|pubkey| inner.account_include.binary_search(pubkey).is_err())
-                        {
-                            return None;
-                        }
-
-                        Some(tx)
-                    })
-                    .collect::<Vec<_>>()
-            } else {
-                vec![]
-            };
+        Box::new(
+            self.filters
+                .iter()
+                .filter(|(_filter, inner)| !inner.fragments)
+                .map(move |(filter, inner)| {
+                    let transactions = inner.filter_transactions(&message.transactions);
+                    let accounts = inner.filter_accounts(&message.accounts);
+                    let entries = if inner.include_entries == Some(true) {
+                        message.entries.iter().collect::<Vec<_>>()
+                    } else {
+                        vec![]
+                    };
 
-            #[allow(clippy::unnecessary_filter_map)]
-            let accounts = if inner.include_accounts == Some(true) {
-                message
-                    .accounts
-                    .iter()
-                    .filter_map(|account| {
-                        if !inner.account_include.is_empty()
-                            && inner
-                                .account_include
-                                .binary_search(&account.pubkey)
-                                .is_err()
-                        {
-                            return None;
-                        }
-
-                        Some(account)
-                    })
-                    .collect::<Vec<_>>()
-            } else {
-                vec![]
-            };
+                    let mut block: MessageBlockRef =
+                        (message, transactions, accounts, entries).into();
+                    if inner.include_entry_index {
+                        block.transactions_entries = Some(&message.entries);
+                    }
 
-            let entries = if inner.include_entries == Some(true) {
-                message.entries.iter().collect::<Vec<_>>()
-            } else {
-                vec![]
-            };
+                    (vec![filter.clone()], MessageRef::Block(block))
+                }),
+        )
+    }
 
-            (
-                vec![filter.clone()],
-                MessageRef::Block((message, transactions, accounts, entries).into()),
-            )
-        }))
+    fn get_fragment_filters<'a>(
+        &'a self,
+        message: &'a MessageBlockFragment,
+    ) -> Box<dyn Iterator<Item = (Vec<String>, MessageRef<'a>)> + Send + 'a> {
+        Box::new(
+            self.filters
+                .iter()
+                .filter(|(_filter, inner)| inner.fragments)
+                .map(move |(filter, inner)| {
+                    (
+                        vec![filter.clone()],
+                        MessageRef::BlockFragment(MessageBlockFragmentRef {
+                            slot: message.slot,
+                            entry: message.entry.as_ref(),
+                            transactions: inner.filter_transactions(&message.transactions),
+                            accounts: inner.filter_accounts(&message.accounts),
+                            block_meta: message.block_meta.as_ref(),
+                        }),
+                    )
+                }),
+        )
     }
 }
 
@@ -817,7 +1120,7 @@ impl FilterBlocksMeta {
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct FilterAccountsDataSlice {
     pub start: usize,
     pub end: usize,
@@ -828,7 +1131,7 @@ impl From<&SubscribeRequestAccountsDataSlice> for FilterAccountsDataSlice {
     fn from(data_slice: &SubscribeRequestAccountsDataSlice) -> Self {
         Self {
             start: data_slice.offset as usize,
-            end: (data_slice.offset + data_slice.length) as usize,
+            end: data_slice.offset.saturating_add(data_slice.length) as usize,
             length: data_slice.length as usize,
         }
     }
@@ -836,21 +1139,8 @@ impl From<&SubscribeRequestAccountsDataSlice> for FilterAccountsDataSlice {
 
 impl FilterAccountsDataSlice {
     pub fn create(slices: &[SubscribeRequestAccountsDataSlice]) -> anyhow::Result<Vec<Self>> {
-        let slices = slices.iter().map(Into::into).collect::<Vec<Self>>();
-
-        for (i, slice_a) in slices.iter().enumerate() {
-            // check order
-            for slice_b in slices[i + 1..].iter() {
-                anyhow::ensure!(slice_a.start <= slice_b.start, "data slices out of order");
-            }
-
-            // check overlap
-            for slice_b in slices[0..i].iter() {
-                anyhow::ensure!(slice_a.start >= slice_b.end, "data slices overlap");
-            }
-        }
-
-        Ok(slices)
+        validate_data_slices(slices)?;
+        Ok(slices.iter().map(Into::into).collect())
     }
 }
 
@@ -858,34 +1148,58 @@ impl FilterAccountsDataSlice {
 mod tests {
     use {
         crate::{
-            config::ConfigGrpcFilters,
+            codec::{EncodedCache, FilteredUpdate},
+            config::{ConfigGrpcAccountsCache, ConfigGrpcFilters},
             filters::Filter,
-            grpc::{Message, MessageRef, MessageTransaction, MessageTransactionInfo},
+            grpc::{
+                Message, MessageAccount, MessageAccountInfo, MessageRef, MessageTransaction,
+                MessageTransactionInfo,
+            },
         },
         solana_sdk::{
             hash::Hash,
+            instruction::CompiledInstruction,
             message::{v0::LoadedAddresses, Message as SolMessage, MessageHeader},
             pubkey::Pubkey,
             signer::{keypair::Keypair, Signer},
             transaction::{SanitizedTransaction, Transaction},
         },
-        solana_transaction_status::TransactionStatusMeta,
+        solana_transaction_status::{InnerInstruction, InnerInstructions, TransactionStatusMeta},
         std::collections::HashMap,
         yellowstone_grpc_proto::geyser::{
-            SubscribeRequest, SubscribeRequestFilterAccounts, SubscribeRequestFilterTransactions,
+            subscribe_request_filter_accounts_filter::Filter as AccountsFilterDataOneof,
+            subscribe_request_filter_accounts_filter_lamports::Cmp as AccountsFilterLamportsCmp,
+            subscribe_update::UpdateOneof, CommitmentLevel, SubscribeRequest,
+            SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter,
+            SubscribeRequestFilterAccountsFilterLamports, SubscribeRequestFilterSlots,
+            SubscribeRequestFilterTransactions, SubscribeRequestFilterTransactionsOmit,
+            SubscribeRequestFilterTransactionsProgram, SubscribeUpdate,
         },
+        yellowstone_grpc_proto::{convert_to::TransactionMetaOmit, prost::Message as _},
     };
 
     fn create_message_transaction(
         keypair: &Keypair,
         account_keys: Vec<Pubkey>,
+    ) -> MessageTransaction {
+        create_message_transaction_with_instructions(keypair, account_keys, 0, vec![], None)
+    }
+
+    fn create_message_transaction_with_instructions(
+        keypair: &Keypair,
+        account_keys: Vec<Pubkey>,
+        num_readonly_unsigned_accounts: u8,
+        instructions: Vec<CompiledInstruction>,
+        inner_instructions: Option<Vec<InnerInstructions>>,
     ) -> MessageTransaction {
         let message = SolMessage {
             header: MessageHeader {
                 num_required_signatures: 1,
+                num_readonly_unsigned_accounts,
                 ..MessageHeader::default()
             },
             account_keys,
+            instructions,
             ..SolMessage::default()
         };
         let recent_blockhash = Hash::default();
@@ -897,7 +1211,7 @@ mod tests {
             fee: 0,
             pre_balances: vec![],
             post_balances: vec![],
-            inner_instructions: None,
+            inner_instructions,
             log_messages: None,
             pre_token_balances: None,
             post_token_balances: None,
@@ -916,6 +1230,25 @@ mod tests {
                 index: 1,
             },
             slot: 100,
+            encoded: EncodedCache::default(),
+        }
+    }
+
+    fn create_message_account(owner: Pubkey, lamports: u64, executable: bool) -> MessageAccount {
+        MessageAccount {
+            account: MessageAccountInfo {
+                pubkey: Pubkey::new_unique(),
+                lamports,
+                owner,
+                executable,
+                rent_epoch: 0,
+                data: vec![],
+                write_version: 1,
+                txn_signature: None,
+            },
+            slot: 100,
+            is_startup: false,
+            encoded: EncodedCache::default(),
         }
     }
 
@@ -933,12 +1266,44 @@ mod tests {
             commitment: None,
             accounts_data_slice: Vec::new(),
             ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
         };
         let limit = ConfigGrpcFilters::default();
         let filter = Filter::new(&config, &limit);
         assert!(filter.is_ok());
     }
 
+    #[test]
+    fn test_filters_slots_include_dead() {
+        let limit = ConfigGrpcFilters::default();
+        for (commitment, is_ok) in [
+            (None, true),
+            (Some(CommitmentLevel::Processed), true),
+            (Some(CommitmentLevel::Confirmed), false),
+            (Some(CommitmentLevel::Finalized), false),
+        ] {
+            let config = SubscribeRequest {
+                slots: HashMap::from([(
+                    "slots".to_owned(),
+                    SubscribeRequestFilterSlots {
+                        filter_by_commitment: None,
+                        include_dead: Some(true),
+                    },
+                )]),
+                commitment: commitment.map(|commitment| commitment as i32),
+                ..Default::default()
+            };
+            assert_eq!(
+                Filter::new(&config, &limit).is_ok(),
+                is_ok,
+                "{commitment:?}"
+            );
+        }
+    }
+
     #[test]
     fn test_filters_account_empty() {
         let mut accounts = HashMap::new();
@@ -949,6 +1314,7 @@ mod tests {
                 account: vec![],
                 owner: vec![],
                 filters: vec![],
+                send_initial_state: false,
             },
         );
 
@@ -963,6 +1329,10 @@ mod tests {
             commitment: None,
             accounts_data_slice: Vec::new(),
             ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
         };
         let mut limit = ConfigGrpcFilters::default();
         limit.accounts.any = false;
@@ -984,6 +1354,11 @@ mod tests {
                 account_include: vec![],
                 account_exclude: vec![],
                 account_required: vec![],
+                program_invoked: vec![],
+                log_contains: vec![],
+                log_prefix: vec![],
+                strip_logs: None,
+                omit: None,
             },
         );
 
@@ -998,6 +1373,10 @@ mod tests {
             commitment: None,
             accounts_data_slice: Vec::new(),
             ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
         };
         let mut limit = ConfigGrpcFilters::default();
         limit.transactions.any = false;
@@ -1018,6 +1397,11 @@ mod tests {
                 account_include: vec![],
                 account_exclude: vec![],
                 account_required: vec![],
+                program_invoked: vec![],
+                log_contains: vec![],
+                log_prefix: vec![],
+                strip_logs: None,
+                omit: None,
             },
         );
 
@@ -1032,6 +1416,10 @@ mod tests {
             commitment: None,
             accounts_data_slice: Vec::new(),
             ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
         };
         let mut limit = ConfigGrpcFilters::default();
         limit.transactions.any = false;
@@ -1058,6 +1446,11 @@ mod tests {
                 account_include,
                 account_exclude: vec![],
                 account_required: vec![],
+                program_invoked: vec![],
+                log_contains: vec![],
+                log_prefix: vec![],
+                strip_logs: None,
+                omit: None,
             },
         );
 
@@ -1072,6 +1465,10 @@ mod tests {
             commitment: None,
             accounts_data_slice: Vec::new(),
             ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
         };
         let limit = ConfigGrpcFilters::default();
         let filter = Filter::new(&config, &limit).unwrap();
@@ -1082,7 +1479,7 @@ mod tests {
         let updates = filter.get_filters(&message, None).collect::<Vec<_>>();
         assert_eq!(updates.len(), 2);
         assert_eq!(updates[0].0, vec!["serum"]);
-        assert!(matches!(updates[0].1, MessageRef::Transaction(_)));
+        assert!(matches!(updates[0].1, MessageRef::Transaction(..)));
         assert_eq!(updates[1].0, Vec::<String>::new());
         assert!(matches!(updates[1].1, MessageRef::TransactionStatus(_)));
     }
@@ -1105,6 +1502,11 @@ mod tests {
                 account_include,
                 account_exclude: vec![],
                 account_required: vec![],
+                program_invoked: vec![],
+                log_contains: vec![],
+                log_prefix: vec![],
+                strip_logs: None,
+                omit: None,
             },
         );
 
@@ -1119,6 +1521,10 @@ mod tests {
             commitment: None,
             accounts_data_slice: Vec::new(),
             ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
         };
         let limit = ConfigGrpcFilters::default();
         let filter = Filter::new(&config, &limit).unwrap();
@@ -1129,7 +1535,7 @@ mod tests {
         let updates = filter.get_filters(&message, None).collect::<Vec<_>>();
         assert_eq!(updates.len(), 2);
         assert_eq!(updates[0].0, vec!["serum"]);
-        assert!(matches!(updates[0].1, MessageRef::Transaction(_)));
+        assert!(matches!(updates[0].1, MessageRef::Transaction(..)));
         assert_eq!(updates[1].0, Vec::<String>::new());
         assert!(matches!(updates[1].1, MessageRef::TransactionStatus(_)));
     }
@@ -1152,6 +1558,11 @@ mod tests {
                 account_include: vec![],
                 account_exclude,
                 account_required: vec![],
+                program_invoked: vec![],
+                log_contains: vec![],
+                log_prefix: vec![],
+                strip_logs: None,
+                omit: None,
             },
         );
 
@@ -1166,6 +1577,10 @@ mod tests {
             commitment: None,
             accounts_data_slice: Vec::new(),
             ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
         };
         let limit = ConfigGrpcFilters::default();
         let filter = Filter::new(&config, &limit).unwrap();
@@ -1202,6 +1617,11 @@ mod tests {
                 account_include,
                 account_exclude: vec![],
                 account_required,
+                program_invoked: vec![],
+                log_contains: vec![],
+                log_prefix: vec![],
+                strip_logs: None,
+                omit: None,
             },
         );
 
@@ -1216,6 +1636,10 @@ mod tests {
             commitment: None,
             accounts_data_slice: Vec::new(),
             ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
         };
         let limit = ConfigGrpcFilters::default();
         let filter = Filter::new(&config, &limit).unwrap();
@@ -1228,7 +1652,7 @@ mod tests {
         let updates = filter.get_filters(&message, None).collect::<Vec<_>>();
         assert_eq!(updates.len(), 2);
         assert_eq!(updates[0].0, vec!["serum"]);
-        assert!(matches!(updates[0].1, MessageRef::Transaction(_)));
+        assert!(matches!(updates[0].1, MessageRef::Transaction(..)));
         assert_eq!(updates[1].0, Vec::<String>::new());
         assert!(matches!(updates[1].1, MessageRef::TransactionStatus(_)));
     }
@@ -1257,6 +1681,11 @@ mod tests {
                 account_include,
                 account_exclude: vec![],
                 account_required,
+                program_invoked: vec![],
+                log_contains: vec![],
+                log_prefix: vec![],
+                strip_logs: None,
+                omit: None,
             },
         );
 
@@ -1271,6 +1700,10 @@ mod tests {
             commitment: None,
             accounts_data_slice: Vec::new(),
             ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
         };
         let limit = ConfigGrpcFilters::default();
         let filter = Filter::new(&config, &limit).unwrap();
@@ -1282,4 +1715,397 @@ mod tests {
             assert!(filters.is_empty());
         }
     }
+
+    #[test]
+    fn test_filters_account_lamports_executable() {
+        let owner = Pubkey::new_unique();
+        let mut accounts = HashMap::new();
+
+        accounts.insert(
+            "dust".to_owned(),
+            SubscribeRequestFilterAccounts {
+                account: vec![],
+                owner: vec![owner.to_string()],
+                filters: vec![
+                    SubscribeRequestFilterAccountsFilter {
+                        filter: Some(AccountsFilterDataOneof::Lamports(
+                            SubscribeRequestFilterAccountsFilterLamports {
+                                cmp: Some(AccountsFilterLamportsCmp::Gt(0)),
+                            },
+                        )),
+                    },
+                    SubscribeRequestFilterAccountsFilter {
+                        filter: Some(AccountsFilterDataOneof::Lamports(
+                            SubscribeRequestFilterAccountsFilterLamports {
+                                cmp: Some(AccountsFilterLamportsCmp::Lt(1_000)),
+                            },
+                        )),
+                    },
+                    SubscribeRequestFilterAccountsFilter {
+                        filter: Some(AccountsFilterDataOneof::Executable(false)),
+                    },
+                ],
+                send_initial_state: false,
+            },
+        );
+
+        let config = SubscribeRequest {
+            accounts,
+            slots: HashMap::new(),
+            transactions: HashMap::new(),
+            transactions_status: HashMap::new(),
+            blocks: HashMap::new(),
+            blocks_meta: HashMap::new(),
+            entry: HashMap::new(),
+            commitment: None,
+            accounts_data_slice: Vec::new(),
+            ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
+        };
+        let limit = ConfigGrpcFilters::default();
+        let filter = Filter::new(&config, &limit).unwrap();
+
+        for (lamports, executable, matched) in [
+            (500, false, true),
+            (0, false, false),
+            (1_000, false, false),
+            (500, true, false),
+        ] {
+            let message = Message::Account(create_message_account(owner, lamports, executable));
+            let updates = filter.get_filters(&message, None).collect::<Vec<_>>();
+            assert_eq!(updates.len(), 1);
+            assert_eq!(updates[0].0.is_empty(), !matched);
+            assert!(matches!(updates[0].1, MessageRef::Account(_)));
+        }
+
+        let mut limit = ConfigGrpcFilters::default();
+        limit.accounts.lamports_max = 1;
+        assert!(Filter::new(&config, &limit).is_err());
+    }
+
+    #[test]
+    fn test_filters_account_initial_state() {
+        let owner = Pubkey::new_unique();
+        let mut accounts = HashMap::new();
+
+        accounts.insert(
+            "state".to_owned(),
+            SubscribeRequestFilterAccounts {
+                account: vec![],
+                owner: vec![owner.to_string()],
+                filters: vec![],
+                send_initial_state: true,
+            },
+        );
+        accounts.insert(
+            "live".to_owned(),
+            SubscribeRequestFilterAccounts {
+                account: vec![],
+                owner: vec![owner.to_string()],
+                filters: vec![],
+                send_initial_state: false,
+            },
+        );
+
+        let config = SubscribeRequest {
+            accounts,
+            slots: HashMap::new(),
+            transactions: HashMap::new(),
+            transactions_status: HashMap::new(),
+            blocks: HashMap::new(),
+            blocks_meta: HashMap::new(),
+            entry: HashMap::new(),
+            commitment: None,
+            accounts_data_slice: Vec::new(),
+            ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
+        };
+        let limit = ConfigGrpcFilters::default();
+        let filter = Filter::new(&config, &limit).unwrap();
+        assert!(filter.has_initial_state());
+
+        let update = filter
+            .get_initial_state_update(&create_message_account(owner, 1, false))
+            .unwrap();
+        let FilteredUpdate::Encoded { filters, update } = update else {
+            panic!("expected encoded update");
+        };
+        assert_eq!(filters, vec!["state".to_owned()]);
+        let update = SubscribeUpdate::decode(update).unwrap();
+        assert!(matches!(
+            update.update_oneof,
+            Some(UpdateOneof::Account(account)) if account.is_startup
+        ));
+        assert!(filter
+            .get_initial_state_update(&create_message_account(Pubkey::new_unique(), 1, false))
+            .is_none());
+
+        let accounts_cache: ConfigGrpcAccountsCache =
+            serde_json::from_str(&format!(r#"{{"owners": ["{owner}"]}}"#)).unwrap();
+        assert!(filter.check_initial_state(&accounts_cache).is_ok());
+        let accounts_cache: ConfigGrpcAccountsCache = serde_json::from_str("{}").unwrap();
+        assert!(filter.check_initial_state(&accounts_cache).is_err());
+
+        // initial state is not sent again if only filters without `send_initial_state` changed
+        let mut config_new = config.clone();
+        config_new.accounts.get_mut("live").unwrap().owner = vec![];
+        let filter_new = Filter::new(&config_new, &limit).unwrap();
+        assert!(!filter_new.is_initial_state_changed(&filter));
+        config_new.commitment = Some(CommitmentLevel::Confirmed as i32);
+        let filter_new = Filter::new(&config_new, &limit).unwrap();
+        assert!(filter_new.is_initial_state_changed(&filter));
+        config_new.commitment = None;
+        config_new.accounts.get_mut("state").unwrap().filters =
+            vec![SubscribeRequestFilterAccountsFilter {
+                filter: Some(AccountsFilterDataOneof::Datasize(165)),
+            }];
+        let filter_new = Filter::new(&config_new, &limit).unwrap();
+        assert!(filter_new.is_initial_state_changed(&filter));
+    }
+
+    #[test]
+    fn test_transaction_program_invoked() {
+        let keypair = Keypair::new();
+        let account_key_c = Pubkey::new_unique();
+        let program_a = Pubkey::new_unique();
+        let program_b = Pubkey::new_unique();
+
+        let mut transactions = HashMap::new();
+        for (name, program_id, discriminators) in [
+            ("a", program_a, vec![vec![1, 2]]),
+            ("a_other", program_a, vec![vec![2]]),
+            ("b", program_b, vec![]),
+            ("c", account_key_c, vec![]),
+        ] {
+            transactions.insert(
+                name.to_owned(),
+                SubscribeRequestFilterTransactions {
+                    vote: None,
+                    failed: None,
+                    signature: None,
+                    account_include: vec![],
+                    account_exclude: vec![],
+                    account_required: vec![],
+                    program_invoked: vec![SubscribeRequestFilterTransactionsProgram {
+                        program_id: program_id.to_string(),
+                        discriminators,
+                    }],
+                    log_contains: vec![],
+                    log_prefix: vec![],
+                    strip_logs: None,
+                    omit: None,
+                },
+            );
+        }
+
+        let config = SubscribeRequest {
+            accounts: HashMap::new(),
+            slots: HashMap::new(),
+            transactions,
+            transactions_status: HashMap::new(),
+            blocks: HashMap::new(),
+            blocks_meta: HashMap::new(),
+            entry: HashMap::new(),
+            commitment: None,
+            accounts_data_slice: Vec::new(),
+            ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
+        };
+        let limit = ConfigGrpcFilters::default();
+        let filter = Filter::new(&config, &limit).unwrap();
+
+        // program a invoked at top level, program b only from inner instruction
+        let message_transaction = create_message_transaction_with_instructions(
+            &keypair,
+            vec![keypair.pubkey(), account_key_c, program_a, program_b],
+            2,
+            vec![CompiledInstruction::new_from_raw_parts(
+                2,
+                vec![1, 2, 3],
+                vec![1],
+            )],
+            Some(vec![InnerInstructions {
+                index: 0,
+                instructions: vec![InnerInstruction {
+                    instruction: CompiledInstruction::new_from_raw_parts(3, vec![9], vec![1]),
+                    stack_height: Some(2),
+                }],
+            }]),
+        );
+        let message = Message::Transaction(message_transaction);
+        let updates = filter.get_filters(&message, None).collect::<Vec<_>>();
+        assert_eq!(updates.len(), 2);
+        let mut filters = updates[0].0.clone();
+        filters.sort();
+        assert_eq!(filters, vec!["a", "b"]);
+        assert!(matches!(updates[0].1, MessageRef::Transaction(..)));
+
+        let mut limit = ConfigGrpcFilters::default();
+        limit.transactions.program_invoked_reject.insert(program_b);
+        assert!(Filter::new(&config, &limit).is_err());
+    }
+
+    #[test]
+    fn test_transaction_log_match_strip() {
+        let keypair = Keypair::new();
+
+        let mut transactions = HashMap::new();
+        for (name, log_contains, log_prefix, strip_logs) in [
+            (
+                "swap",
+                vec![],
+                vec!["Program log: Instruction: Swap"],
+                Some(true),
+            ),
+            ("data", vec!["data: abc"], vec![], Some(true)),
+            ("transfer", vec!["Transfer"], vec![], None),
+        ] {
+            transactions.insert(
+                name.to_owned(),
+                SubscribeRequestFilterTransactions {
+                    vote: None,
+                    failed: None,
+                    signature: None,
+                    account_include: vec![],
+                    account_exclude: vec![],
+                    account_required: vec![],
+                    program_invoked: vec![],
+                    log_contains: log_contains.into_iter().map(Into::into).collect(),
+                    log_prefix: log_prefix.into_iter().map(Into::into).collect(),
+                    strip_logs,
+                    omit: None,
+                },
+            );
+        }
+
+        let config = SubscribeRequest {
+            accounts: HashMap::new(),
+            slots: HashMap::new(),
+            transactions,
+            transactions_status: HashMap::new(),
+            blocks: HashMap::new(),
+            blocks_meta: HashMap::new(),
+            entry: HashMap::new(),
+            commitment: None,
+            accounts_data_slice: Vec::new(),
+            ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
+        };
+        let limit = ConfigGrpcFilters::default();
+        let filter = Filter::new(&config, &limit).unwrap();
+
+        let mut message_transaction = create_message_transaction(&keypair, vec![keypair.pubkey()]);
+        message_transaction.transaction.meta.log_messages = Some(vec![
+            "Program log: Instruction: Swap".to_owned(),
+            "Program data: abc".to_owned(),
+        ]);
+        let message = Message::Transaction(message_transaction);
+        let updates = filter.get_filters(&message, None).collect::<Vec<_>>();
+        assert_eq!(updates.len(), 2);
+        let mut filters = updates[0].0.clone();
+        filters.sort();
+        assert_eq!(filters, vec!["data", "swap"]);
+        assert!(matches!(&updates[0].1, MessageRef::Transaction(_, omit) if omit.log_messages));
+        match updates[0].1.to_proto(&[]) {
+            UpdateOneof::Transaction(tx) => {
+                let meta = tx.transaction.unwrap().meta.unwrap();
+                assert!(meta.log_messages.is_empty());
+                assert!(meta.log_messages_none);
+            }
+            _ => panic!("expected transaction update"),
+        }
+
+        let mut limit = ConfigGrpcFilters::default();
+        limit.transactions.log_pattern_length_max = 16;
+        assert!(Filter::new(&config, &limit).is_err());
+    }
+
+    #[test]
+    fn test_transaction_omit() {
+        let keypair = Keypair::new();
+
+        let mut transactions = HashMap::new();
+        for (name, omit) in [
+            (
+                "a",
+                SubscribeRequestFilterTransactionsOmit {
+                    inner_instructions: true,
+                    rewards: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                "b",
+                SubscribeRequestFilterTransactionsOmit {
+                    rewards: true,
+                    loaded_addresses: true,
+                    ..Default::default()
+                },
+            ),
+        ] {
+            transactions.insert(
+                name.to_owned(),
+                SubscribeRequestFilterTransactions {
+                    vote: None,
+                    failed: None,
+                    signature: None,
+                    account_include: vec![],
+                    account_exclude: vec![],
+                    account_required: vec![],
+                    program_invoked: vec![],
+                    log_contains: vec![],
+                    log_prefix: vec![],
+                    strip_logs: None,
+                    omit: Some(omit),
+                },
+            );
+        }
+
+        let config = SubscribeRequest {
+            accounts: HashMap::new(),
+            slots: HashMap::new(),
+            transactions,
+            transactions_status: HashMap::new(),
+            blocks: HashMap::new(),
+            blocks_meta: HashMap::new(),
+            entry: HashMap::new(),
+            commitment: None,
+            accounts_data_slice: Vec::new(),
+            ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
+        };
+        let limit = ConfigGrpcFilters::default();
+        let filter = Filter::new(&config, &limit).unwrap();
+
+        let message_transaction = create_message_transaction(&keypair, vec![keypair.pubkey()]);
+        let message = Message::Transaction(message_transaction);
+        let updates = filter.get_filters(&message, None).collect::<Vec<_>>();
+        assert_eq!(updates.len(), 2);
+        assert_eq!(updates[0].0.len(), 2);
+        match &updates[0].1 {
+            MessageRef::Transaction(_, omit) => assert_eq!(
+                omit,
+                &TransactionMetaOmit {
+                    rewards: true,
+                    ..Default::default()
+                }
+            ),
+            _ => panic!("expected transaction message"),
+        }
+    }
 }
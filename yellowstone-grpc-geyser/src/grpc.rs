@@ -1,15 +1,23 @@
 use {
     crate::{
-        config::{ConfigBlockFailAction, ConfigGrpc, ConfigGrpcFilters},
+        accounts::{AccountsStorage, AccountsStorageMessage},
+        codec::{
+            geyser_server::{Geyser, GeyserServer},
+            EncodedCache, EncodedCacheKey, FilteredUpdate,
+        },
+        config::{ConfigBlockFailAction, ConfigGrpc, ConfigGrpcBlockReconstruction},
         filters::{Filter, FilterAccountsDataSlice},
+        listener,
         prom::{self, DebugClientMessage, CONNECTIONS_TOTAL, MESSAGE_QUEUE_SIZE},
+        snapshot::Snapshot,
+        tenant::{Tenant, TenantsUsage},
+        tls,
         version::GrpcVersionInfo,
     },
     agave_geyser_plugin_interface::geyser_plugin_interface::{
         ReplicaAccountInfoV3, ReplicaBlockInfoV3, ReplicaEntryInfoV2, ReplicaTransactionInfoV2,
-        SlotStatus,
+        SlotStatus as GeyserSlotStatus,
     },
-    anyhow::Context,
     log::{error, info},
     solana_sdk::{
         clock::{UnixTimestamp, MAX_RECENT_BLOCKHASHES},
@@ -19,46 +27,56 @@ use {
     },
     solana_transaction_status::{Reward, TransactionStatusMeta},
     std::{
-        collections::{BTreeMap, HashMap},
+        collections::{BTreeMap, HashMap, HashSet, VecDeque},
+        mem, slice,
         sync::{
             atomic::{AtomicUsize, Ordering},
             Arc,
         },
     },
     tokio::{
-        fs,
         runtime::Builder,
-        sync::{broadcast, mpsc, Mutex, Notify, RwLock, Semaphore},
+        sync::{broadcast, mpsc, oneshot, watch, Notify, RwLock, Semaphore},
         task::spawn_blocking,
         time::{sleep, Duration, Instant},
     },
     tokio_stream::wrappers::ReceiverStream,
     tonic::{
         service::interceptor::interceptor,
-        transport::{
-            server::{Server, TcpIncoming},
-            Identity, ServerTlsConfig,
-        },
+        transport::server::{Server, TcpIncoming},
         Request, Response, Result as TonicResult, Status, Streaming,
     },
     tonic_health::server::health_reporter,
     yellowstone_grpc_proto::{
-        convert_to,
+        compression::{SendCompressionConfig, SendCompressionLayer},
+        convert_to::{self, TransactionMetaOmit},
         prelude::{
-            geyser_server::{Geyser, GeyserServer},
-            subscribe_update::UpdateOneof,
-            CommitmentLevel, GetBlockHeightRequest, GetBlockHeightResponse,
-            GetLatestBlockhashRequest, GetLatestBlockhashResponse, GetSlotRequest, GetSlotResponse,
-            GetVersionRequest, GetVersionResponse, IsBlockhashValidRequest,
-            IsBlockhashValidResponse, PingRequest, PongResponse, SubscribeRequest, SubscribeUpdate,
-            SubscribeUpdateAccount, SubscribeUpdateAccountInfo, SubscribeUpdateBlock,
-            SubscribeUpdateBlockMeta, SubscribeUpdateEntry, SubscribeUpdatePing,
+            subscribe_update::UpdateOneof, BackpressurePolicy, CommitmentLevel,
+            GetAccountInfoRequest, GetAccountInfoResponse, GetBlockHeightRequest,
+            GetBlockHeightResponse, GetLatestBlockhashRequest, GetLatestBlockhashResponse,
+            GetMultipleAccountsRequest, GetMultipleAccountsResponse,
+            GetMultipleAccountsResponseAccount, GetSlotRequest, GetSlotResponse, GetVersionRequest,
+            GetVersionResponse, IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest,
+            PongResponse, SlotStatus, SubscribeRequest, SubscribeUpdate, SubscribeUpdateAccount,
+            SubscribeUpdateAccountInfo, SubscribeUpdateBlock, SubscribeUpdateBlockFragment,
+            SubscribeUpdateBlockMeta, SubscribeUpdateEntry, SubscribeUpdateGap,
+            SubscribeUpdatePing, SubscribeUpdateRollback, SubscribeUpdateRollbackAccount,
             SubscribeUpdateSlot, SubscribeUpdateTransaction, SubscribeUpdateTransactionInfo,
             SubscribeUpdateTransactionStatus, TransactionError as SubscribeUpdateTransactionError,
         },
     },
 };
 
+type BroadcastedMessage = (CommitmentLevel, Arc<Vec<Arc<Message>>>);
+
+type ReplayStoredSlotsRequest = (
+    CommitmentLevel,
+    u64,
+    oneshot::Sender<
+        Result<(Vec<Arc<Message>>, broadcast::Receiver<BroadcastedMessage>), Option<u64>>,
+    >,
+);
+
 #[derive(Debug, Clone)]
 pub struct MessageAccountInfo {
     pub pubkey: Pubkey,
@@ -105,6 +123,7 @@ pub struct MessageAccount {
     pub account: MessageAccountInfo,
     pub slot: u64,
     pub is_startup: bool,
+    pub encoded: EncodedCache,
 }
 
 impl<'a> From<(&'a ReplicaAccountInfoV3<'a>, u64, bool)> for MessageAccount {
@@ -122,6 +141,20 @@ impl<'a> From<(&'a ReplicaAccountInfoV3<'a>, u64, bool)> for MessageAccount {
             },
             slot,
             is_startup,
+            encoded: EncodedCache::default(),
+        }
+    }
+}
+
+impl MessageAccount {
+    pub fn to_proto(
+        &self,
+        accounts_data_slice: &[FilterAccountsDataSlice],
+    ) -> SubscribeUpdateAccount {
+        SubscribeUpdateAccount {
+            account: Some(self.account.to_proto(accounts_data_slice)),
+            slot: self.slot,
+            is_startup: self.is_startup,
         }
     }
 }
@@ -130,18 +163,18 @@ impl<'a> From<(&'a ReplicaAccountInfoV3<'a>, u64, bool)> for MessageAccount {
 pub struct MessageSlot {
     pub slot: u64,
     pub parent: Option<u64>,
-    pub status: CommitmentLevel,
+    pub status: SlotStatus,
 }
 
-impl From<(u64, Option<u64>, SlotStatus)> for MessageSlot {
-    fn from((slot, parent, status): (u64, Option<u64>, SlotStatus)) -> Self {
+impl From<(u64, Option<u64>, GeyserSlotStatus)> for MessageSlot {
+    fn from((slot, parent, status): (u64, Option<u64>, GeyserSlotStatus)) -> Self {
         Self {
             slot,
             parent,
             status: match status {
-                SlotStatus::Processed => CommitmentLevel::Processed,
-                SlotStatus::Confirmed => CommitmentLevel::Confirmed,
-                SlotStatus::Rooted => CommitmentLevel::Finalized,
+                GeyserSlotStatus::Processed => SlotStatus::SlotProcessed,
+                GeyserSlotStatus::Confirmed => SlotStatus::SlotConfirmed,
+                GeyserSlotStatus::Rooted => SlotStatus::SlotFinalized,
             },
         }
     }
@@ -157,13 +190,14 @@ pub struct MessageTransactionInfo {
 }
 
 impl MessageTransactionInfo {
-    fn to_proto(&self) -> SubscribeUpdateTransactionInfo {
+    fn to_proto(&self, omit: TransactionMetaOmit) -> SubscribeUpdateTransactionInfo {
         SubscribeUpdateTransactionInfo {
             signature: self.signature.as_ref().into(),
             is_vote: self.is_vote,
             transaction: Some(convert_to::create_transaction(&self.transaction)),
-            meta: Some(convert_to::create_transaction_meta(&self.meta)),
+            meta: Some(convert_to::create_transaction_meta_omit(&self.meta, omit)),
             index: self.index as u64,
+            entry_index: None,
         }
     }
 }
@@ -172,6 +206,7 @@ impl MessageTransactionInfo {
 pub struct MessageTransaction {
     pub transaction: MessageTransactionInfo,
     pub slot: u64,
+    pub encoded: EncodedCache,
 }
 
 impl<'a> From<(&'a ReplicaTransactionInfoV2<'a>, u64)> for MessageTransaction {
@@ -185,6 +220,7 @@ impl<'a> From<(&'a ReplicaTransactionInfoV2<'a>, u64)> for MessageTransaction {
                 index: transaction.index,
             },
             slot,
+            encoded: EncodedCache::default(),
         }
     }
 }
@@ -308,6 +344,35 @@ impl<'a> From<&'a ReplicaBlockInfoV3<'a>> for MessageBlockMeta {
     }
 }
 
+impl MessageBlockMeta {
+    fn to_proto(&self) -> SubscribeUpdateBlockMeta {
+        SubscribeUpdateBlockMeta {
+            slot: self.slot,
+            blockhash: self.blockhash.clone(),
+            rewards: Some(convert_to::create_rewards_obj(
+                self.rewards.as_slice(),
+                None,
+            )),
+            block_time: self.block_time.map(convert_to::create_timestamp),
+            block_height: self.block_height.map(convert_to::create_block_height),
+            parent_slot: self.parent_slot,
+            parent_blockhash: self.parent_blockhash.clone(),
+            executed_transaction_count: self.executed_transaction_count,
+            entries_count: self.entries_count,
+        }
+    }
+}
+
+/// Entry with own transactions and accounts, or block meta with rest of accounts for the final fragment
+#[derive(Debug, Clone)]
+pub struct MessageBlockFragment {
+    pub slot: u64,
+    pub entry: Option<MessageEntry>,
+    pub transactions: Vec<MessageTransactionInfo>,
+    pub accounts: Vec<MessageAccountInfo>,
+    pub block_meta: Option<MessageBlockMeta>,
+}
+
 #[derive(Debug, Clone)]
 #[allow(clippy::large_enum_variant)]
 pub enum Message {
@@ -317,6 +382,7 @@ pub enum Message {
     Entry(MessageEntry),
     Block(MessageBlock),
     BlockMeta(MessageBlockMeta),
+    BlockFragment(MessageBlockFragment),
 }
 
 impl Message {
@@ -328,6 +394,63 @@ impl Message {
             Self::Entry(msg) => msg.slot,
             Self::Block(msg) => msg.slot,
             Self::BlockMeta(msg) => msg.slot,
+            Self::BlockFragment(msg) => msg.slot,
+        }
+    }
+
+    /// Approximate size of the message in memory
+    pub fn size(&self) -> usize {
+        let account_size = |account: &MessageAccountInfo| {
+            mem::size_of::<MessageAccountInfo>() + account.data.len()
+        };
+
+        let transaction_size = |transaction: &MessageTransactionInfo| {
+            let message = transaction.transaction.message();
+            mem::size_of::<MessageTransactionInfo>()
+                + mem::size_of_val(transaction.transaction.signatures())
+                + message.account_keys().len() * mem::size_of::<Pubkey>()
+                + message
+                    .instructions()
+                    .iter()
+                    .map(|ix| ix.accounts.len() + ix.data.len())
+                    .sum::<usize>()
+                + transaction
+                    .meta
+                    .log_messages
+                    .iter()
+                    .flatten()
+                    .map(|log| log.len())
+                    .sum::<usize>()
+                + transaction
+                    .meta
+                    .inner_instructions
+                    .iter()
+                    .flatten()
+                    .flat_map(|ixs| ixs.instructions.iter())
+                    .map(|ix| ix.instruction.accounts.len() + ix.instruction.data.len())
+                    .sum::<usize>()
+        };
+
+        let entry_size = |entry: &MessageEntry| mem::size_of::<MessageEntry>() + entry.hash.len();
+
+        match self {
+            Self::Slot(_) => mem::size_of::<MessageSlot>(),
+            Self::Account(msg) => account_size(&msg.account),
+            Self::Transaction(msg) => transaction_size(&msg.transaction),
+            Self::Entry(msg) => entry_size(msg),
+            Self::Block(msg) => {
+                mem::size_of::<MessageBlock>()
+                    + msg.transactions.iter().map(transaction_size).sum::<usize>()
+                    + msg.accounts.iter().map(account_size).sum::<usize>()
+                    + msg.entries.iter().map(entry_size).sum::<usize>()
+            }
+            Self::BlockMeta(_) => mem::size_of::<MessageBlockMeta>(),
+            Self::BlockFragment(msg) => {
+                mem::size_of::<MessageBlockFragment>()
+                    + msg.entry.as_ref().map(entry_size).unwrap_or_default()
+                    + msg.transactions.iter().map(transaction_size).sum::<usize>()
+                    + msg.accounts.iter().map(account_size).sum::<usize>()
+            }
         }
     }
 
@@ -339,6 +462,7 @@ impl Message {
             Self::Entry(_) => "Entry",
             Self::Block(_) => "Block",
             Self::BlockMeta(_) => "BlockMeta",
+            Self::BlockFragment(_) => "BlockFragment",
         }
     }
 }
@@ -358,6 +482,22 @@ pub struct MessageBlockRef<'a> {
     pub accounts: Vec<&'a MessageAccountInfo>,
     pub entries_count: u64,
     pub entries: Vec<&'a MessageEntry>,
+    // All entries of the block, used to set `entry_index` for transactions
+    pub transactions_entries: Option<&'a Vec<MessageEntry>>,
+}
+
+impl<'a> MessageBlockRef<'a> {
+    fn get_transactions_entry_index(&self) -> HashMap<usize, u64> {
+        let mut map = HashMap::new();
+        for entry in self.transactions_entries.into_iter().flatten() {
+            let start = entry.starting_transaction_index as usize;
+            let end = start + entry.executed_transaction_count as usize;
+            for tx_index in start..end {
+                map.insert(tx_index, entry.index as u64);
+            }
+        }
+        map
+    }
 }
 
 impl<'a>
@@ -390,23 +530,64 @@ impl<'a>
             accounts,
             entries_count: block.entries_count,
             entries,
+            transactions_entries: None,
         }
     }
 }
 
+#[derive(Debug, Clone)]
+pub struct MessageBlockFragmentRef<'a> {
+    pub slot: u64,
+    pub entry: Option<&'a MessageEntry>,
+    pub transactions: Vec<&'a MessageTransactionInfo>,
+    pub accounts: Vec<&'a MessageAccountInfo>,
+    pub block_meta: Option<&'a MessageBlockMeta>,
+}
+
 #[derive(Debug, Clone)]
 #[allow(clippy::large_enum_variant)]
 pub enum MessageRef<'a> {
     Slot(&'a MessageSlot),
     Account(&'a MessageAccount),
-    Transaction(&'a MessageTransaction),
+    Transaction(&'a MessageTransaction, TransactionMetaOmit),
     TransactionStatus(&'a MessageTransaction),
     Entry(&'a MessageEntry),
     Block(MessageBlockRef<'a>),
     BlockMeta(&'a MessageBlockMeta),
+    BlockFragment(MessageBlockFragmentRef<'a>),
 }
 
 impl<'a> MessageRef<'a> {
+    pub fn to_filtered_update(
+        &self,
+        filters: Vec<String>,
+        accounts_data_slice: &[FilterAccountsDataSlice],
+    ) -> FilteredUpdate {
+        let (cache, key) = match self {
+            Self::Account(message) => (
+                &message.encoded,
+                EncodedCacheKey::Account(accounts_data_slice.to_vec()),
+            ),
+            Self::Transaction(message, omit) => {
+                (&message.encoded, EncodedCacheKey::Transaction(*omit))
+            }
+            Self::TransactionStatus(message) => {
+                (&message.encoded, EncodedCacheKey::TransactionStatus)
+            }
+            _ => {
+                return FilteredUpdate::Update(SubscribeUpdate {
+                    filters,
+                    update_oneof: Some(self.to_proto(accounts_data_slice)),
+                })
+            }
+        };
+
+        FilteredUpdate::Encoded {
+            filters,
+            update: cache.get_or_encode(key, || self.to_proto(accounts_data_slice)),
+        }
+    }
+
     pub fn to_proto(&self, accounts_data_slice: &[FilterAccountsDataSlice]) -> UpdateOneof {
         match self {
             Self::Slot(message) => UpdateOneof::Slot(SubscribeUpdateSlot {
@@ -414,15 +595,13 @@ impl<'a> MessageRef<'a> {
                 parent: message.parent,
                 status: message.status as i32,
             }),
-            Self::Account(message) => UpdateOneof::Account(SubscribeUpdateAccount {
-                account: Some(message.account.to_proto(accounts_data_slice)),
-                slot: message.slot,
-                is_startup: message.is_startup,
-            }),
-            Self::Transaction(message) => UpdateOneof::Transaction(SubscribeUpdateTransaction {
-                transaction: Some(message.transaction.to_proto()),
-                slot: message.slot,
-            }),
+            Self::Account(message) => UpdateOneof::Account(message.to_proto(accounts_data_slice)),
+            Self::Transaction(message, omit) => {
+                UpdateOneof::Transaction(SubscribeUpdateTransaction {
+                    transaction: Some(message.transaction.to_proto(*omit)),
+                    slot: message.slot,
+                })
+            }
             Self::TransactionStatus(message) => {
                 UpdateOneof::TransactionStatus(SubscribeUpdateTransactionStatus {
                     slot: message.slot,
@@ -439,50 +618,60 @@ impl<'a> MessageRef<'a> {
                 })
             }
             Self::Entry(message) => UpdateOneof::Entry(message.to_proto()),
-            Self::Block(message) => UpdateOneof::Block(SubscribeUpdateBlock {
-                slot: message.slot,
-                blockhash: message.blockhash.clone(),
-                rewards: Some(convert_to::create_rewards_obj(
-                    message.rewards.as_slice(),
-                    None,
-                )),
-                block_time: message.block_time.map(convert_to::create_timestamp),
-                block_height: message.block_height.map(convert_to::create_block_height),
-                parent_slot: message.parent_slot,
-                parent_blockhash: message.parent_blockhash.clone(),
-                executed_transaction_count: message.executed_transaction_count,
-                transactions: message
-                    .transactions
-                    .iter()
-                    .map(|tx| tx.to_proto())
-                    .collect(),
-                updated_account_count: message.updated_account_count,
-                accounts: message
-                    .accounts
-                    .iter()
-                    .map(|acc| acc.to_proto(accounts_data_slice))
-                    .collect(),
-                entries_count: message.entries_count,
-                entries: message
-                    .entries
-                    .iter()
-                    .map(|entry| entry.to_proto())
-                    .collect(),
-            }),
-            Self::BlockMeta(message) => UpdateOneof::BlockMeta(SubscribeUpdateBlockMeta {
-                slot: message.slot,
-                blockhash: message.blockhash.clone(),
-                rewards: Some(convert_to::create_rewards_obj(
-                    message.rewards.as_slice(),
-                    None,
-                )),
-                block_time: message.block_time.map(convert_to::create_timestamp),
-                block_height: message.block_height.map(convert_to::create_block_height),
-                parent_slot: message.parent_slot,
-                parent_blockhash: message.parent_blockhash.clone(),
-                executed_transaction_count: message.executed_transaction_count,
-                entries_count: message.entries_count,
-            }),
+            Self::Block(message) => {
+                let entry_index = message.get_transactions_entry_index();
+                UpdateOneof::Block(SubscribeUpdateBlock {
+                    slot: message.slot,
+                    blockhash: message.blockhash.clone(),
+                    rewards: Some(convert_to::create_rewards_obj(
+                        message.rewards.as_slice(),
+                        None,
+                    )),
+                    block_time: message.block_time.map(convert_to::create_timestamp),
+                    block_height: message.block_height.map(convert_to::create_block_height),
+                    parent_slot: message.parent_slot,
+                    parent_blockhash: message.parent_blockhash.clone(),
+                    executed_transaction_count: message.executed_transaction_count,
+                    transactions: message
+                        .transactions
+                        .iter()
+                        .map(|tx| SubscribeUpdateTransactionInfo {
+                            entry_index: entry_index.get(&tx.index).copied(),
+                            ..tx.to_proto(TransactionMetaOmit::default())
+                        })
+                        .collect(),
+                    updated_account_count: message.updated_account_count,
+                    accounts: message
+                        .accounts
+                        .iter()
+                        .map(|acc| acc.to_proto(accounts_data_slice))
+                        .collect(),
+                    entries_count: message.entries_count,
+                    entries: message
+                        .entries
+                        .iter()
+                        .map(|entry| entry.to_proto())
+                        .collect(),
+                })
+            }
+            Self::BlockMeta(message) => UpdateOneof::BlockMeta(message.to_proto()),
+            Self::BlockFragment(message) => {
+                UpdateOneof::BlockFragment(SubscribeUpdateBlockFragment {
+                    slot: message.slot,
+                    entry: message.entry.map(|entry| entry.to_proto()),
+                    transactions: message
+                        .transactions
+                        .iter()
+                        .map(|tx| tx.to_proto(TransactionMetaOmit::default()))
+                        .collect(),
+                    accounts: message
+                        .accounts
+                        .iter()
+                        .map(|acc| acc.to_proto(accounts_data_slice))
+                        .collect(),
+                    block_meta: message.block_meta.map(|block_meta| block_meta.to_proto()),
+                })
+            }
         }
     }
 }
@@ -534,7 +723,11 @@ impl BlockMetaStorage {
                 let mut storage = storage.write().await;
                 match message {
                     Message::Slot(msg) => {
-                        match msg.status {
+                        let Some(commitment) = msg.status.commitment() else {
+                            continue;
+                        };
+
+                        match commitment {
                             CommitmentLevel::Processed => &mut storage.processed,
                             CommitmentLevel::Confirmed => &mut storage.confirmed,
                             CommitmentLevel::Finalized => &mut storage.finalized,
@@ -551,7 +744,7 @@ impl BlockMetaStorage {
                                 .entry(blockhash)
                                 .or_insert_with(|| BlockhashStatus::new(msg.slot));
 
-                            let status = match msg.status {
+                            let status = match commitment {
                                 CommitmentLevel::Processed => &mut entry.processed,
                                 CommitmentLevel::Confirmed => &mut entry.confirmed,
                                 CommitmentLevel::Finalized => &mut entry.finalized,
@@ -559,7 +752,7 @@ impl BlockMetaStorage {
                             *status = true;
                         }
 
-                        if msg.status == CommitmentLevel::Finalized {
+                        if commitment == CommitmentLevel::Finalized {
                             if let Some(keep_slot) = msg.slot.checked_sub(KEEP_SLOTS) {
                                 storage.blocks.retain(|slot, _block| *slot >= keep_slot);
                             }
@@ -663,20 +856,127 @@ impl BlockMetaStorage {
 
 #[derive(Debug, Default)]
 struct SlotMessages {
+    parent_slot: Option<u64>,
     messages: Vec<Option<Arc<Message>>>, // Option is used for accounts with low write_version
     block_meta: Option<MessageBlockMeta>,
     transactions: Vec<MessageTransactionInfo>,
+    transactions_index: HashMap<usize, usize>, // transaction index in the block to index in `transactions`
     accounts_dedup: HashMap<Pubkey, (u64, usize)>, // (write_version, message_index)
+    accounts_signatures: HashMap<Signature, Vec<usize>>, // message_index of accounts updated by transaction
     entries: Vec<MessageEntry>,
+    fragments_sent: usize, // number of entries sent as block fragments
+    fragments_accounts: HashSet<usize>, // message_index of accounts sent in block fragments
     sealed: bool,
     entries_count: usize,
     confirmed_at: Option<usize>,
     finalized_at: Option<usize>,
+    bytes: usize,
+    evicted: bool,
 }
 
 impl SlotMessages {
-    pub fn try_seal(&mut self) -> Option<Arc<Message>> {
-        if !self.sealed {
+    fn push(&mut self, message: Arc<Message>) {
+        let size = message.size();
+        self.bytes += size;
+        self.messages.push(Some(message));
+        prom::BLOCK_RECONSTRUCTION_MESSAGES.inc();
+        prom::BLOCK_RECONSTRUCTION_BYTES.add(size as i64);
+    }
+
+    // Should be called before removing slot from the buffer
+    fn remove_metrics(&self) {
+        prom::BLOCK_RECONSTRUCTION_MESSAGES.sub(self.messages.len() as i64);
+        prom::BLOCK_RECONSTRUCTION_BYTES.sub(self.bytes as i64);
+    }
+
+    // Drop state used for block reconstruction, block would not be reconstructed,
+    // but buffered messages are still sent for confirmed and finalized commitment levels
+    fn evict(&mut self) {
+        self.transactions = Vec::new();
+        self.transactions_index = HashMap::new();
+        self.accounts_signatures = HashMap::new();
+        self.entries = Vec::new();
+        self.fragments_accounts = HashSet::new();
+        self.evicted = true;
+    }
+
+    fn push_transaction(&mut self, transaction: &MessageTransactionInfo) {
+        self.transactions_index
+            .insert(transaction.index, self.transactions.len());
+        self.transactions.push(transaction.clone());
+    }
+
+    fn push_account(&mut self, account: &MessageAccountInfo, message_index: usize) {
+        if let Some(signature) = account.txn_signature {
+            self.accounts_signatures
+                .entry(signature)
+                .or_default()
+                .push(message_index);
+        }
+    }
+
+    // Create block fragments for entries with all transactions received, in the order of entries
+    fn try_fragments(&mut self) -> Vec<Arc<Message>> {
+        let mut fragments = vec![];
+        while !self.sealed && !self.evicted {
+            let index = self.fragments_sent;
+            let Some(entry) = self
+                .entries
+                .get(index)
+                .filter(|entry| entry.index == index)
+                .or_else(|| self.entries.iter().find(|entry| entry.index == index))
+                .cloned()
+            else {
+                break;
+            };
+
+            let start = entry.starting_transaction_index as usize;
+            let end = start + entry.executed_transaction_count as usize;
+            let Some(transactions) = (start..end)
+                .map(|tx_index| {
+                    self.transactions_index
+                        .get(&tx_index)
+                        .map(|index| self.transactions[*index].clone())
+                })
+                .collect::<Option<Vec<_>>>()
+            else {
+                break;
+            };
+
+            let mut accounts = vec![];
+            for transaction in transactions.iter() {
+                for message_index in self
+                    .accounts_signatures
+                    .get(&transaction.signature)
+                    .into_iter()
+                    .flatten()
+                {
+                    if let Some(Some(message)) = self.messages.get(*message_index) {
+                        if let Message::Account(msg) = message.as_ref() {
+                            accounts.push(msg.account.clone());
+                            self.fragments_accounts.insert(*message_index);
+                        }
+                    }
+                }
+            }
+
+            let message = Arc::new(Message::BlockFragment(MessageBlockFragment {
+                slot: entry.slot,
+                entry: Some(entry),
+                transactions,
+                accounts,
+                block_meta: None,
+            }));
+            self.push(Arc::clone(&message));
+            fragments.push(message);
+            self.fragments_sent += 1;
+        }
+        fragments
+    }
+
+    // Returns the final block fragment (if fragments are requested) and block message
+    pub fn try_seal(&mut self, fragments: bool) -> Vec<Arc<Message>> {
+        if !self.sealed && !self.evicted {
             if let Some(block_meta) = &self.block_meta {
                 let executed_transaction_count = block_meta.executed_transaction_count as usize;
                 let entries_count = block_meta.entries_count as usize;
@@ -693,37 +993,261 @@ impl SlotMessages {
                     }
 
                     let mut accounts = Vec::with_capacity(self.messages.len());
-                    for item in self.messages.iter().flatten() {
-                        if let Message::Account(account) = item.as_ref() {
+                    let mut fragment_accounts = vec![];
+                    for (index, item) in self.messages.iter().enumerate() {
+                        if let Some(Message::Account(account)) = item.as_deref() {
                             accounts.push(account.account.clone());
+                            if fragments && !self.fragments_accounts.contains(&index) {
+                                fragment_accounts.push(account.account.clone());
+                            }
                         }
                     }
 
+                    let block_meta = block_meta.clone();
+                    let mut messages = Vec::with_capacity(2);
+                    if fragments {
+                        let fragment = Arc::new(Message::BlockFragment(MessageBlockFragment {
+                            slot: block_meta.slot,
+                            entry: None,
+                            transactions: vec![],
+                            accounts: fragment_accounts,
+                            block_meta: Some(block_meta.clone()),
+                        }));
+                        self.push(Arc::clone(&fragment));
+                        messages.push(fragment);
+                    }
+
                     let message = Arc::new(Message::Block(
-                        (block_meta.clone(), transactions, accounts, entries).into(),
+                        (block_meta, transactions, accounts, entries).into(),
                     ));
-                    self.messages.push(Some(Arc::clone(&message)));
+                    self.push(Arc::clone(&message));
+                    messages.push(message);
 
                     self.sealed = true;
                     self.entries_count = entries_count;
-                    return Some(message);
+                    return messages;
                 }
             }
         }
 
-        None
+        vec![]
+    }
+}
+
+#[derive(Debug, Default)]
+struct ReplayStoredSlots {
+    max_slots: usize,
+    processed: BTreeMap<u64, Vec<Arc<Message>>>,
+    confirmed: BTreeMap<u64, Vec<Arc<Message>>>,
+    finalized: BTreeMap<u64, Vec<Arc<Message>>>,
+}
+
+impl ReplayStoredSlots {
+    fn new(max_slots: usize) -> Self {
+        Self {
+            max_slots,
+            ..Default::default()
+        }
+    }
+
+    const fn get_slots(&self, commitment: CommitmentLevel) -> &BTreeMap<u64, Vec<Arc<Message>>> {
+        match commitment {
+            CommitmentLevel::Processed => &self.processed,
+            CommitmentLevel::Confirmed => &self.confirmed,
+            CommitmentLevel::Finalized => &self.finalized,
+        }
+    }
+
+    fn push(&mut self, commitment: CommitmentLevel, messages: &[Arc<Message>]) {
+        if self.max_slots == 0 {
+            return;
+        }
+
+        let slots = match commitment {
+            CommitmentLevel::Processed => &mut self.processed,
+            CommitmentLevel::Confirmed => &mut self.confirmed,
+            CommitmentLevel::Finalized => &mut self.finalized,
+        };
+        for message in messages {
+            slots
+                .entry(message.get_slot())
+                .or_default()
+                .push(Arc::clone(message));
+        }
+        while slots.len() > self.max_slots {
+            slots.pop_first();
+        }
+    }
+
+    // Messages are grouped by slot, on error the oldest stored slot is returned
+    fn get_messages(
+        &self,
+        commitment: CommitmentLevel,
+        from_slot: u64,
+    ) -> Result<Vec<Arc<Message>>, Option<u64>> {
+        let slots = self.get_slots(commitment);
+        match slots.keys().next().copied() {
+            Some(oldest_slot) if oldest_slot <= from_slot => Ok(slots
+                .range(from_slot..)
+                .flat_map(|(_slot, messages)| messages.iter().cloned())
+                .collect()),
+            oldest_slot => Err(oldest_slot),
+        }
+    }
+}
+
+#[derive(Debug)]
+struct ClientQueue {
+    capacity: usize,
+    gap: Option<(u64, u64)>,
+    slots: VecDeque<(u64, TonicResult<FilteredUpdate>)>,
+    updates: VecDeque<(u64, TonicResult<FilteredUpdate>)>,
+}
+
+impl ClientQueue {
+    const fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            gap: None,
+            slots: VecDeque::new(),
+            updates: VecDeque::new(),
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.gap.is_none() && self.slots.is_empty() && self.updates.is_empty()
+    }
+
+    // With `HoldProcessed` slot status is not blocked by queued updates,
+    // so slot status can be sent before accounts and transactions of the slot
+    fn can_send(&self, policy: BackpressurePolicy, is_slot: bool) -> bool {
+        self.gap.is_none()
+            && self.slots.is_empty()
+            && (self.updates.is_empty() || (is_slot && policy == BackpressurePolicy::HoldProcessed))
+    }
+
+    fn add_gap(&mut self, from_slot: u64, to_slot: u64) {
+        self.gap = Some(match self.gap {
+            Some((from, to)) => (from.min(from_slot), to.max(to_slot)),
+            None => (from_slot, to_slot),
+        });
+    }
+
+    // Returns `false` if client should be disconnected, with `DropOldest` and `HoldProcessed`
+    // the oldest updates are dropped on full queue (accounts and transactions before slot status)
+    fn push(
+        &mut self,
+        policy: BackpressurePolicy,
+        slot: u64,
+        is_slot: bool,
+        update: TonicResult<FilteredUpdate>,
+    ) -> bool {
+        if policy == BackpressurePolicy::Disconnect {
+            return false;
+        }
+
+        if is_slot && policy == BackpressurePolicy::HoldProcessed {
+            self.slots.push_back((slot, update));
+        } else {
+            self.updates.push_back((slot, update));
+        }
+
+        while self.slots.len() + self.updates.len() > self.capacity {
+            if let Some((slot, _update)) =
+                self.updates.pop_front().or_else(|| self.slots.pop_front())
+            {
+                self.add_gap(slot, slot);
+            }
+        }
+
+        true
+    }
+
+    fn pop(&mut self) -> Option<TonicResult<FilteredUpdate>> {
+        if let Some((from_slot, to_slot)) = self.gap.take() {
+            return Some(Ok(SubscribeUpdate {
+                filters: vec![],
+                update_oneof: Some(UpdateOneof::Gap(SubscribeUpdateGap { from_slot, to_slot })),
+            }
+            .into()));
+        }
+
+        self.slots
+            .pop_front()
+            .or_else(|| self.updates.pop_front())
+            .map(|(_slot, update)| update)
+    }
+}
+
+/// Accounts and transactions sent to the client on processed commitment,
+/// kept until the slot is finalized to notify the client about dead and abandoned slots
+#[derive(Debug, Default)]
+struct ClientRollback {
+    slots: BTreeMap<u64, SubscribeUpdateRollback>,
+}
+
+impl ClientRollback {
+    /// Max number of not finalized slots, oldest slots are removed if finalized slot is not received
+    const SLOTS_MAX: usize = 256;
+
+    fn push(&mut self, message: &Message) {
+        match message {
+            Message::Account(msg) => {
+                self.slots.entry(msg.slot).or_default().accounts.push(
+                    SubscribeUpdateRollbackAccount {
+                        pubkey: msg.account.pubkey.as_ref().into(),
+                        write_version: msg.account.write_version,
+                    },
+                );
+            }
+            Message::Transaction(msg) => {
+                self.slots
+                    .entry(msg.slot)
+                    .or_default()
+                    .transactions
+                    .push(msg.transaction.signature.as_ref().into());
+            }
+            _ => {}
+        }
+        while self.slots.len() > Self::SLOTS_MAX {
+            self.slots.pop_first();
+        }
+    }
+
+    // Returns rollback update if slot is dead or abandoned
+    fn update_slot(&mut self, message: &MessageSlot) -> Option<FilteredUpdate> {
+        match message.status {
+            SlotStatus::SlotFinalized => {
+                self.slots = self.slots.split_off(&(message.slot + 1));
+                None
+            }
+            SlotStatus::SlotDead | SlotStatus::SlotAbandoned => {
+                self.slots.remove(&message.slot).map(|mut update| {
+                    update.slot = message.slot;
+                    update.status = message.status as i32;
+                    SubscribeUpdate {
+                        filters: vec![],
+                        update_oneof: Some(UpdateOneof::Rollback(update)),
+                    }
+                    .into()
+                })
+            }
+            SlotStatus::SlotProcessed | SlotStatus::SlotConfirmed => None,
+        }
     }
 }
 
 #[derive(Debug)]
 pub struct GrpcService {
-    config_snapshot_client_channel_capacity: usize,
-    config_channel_capacity: usize,
-    config_filters: Arc<ConfigGrpcFilters>,
+    config_rx: watch::Receiver<Arc<ConfigGrpc>>,
+    tenants_usage: Arc<TenantsUsage>,
     blocks_meta: Option<BlockMetaStorage>,
+    accounts: Option<Arc<AccountsStorage>>,
     subscribe_id: AtomicUsize,
-    snapshot_rx: Mutex<Option<crossbeam_channel::Receiver<Option<Message>>>>,
-    broadcast_tx: broadcast::Sender<(CommitmentLevel, Arc<Vec<Arc<Message>>>)>,
+    blocks_fragments_subscriptions: Arc<AtomicUsize>,
+    snapshot: Option<Arc<Snapshot>>,
+    broadcast_tx: broadcast::Sender<BroadcastedMessage>,
+    replay_stored_slots_tx: Option<mpsc::Sender<ReplayStoredSlotsRequest>>,
     debug_clients_tx: Option<mpsc::UnboundedSender<DebugClientMessage>>,
 }
 
@@ -738,6 +1262,7 @@ impl GrpcService {
         Option<crossbeam_channel::Sender<Option<Message>>>,
         mpsc::UnboundedSender<Arc<Message>>,
         Arc<Notify>,
+        watch::Sender<Arc<ConfigGrpc>>,
     )> {
         // Bind service address
         let incoming = TcpIncoming::new(
@@ -746,6 +1271,11 @@ impl GrpcService {
             Some(Duration::from_secs(20)), // tcp_keepalive
         )
         .map_err(|error| anyhow::anyhow!(error))?;
+        let listeners_incoming = config
+            .listeners
+            .iter()
+            .map(listener::bind)
+            .collect::<anyhow::Result<Vec<_>>>()?;
 
         // Snapshot channel
         let (snapshot_tx, snapshot_rx) = match config.snapshot_plugin_channel_capacity {
@@ -765,44 +1295,74 @@ impl GrpcService {
             (Some(blocks_meta), Some(blocks_meta_tx))
         };
 
+        // Accounts cache
+        let (accounts, accounts_tx) = match &config.accounts_cache {
+            Some(accounts_cache) if !config.unary_disabled => {
+                let (accounts, accounts_tx) =
+                    AccountsStorage::new(accounts_cache.clone(), config.unary_concurrency_limit);
+                (Some(Arc::new(accounts)), Some(accounts_tx))
+            }
+            _ => (None, None),
+        };
+
         // Messages to clients combined by commitment
         let (broadcast_tx, _) = broadcast::channel(config.channel_capacity);
 
-        // gRPC server builder with optional TLS
-        let mut server_builder = Server::builder();
-        if let Some(tls_config) = &config.tls_config {
-            let (cert, key) = tokio::try_join!(
-                fs::read(&tls_config.cert_path),
-                fs::read(&tls_config.key_path)
-            )
-            .context("failed to load tls_config files")?;
-            server_builder = server_builder
-                .tls_config(ServerTlsConfig::new().identity(Identity::from_pem(cert, key)))
-                .context("failed to apply tls_config")?;
-        }
+        // Requests for replay of stored slots
+        let (replay_stored_slots_tx, replay_stored_slots_rx) = mpsc::channel(16);
+        let replay_stored_slots_tx =
+            (config.replay_stored_slots > 0).then_some(replay_stored_slots_tx);
+
+        // TLS acceptor with certificate hot-rotation
+        let tls_acceptor = match &config.tls_config {
+            Some(tls_config) => Some(tls::TlsAcceptor::new(tls_config).await?),
+            None => None,
+        };
+
+        // Number of subscriptions with block fragments, fragments are not built without them
+        let blocks_fragments_subscriptions = Arc::new(AtomicUsize::new(0));
+
+        // Config which can be updated without restart
+        let config = Arc::new(config);
+        let (config_tx, config_rx) = watch::channel(Arc::clone(&config));
 
         // Create Server
         let max_decoding_message_size = config.max_decoding_message_size;
         let mut service = GeyserServer::new(Self {
-            config_snapshot_client_channel_capacity: config.snapshot_client_channel_capacity,
-            config_channel_capacity: config.channel_capacity,
-            config_filters: Arc::new(config.filters),
+            config_rx: config_rx.clone(),
+            tenants_usage: Arc::new(TenantsUsage::default()),
             blocks_meta,
+            accounts,
             subscribe_id: AtomicUsize::new(0),
-            snapshot_rx: Mutex::new(snapshot_rx),
+            blocks_fragments_subscriptions: Arc::clone(&blocks_fragments_subscriptions),
+            snapshot: snapshot_rx.map(|snapshot_rx| {
+                Snapshot::new(
+                    snapshot_rx,
+                    Duration::from_millis(config.snapshot_retention_ms as u64),
+                    config.snapshot_bytes_max,
+                    config.snapshot_accounts.clone(),
+                )
+            }),
             broadcast_tx: broadcast_tx.clone(),
+            replay_stored_slots_tx,
             debug_clients_tx,
         })
         .max_decoding_message_size(max_decoding_message_size);
-        for encoding in config.compression.accept {
+        for encoding in config.compression.accept.iter().copied() {
             service = service.accept_compressed(encoding);
         }
-        for encoding in config.compression.send {
-            service = service.send_compressed(encoding);
-        }
+        // Responses are compressed by the layer, tonic supports only gzip with default level
+        let compression_layer = SendCompressionLayer::new(SendCompressionConfig {
+            encodings: config.compression.send.clone(),
+            gzip_level: config.compression.gzip_level,
+            zstd_level: config.compression.zstd_level,
+            metrics: Some(prom::update_compression),
+        });
 
         // Run geyser message loop
         let (messages_tx, messages_rx) = mpsc::unbounded_channel();
+        let replay_stored_slots = config.replay_stored_slots;
+        let block_reconstruction = config.block_reconstruction;
         spawn_blocking(move || {
             Builder::new_multi_thread()
                 .thread_name_fn(crate::get_thread_name)
@@ -813,8 +1373,13 @@ impl GrpcService {
                 .block_on(Self::geyser_loop(
                     messages_rx,
                     blocks_meta_tx,
+                    accounts_tx,
                     broadcast_tx,
+                    ReplayStoredSlots::new(replay_stored_slots),
+                    replay_stored_slots_rx,
+                    block_reconstruction,
                     block_fail_action,
+                    blocks_fragments_subscriptions,
                 ));
         });
 
@@ -826,32 +1391,182 @@ impl GrpcService {
             let (mut health_reporter, health_service) = health_reporter();
             health_reporter.set_serving::<GeyserServer<Self>>().await;
 
-            server_builder
-                .http2_keepalive_interval(Some(Duration::from_secs(5)))
-                .layer(interceptor(move |request: Request<()>| {
-                    if let Some(x_token) = &config.x_token {
-                        match request.metadata().get("x-token") {
-                            Some(token) if x_token == token => Ok(request),
-                            _ => Err(Status::unauthenticated("No valid auth token")),
+            let create_router = |server_builder: Server| {
+                let config_rx = config_rx.clone();
+                server_builder
+                    .http2_keepalive_interval(Some(Duration::from_secs(5)))
+                    .layer(compression_layer.clone())
+                    .layer(interceptor(move |mut request: Request<()>| {
+                        let config = Arc::clone(&config_rx.borrow());
+                        let identities = tls::get_client_identities(&request);
+                        match Tenant::resolve(
+                            &config,
+                            request.metadata().get("x-token"),
+                            identities,
+                        ) {
+                            Some(tenant) => {
+                                request.extensions_mut().insert(tenant);
+                                Ok(request)
+                            }
+                            None => Err(Status::unauthenticated("No valid auth token")),
                         }
-                    } else {
-                        Ok(request)
+                    }))
+                    .add_service(health_service.clone())
+                    .add_service(service.clone())
+            };
+
+            // Additional listeners and TLS acceptor are stopped with the main listener
+            let (shutdown_tx, shutdown_rx) = watch::channel(());
+            let shutdown = async move {
+                shutdown_grpc.notified().await;
+                drop(shutdown_tx);
+            };
+            for incoming in listeners_incoming {
+                let mut shutdown_rx = shutdown_rx.clone();
+                let server = create_router(Server::builder()).serve_with_incoming_shutdown(
+                    incoming,
+                    async move {
+                        let _ = shutdown_rx.changed().await;
+                    },
+                );
+                tokio::spawn(async move {
+                    if let Err(error) = server.await {
+                        error!("failed to serve additional listener: {error}");
                     }
-                }))
-                .add_service(health_service)
-                .add_service(service)
-                .serve_with_incoming_shutdown(incoming, shutdown_grpc.notified())
-                .await
+                });
+            }
+
+            let router = create_router(Server::builder());
+            match tls_acceptor {
+                Some(tls_acceptor) => {
+                    router
+                        .serve_with_incoming_shutdown(
+                            tls_acceptor.incoming(incoming, shutdown_rx),
+                            shutdown,
+                        )
+                        .await
+                }
+                None => {
+                    router
+                        .serve_with_incoming_shutdown(incoming, shutdown)
+                        .await
+                }
+            }
         });
 
-        Ok((snapshot_tx, messages_tx, shutdown))
+        Ok((snapshot_tx, messages_tx, shutdown, config_tx))
+    }
+
+    fn check_unary<T>(&self, request: &Request<T>) -> Result<Tenant, Status> {
+        let tenant = Tenant::from_request(request)?;
+        self.tenants_usage.unary(&tenant)?;
+        Ok(tenant)
+    }
+
+    fn check_unary_commitment(
+        tenant: &Tenant,
+        commitment: Option<i32>,
+    ) -> Result<CommitmentLevel, Status> {
+        let commitment = BlockMetaStorage::parse_commitment(commitment)?;
+        if tenant.is_commitment_allowed(commitment) {
+            Ok(commitment)
+        } else {
+            Err(Status::permission_denied(format!(
+                "commitment {} is not allowed",
+                commitment.as_str_name()
+            )))
+        }
+    }
+
+    fn create_filter(tenant: &Tenant, request: &SubscribeRequest) -> anyhow::Result<Filter> {
+        let filter = Filter::new(request, tenant.filters())?;
+        let commitment = filter.get_commitment_level();
+        anyhow::ensure!(
+            filter.get_pong_msg().is_some() || tenant.is_commitment_allowed(commitment),
+            "commitment {} is not allowed",
+            commitment.as_str_name()
+        );
+        Ok(filter)
+    }
+
+    fn update_blocks_fragments_subscriptions(
+        subscriptions: &AtomicUsize,
+        old: Option<&Filter>,
+        new: Option<&Filter>,
+    ) {
+        if old.is_some_and(Filter::has_blocks_fragments) {
+            subscriptions.fetch_sub(1, Ordering::Relaxed);
+        }
+        if new.is_some_and(Filter::has_blocks_fragments) {
+            subscriptions.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    fn broadcast(
+        broadcast_tx: &broadcast::Sender<BroadcastedMessage>,
+        replay_stored_slots: &mut ReplayStoredSlots,
+        commitment: CommitmentLevel,
+        messages: Vec<Arc<Message>>,
+    ) {
+        replay_stored_slots.push(commitment, &messages);
+        let _ = broadcast_tx.send((commitment, messages.into()));
     }
 
+    /// Slots processed after previous finalized slot which are not ancestors of new finalized slot.
+    /// Slots without `BlockMeta` were not completed by the validator and reported as dead.
+    fn get_abandoned_slots(
+        messages: &BTreeMap<u64, SlotMessages>,
+        finalized_slot: u64,
+        finalized_last_slot: Option<u64>,
+    ) -> Vec<Message> {
+        let mut ancestors = HashSet::new();
+        let mut slot = finalized_slot;
+        let lowest_slot = loop {
+            ancestors.insert(slot);
+            match messages
+                .get(&slot)
+                .and_then(|slot_messages| slot_messages.parent_slot)
+            {
+                // Previous finalized slot reached, all slots after it can be checked
+                Some(parent) if matches!(finalized_last_slot, Some(last) if parent <= last) => {
+                    break parent
+                }
+                Some(parent) => slot = parent,
+                // Chain is not known (startup), check only slots after known part
+                None => break slot,
+            }
+        };
+
+        messages
+            .range(lowest_slot + 1..finalized_slot)
+            .filter(|(slot, slot_messages)| {
+                !ancestors.contains(slot) && slot_messages.parent_slot.is_some()
+            })
+            .map(|(slot, slot_messages)| {
+                Message::Slot(MessageSlot {
+                    slot: *slot,
+                    parent: slot_messages.parent_slot,
+                    status: if slot_messages.block_meta.is_some() {
+                        SlotStatus::SlotAbandoned
+                    } else {
+                        SlotStatus::SlotDead
+                    },
+                })
+            })
+            .collect()
+    }
+
+    #[allow(clippy::too_many_arguments)]
     async fn geyser_loop(
         mut messages_rx: mpsc::UnboundedReceiver<Arc<Message>>,
         blocks_meta_tx: Option<mpsc::UnboundedSender<Message>>,
-        broadcast_tx: broadcast::Sender<(CommitmentLevel, Arc<Vec<Arc<Message>>>)>,
+        accounts_tx: Option<mpsc::UnboundedSender<AccountsStorageMessage>>,
+        broadcast_tx: broadcast::Sender<BroadcastedMessage>,
+        mut replay_stored_slots: ReplayStoredSlots,
+        mut replay_stored_slots_rx: mpsc::Receiver<ReplayStoredSlotsRequest>,
+        block_reconstruction: ConfigGrpcBlockReconstruction,
         block_fail_action: ConfigBlockFailAction,
+        blocks_fragments_subscriptions: Arc<AtomicUsize>,
     ) {
         const PROCESSED_MESSAGES_MAX: usize = 31;
         const PROCESSED_MESSAGES_SLEEP: Duration = Duration::from_millis(10);
@@ -859,6 +1574,8 @@ impl GrpcService {
         let mut messages: BTreeMap<u64, SlotMessages> = Default::default();
         let mut processed_messages = Vec::with_capacity(PROCESSED_MESSAGES_MAX);
         let mut processed_first_slot = None;
+        let mut finalized_last_slot = None;
+        let mut slots_max_evicted = None;
         let processed_sleep = sleep(PROCESSED_MESSAGES_SLEEP);
         tokio::pin!(processed_sleep);
 
@@ -869,7 +1586,9 @@ impl GrpcService {
 
                     // Update metrics
                     if let Message::Slot(slot_message) = message.as_ref() {
-                        prom::update_slot_plugin_status(slot_message.status, slot_message.slot);
+                        if let Some(commitment) = slot_message.status.commitment() {
+                            prom::update_slot_plugin_status(commitment, slot_message.slot);
+                        }
                     }
 
                     // Update blocks info
@@ -879,27 +1598,44 @@ impl GrpcService {
                         }
                     }
 
+                    // Update accounts cache, startup accounts are sent only for cache seeding
+                    if let Some(accounts_tx) = &accounts_tx {
+                        if matches!(message.as_ref(), Message::Slot(_) | Message::Account(_)) {
+                            let _ = accounts_tx.send(AccountsStorageMessage::Message(Arc::clone(&message)));
+                        }
+                    }
+                    if matches!(message.as_ref(), Message::Account(msg) if msg.is_startup) {
+                        continue;
+                    }
+
                     // Remove outdated block reconstruction info
                     match message.as_ref() {
                         // On startup we can receive few Confirmed/Finalized slots without BlockMeta message
                         // With saved first Processed slot we can ignore errors caused by startup process
-                        Message::Slot(msg) if processed_first_slot.is_none() && msg.status == CommitmentLevel::Processed => {
+                        Message::Slot(msg) if processed_first_slot.is_none() && msg.status == SlotStatus::SlotProcessed => {
                             processed_first_slot = Some(msg.slot);
                         }
-                        Message::Slot(msg) if msg.status == CommitmentLevel::Finalized => {
+                        Message::Slot(msg) if msg.status == SlotStatus::SlotFinalized => {
+                            // Notify about processed slots which are not on the finalized fork
+                            for message in Self::get_abandoned_slots(&messages, msg.slot, finalized_last_slot) {
+                                processed_messages.push(Arc::new(message));
+                            }
+                            finalized_last_slot = Some(msg.slot);
+
                             // keep extra 10 slots
                             if let Some(msg_slot) = msg.slot.checked_sub(10) {
                                 loop {
                                     match messages.keys().next().cloned() {
                                         Some(slot) if slot < msg_slot => {
                                             if let Some(slot_messages) = messages.remove(&slot) {
+                                                slot_messages.remove_metrics();
                                                 match processed_first_slot {
                                                     Some(processed_first) if slot <= processed_first => continue,
                                                     None => continue,
                                                     _ => {}
                                                 }
 
-                                                if !slot_messages.sealed && slot_messages.finalized_at.is_some() {
+                                                if !slot_messages.sealed && !slot_messages.evicted && slot_messages.finalized_at.is_some() {
                                                     let mut reasons = vec![];
                                                     if let Some(block_meta) = slot_messages.block_meta {
                                                         let block_txn_count = block_meta.executed_transaction_count as usize;
@@ -939,79 +1675,117 @@ impl GrpcService {
                         _ => {}
                     }
 
-                    // Update block reconstruction info
-                    let slot_messages = messages.entry(message.get_slot()).or_default();
-                    if !matches!(message.as_ref(), Message::Slot(_)) {
-                        slot_messages.messages.push(Some(Arc::clone(&message)));
-
-                        // If we already build Block message, new message will be a problem
-                        if slot_messages.sealed && !(matches!(message.as_ref(), Message::Entry(_)) && slot_messages.entries_count == 0) {
-                            prom::update_invalid_blocks(format!("unexpected message {}", message.kind()));
-                            match block_fail_action {
-                                ConfigBlockFailAction::Log => {
-                                    error!("unexpected message #{} -- {} (invalid order)", message.get_slot(), message.kind());
-                                }
-                                ConfigBlockFailAction::Panic => {
-                                    panic!("unexpected message #{} -- {} (invalid order)", message.get_slot(), message.kind());
-                                }
-                            }
+                    // Update block reconstruction info, slots removed by `slots_max` are not created again
+                    let slot = message.get_slot();
+                    let slot_messages = if matches!(slots_max_evicted, Some(evicted) if slot <= evicted) {
+                        messages.get_mut(&slot)
+                    } else {
+                        Some(messages.entry(slot).or_default())
+                    };
+                    let mut block_messages = vec![];
+                    let fragments = blocks_fragments_subscriptions.load(Ordering::Relaxed) > 0;
+                    if let Some(slot_messages) = slot_messages {
+                        if let Message::Slot(MessageSlot { parent: Some(parent), .. }) = message.as_ref() {
+                            slot_messages.parent_slot = Some(*parent);
                         }
-                    }
-                    let mut sealed_block_msg = None;
-                    match message.as_ref() {
-                        Message::BlockMeta(msg) => {
-                            if slot_messages.block_meta.is_some() {
-                                prom::update_invalid_blocks("unexpected message: BlockMeta (duplicate)");
+                        if !matches!(message.as_ref(), Message::Slot(_)) {
+                            slot_messages.push(Arc::clone(&message));
+
+                            // If we already build Block message, new message will be a problem
+                            if slot_messages.sealed && !(matches!(message.as_ref(), Message::Entry(_)) && slot_messages.entries_count == 0) {
+                                prom::update_invalid_blocks(format!("unexpected message {}", message.kind()));
                                 match block_fail_action {
                                     ConfigBlockFailAction::Log => {
-                                        error!("unexpected message #{} -- BlockMeta (duplicate)", message.get_slot());
+                                        error!("unexpected message #{} -- {} (invalid order)", message.get_slot(), message.kind());
                                     }
                                     ConfigBlockFailAction::Panic => {
-                                        panic!("unexpected message #{} -- BlockMeta (duplicate)", message.get_slot());
+                                        panic!("unexpected message #{} -- {} (invalid order)", message.get_slot(), message.kind());
                                     }
                                 }
                             }
-                            slot_messages.block_meta = Some(msg.clone());
-                            sealed_block_msg = slot_messages.try_seal();
-                        }
-                        Message::Transaction(msg) => {
-                            slot_messages.transactions.push(msg.transaction.clone());
-                            sealed_block_msg = slot_messages.try_seal();
                         }
-                        // Dedup accounts by max write_version
-                        Message::Account(msg) => {
-                            let write_version = msg.account.write_version;
-                            let msg_index = slot_messages.messages.len() - 1;
-                            if let Some(entry) = slot_messages.accounts_dedup.get_mut(&msg.account.pubkey) {
-                                if entry.0 < write_version {
-                                    // We can replace the message, but in this case we will lose the order
-                                    slot_messages.messages[entry.1] = None;
-                                    *entry = (write_version, msg_index);
+                        match message.as_ref() {
+                            Message::BlockMeta(msg) => {
+                                if slot_messages.block_meta.is_some() {
+                                    prom::update_invalid_blocks("unexpected message: BlockMeta (duplicate)");
+                                    match block_fail_action {
+                                        ConfigBlockFailAction::Log => {
+                                            error!("unexpected message #{} -- BlockMeta (duplicate)", message.get_slot());
+                                        }
+                                        ConfigBlockFailAction::Panic => {
+                                            panic!("unexpected message #{} -- BlockMeta (duplicate)", message.get_slot());
+                                        }
+                                    }
                                 }
-                            } else {
-                                slot_messages.accounts_dedup.insert(msg.account.pubkey, (write_version, msg_index));
+                                slot_messages.block_meta = Some(msg.clone());
+                                block_messages = slot_messages.try_seal(fragments);
                             }
+                            // Dedup accounts by max write_version
+                            Message::Account(msg) => {
+                                let write_version = msg.account.write_version;
+                                let msg_index = slot_messages.messages.len() - 1;
+                                if let Some(entry) = slot_messages.accounts_dedup.get_mut(&msg.account.pubkey) {
+                                    if entry.0 < write_version {
+                                        // We can replace the message, but in this case we will lose the order
+                                        slot_messages.messages[entry.1] = None;
+                                        *entry = (write_version, msg_index);
+                                    }
+                                } else {
+                                    slot_messages.accounts_dedup.insert(msg.account.pubkey, (write_version, msg_index));
+                                }
+                                if !slot_messages.evicted {
+                                    slot_messages.push_account(&msg.account, msg_index);
+                                }
+                            }
+                            // Block is not reconstructed after eviction
+                            _ if slot_messages.evicted => {}
+                            Message::Transaction(msg) => {
+                                slot_messages.push_transaction(&msg.transaction);
+                                if fragments {
+                                    block_messages = slot_messages.try_fragments();
+                                }
+                                block_messages.extend(slot_messages.try_seal(fragments));
+                            }
+                            Message::Entry(msg) => {
+                                slot_messages.entries.push(msg.clone());
+                                if fragments {
+                                    block_messages = slot_messages.try_fragments();
+                                }
+                                block_messages.extend(slot_messages.try_seal(fragments));
+                            }
+                            _ => {}
                         }
-                        Message::Entry(msg) => {
-                            slot_messages.entries.push(msg.clone());
-                            sealed_block_msg = slot_messages.try_seal();
+
+                        // Limit memory used for block reconstruction
+                        if !slot_messages.evicted && slot_messages.bytes > block_reconstruction.slot_bytes_max {
+                            slot_messages.evict();
+                            prom::update_invalid_blocks("evicted: slot_bytes_max");
+                            error!("evicted #{slot} -- slot_bytes_max limit reached");
+                        }
+                    }
+                    while messages.len() > block_reconstruction.slots_max {
+                        if let Some((slot, slot_messages)) = messages.pop_first() {
+                            slot_messages.remove_metrics();
+                            slots_max_evicted = Some(slot);
+                            // block is already sent or slot is evicted by `slot_bytes_max`
+                            if !slot_messages.sealed && !slot_messages.evicted {
+                                prom::update_invalid_blocks("evicted: slots_max");
+                                error!("evicted #{slot} -- slots_max limit reached");
+                            }
                         }
-                        _ => {}
                     }
 
                     // Send messages to filter (and to clients)
                     let mut messages_vec = vec![message];
-                    if let Some(sealed_block_msg) = sealed_block_msg {
-                        messages_vec.push(sealed_block_msg);
-                    }
+                    messages_vec.extend(block_messages);
 
                     for message in messages_vec {
                         if let Message::Slot(slot) = message.as_ref() {
                             let (mut confirmed_messages, mut finalized_messages) = match slot.status {
-                                CommitmentLevel::Processed => {
+                                SlotStatus::SlotProcessed | SlotStatus::SlotDead | SlotStatus::SlotAbandoned => {
                                     (Vec::with_capacity(1), Vec::with_capacity(1))
                                 }
-                                CommitmentLevel::Confirmed => {
+                                SlotStatus::SlotConfirmed => {
                                     if let Some(slot_messages) = messages.get_mut(&slot.slot) {
                                         if !slot_messages.sealed {
                                             slot_messages.confirmed_at = Some(slot_messages.messages.len());
@@ -1024,7 +1798,7 @@ impl GrpcService {
                                         .unwrap_or_default();
                                     (vec, Vec::with_capacity(1))
                                 }
-                                CommitmentLevel::Finalized => {
+                                SlotStatus::SlotFinalized => {
                                     if let Some(slot_messages) = messages.get_mut(&slot.slot) {
                                         if !slot_messages.sealed {
                                             slot_messages.finalized_at = Some(slot_messages.messages.len());
@@ -1041,8 +1815,7 @@ impl GrpcService {
 
                             // processed
                             processed_messages.push(Arc::clone(&message));
-                            let _ =
-                                broadcast_tx.send((CommitmentLevel::Processed, processed_messages.into()));
+                            Self::broadcast(&broadcast_tx, &mut replay_stored_slots, CommitmentLevel::Processed, processed_messages);
                             processed_messages = Vec::with_capacity(PROCESSED_MESSAGES_MAX);
                             processed_sleep
                                 .as_mut()
@@ -1050,27 +1823,30 @@ impl GrpcService {
 
                             // confirmed
                             confirmed_messages.push(Arc::clone(&message));
-                            let _ =
-                                broadcast_tx.send((CommitmentLevel::Confirmed, confirmed_messages.into()));
+                            Self::broadcast(&broadcast_tx, &mut replay_stored_slots, CommitmentLevel::Confirmed, confirmed_messages);
 
                             // finalized
                             finalized_messages.push(message);
-                            let _ =
-                                broadcast_tx.send((CommitmentLevel::Finalized, finalized_messages.into()));
+                            Self::broadcast(&broadcast_tx, &mut replay_stored_slots, CommitmentLevel::Finalized, finalized_messages);
                         } else {
                             let mut confirmed_messages = vec![];
                             let mut finalized_messages = vec![];
-                            if matches!(message.as_ref(), Message::Block(_)) {
-                                if let Some(slot_messages) = messages.get(&message.get_slot()) {
+                            if let Some(slot_messages) = messages.get_mut(&message.get_slot()) {
+                                // Block is not reconstructed for evicted slot, messages received after
+                                // slot status are sent without waiting for the block
+                                if matches!(message.as_ref(), Message::Block(_)) || slot_messages.evicted {
+                                    let len = slot_messages.messages.len();
                                     if let Some(confirmed_at) = slot_messages.confirmed_at {
                                         confirmed_messages.extend(
                                             slot_messages.messages.as_slice()[confirmed_at..].iter().filter_map(|x| x.clone())
                                         );
+                                        slot_messages.confirmed_at = Some(len);
                                     }
                                     if let Some(finalized_at) = slot_messages.finalized_at {
                                         finalized_messages.extend(
                                             slot_messages.messages.as_slice()[finalized_at..].iter().filter_map(|x| x.clone())
                                         );
+                                        slot_messages.finalized_at = Some(len);
                                     }
                                 }
                             }
@@ -1080,8 +1856,7 @@ impl GrpcService {
                                 || !confirmed_messages.is_empty()
                                 || !finalized_messages.is_empty()
                             {
-                                let _ = broadcast_tx
-                                    .send((CommitmentLevel::Processed, processed_messages.into()));
+                                Self::broadcast(&broadcast_tx, &mut replay_stored_slots, CommitmentLevel::Processed, processed_messages);
                                 processed_messages = Vec::with_capacity(PROCESSED_MESSAGES_MAX);
                                 processed_sleep
                                     .as_mut()
@@ -1089,24 +1864,30 @@ impl GrpcService {
                             }
 
                             if !confirmed_messages.is_empty() {
-                                let _ =
-                                    broadcast_tx.send((CommitmentLevel::Confirmed, confirmed_messages.into()));
+                                Self::broadcast(&broadcast_tx, &mut replay_stored_slots, CommitmentLevel::Confirmed, confirmed_messages);
                             }
 
                             if !finalized_messages.is_empty() {
-                                let _ =
-                                    broadcast_tx.send((CommitmentLevel::Finalized, finalized_messages.into()));
+                                Self::broadcast(&broadcast_tx, &mut replay_stored_slots, CommitmentLevel::Finalized, finalized_messages);
                             }
                         }
                     }
                 }
                 () = &mut processed_sleep => {
                     if !processed_messages.is_empty() {
-                        let _ = broadcast_tx.send((CommitmentLevel::Processed, processed_messages.into()));
+                        Self::broadcast(&broadcast_tx, &mut replay_stored_slots, CommitmentLevel::Processed, processed_messages);
                         processed_messages = Vec::with_capacity(PROCESSED_MESSAGES_MAX);
                     }
                     processed_sleep.as_mut().reset(Instant::now() + PROCESSED_MESSAGES_SLEEP);
                 }
+                Some((commitment, from_slot, tx)) = replay_stored_slots_rx.recv() => {
+                    // Subscribe here, so client receive all messages after replayed without gap
+                    let _ = tx.send(
+                        replay_stored_slots
+                            .get_messages(commitment, from_slot)
+                            .map(|messages| (messages, broadcast_tx.subscribe())),
+                    );
+                }
                 else => break,
             }
         }
@@ -1116,12 +1897,15 @@ impl GrpcService {
     async fn client_loop(
         id: usize,
         endpoint: String,
-        config_filters: Arc<ConfigGrpcFilters>,
-        stream_tx: mpsc::Sender<TonicResult<SubscribeUpdate>>,
+        tenant: Tenant,
+        stream_tx: mpsc::Sender<TonicResult<FilteredUpdate>>,
         mut client_rx: mpsc::UnboundedReceiver<Option<Filter>>,
-        mut snapshot_rx: Option<crossbeam_channel::Receiver<Option<Message>>>,
-        mut messages_rx: broadcast::Receiver<(CommitmentLevel, Arc<Vec<Arc<Message>>>)>,
+        snapshot: Option<Arc<Snapshot>>,
+        accounts: Option<Arc<AccountsStorage>>,
+        mut messages_rx: broadcast::Receiver<BroadcastedMessage>,
+        replay_stored_slots_tx: Option<mpsc::Sender<ReplayStoredSlotsRequest>>,
         debug_client_tx: Option<mpsc::UnboundedSender<DebugClientMessage>>,
+        blocks_fragments_subscriptions: Arc<AtomicUsize>,
         drop_client: impl FnOnce(),
     ) {
         let mut filter = Filter::new(
@@ -1136,157 +1920,301 @@ impl GrpcService {
                 commitment: None,
                 accounts_data_slice: Vec::new(),
                 ping: None,
+                from_slot: None,
+                backpressure: None,
+                snapshot: false,
+                rollback: false,
             },
-            &config_filters,
+            tenant.filters(),
         )
         .expect("empty filter");
-        prom::update_subscriptions(&endpoint, None, Some(&filter));
+        prom::update_subscriptions(&endpoint, tenant.name(), None, Some(&filter));
 
-        CONNECTIONS_TOTAL.inc();
+        CONNECTIONS_TOTAL.with_label_values(&[tenant.name()]).inc();
         DebugClientMessage::maybe_send(&debug_client_tx, || DebugClientMessage::UpdateFilter {
             id,
             filter: Box::new(filter.clone()),
         });
-        info!("client #{id}: new");
-
-        let mut is_alive = true;
-        if let Some(snapshot_rx) = snapshot_rx.take() {
-            info!("client #{id}: going to receive snapshot data");
-
-            // we start with default filter, for snapshot we need wait actual filter first
-            while is_alive {
-                match client_rx.recv().await {
-                    Some(Some(filter_new)) => {
-                        if let Some(msg) = filter_new.get_pong_msg() {
-                            if stream_tx.send(Ok(msg)).await.is_err() {
-                                error!("client #{id}: stream closed");
-                                is_alive = false;
-                                break;
+        match tenant.identity() {
+            Some(identity) => info!(
+                "client #{id}: new, tenant {}, identity {identity}",
+                tenant.name()
+            ),
+            None => info!("client #{id}: new, tenant {}", tenant.name()),
+        }
+
+        let mut queue = ClientQueue::new(tenant.config().backpressure.queue_capacity);
+        let queue_stream_tx = stream_tx.clone();
+        let mut lagged = false;
+        let mut last_slot = None;
+        let mut snapshot_sent = false;
+        // stored slots are replayed only on the first request with `from_slot` or on change
+        let mut replayed_from_slot = None;
+        // write_version of accounts sent as initial state, older live updates are skipped
+        let mut initial_state: HashMap<Pubkey, u64> = HashMap::new();
+        let mut rollback = ClientRollback::default();
+
+        'outer: loop {
+            tokio::select! {
+                message = client_rx.recv() => {
+                    match message {
+                        Some(Some(filter_new)) => {
+                            if let Some(msg) = filter_new.get_pong_msg() {
+                                if stream_tx.send(Ok(msg.into())).await.is_err() {
+                                    error!("client #{id}: stream closed");
+                                    break 'outer;
+                                }
+                                continue;
                             }
-                            continue;
-                        }
 
-                        prom::update_subscriptions(&endpoint, Some(&filter), Some(&filter_new));
-                        filter = filter_new;
-                        info!("client #{id}: filter updated");
-                    }
-                    Some(None) => {
-                        is_alive = false;
-                    }
-                    None => {
-                        is_alive = false;
-                    }
-                };
-            }
+                            prom::update_subscriptions(&endpoint, tenant.name(), Some(&filter), Some(&filter_new));
+                            Self::update_blocks_fragments_subscriptions(&blocks_fragments_subscriptions, Some(&filter), Some(&filter_new));
+                            let initial_state_changed = filter_new.is_initial_state_changed(&filter);
+                            filter = filter_new;
+                            if initial_state_changed {
+                                initial_state.clear();
+                            }
+                            if !filter.get_rollback() {
+                                rollback = ClientRollback::default();
+                            }
+                            DebugClientMessage::maybe_send(&debug_client_tx, || DebugClientMessage::UpdateFilter { id, filter: Box::new(filter.clone()) });
+                            info!("client #{id}: filter updated");
+
+                            if let Some(from_slot) = filter.get_from_slot().filter(|slot| replayed_from_slot != Some(*slot)) {
+                                replayed_from_slot = Some(from_slot);
+                                let replay_stored_slots_tx = match &replay_stored_slots_tx {
+                                    Some(tx) => tx,
+                                    None => {
+                                        info!("client #{id}: from_slot is not supported");
+                                        tokio::spawn(async move {
+                                            let _ = stream_tx.send(Err(Status::invalid_argument("from_slot is not supported"))).await;
+                                        });
+                                        break 'outer;
+                                    }
+                                };
+
+                                let commitment = filter.get_commitment_level();
+                                let (tx, rx) = oneshot::channel();
+                                if replay_stored_slots_tx.send((commitment, from_slot, tx)).await.is_err() {
+                                    error!("client #{id}: failed to send from_slot request");
+                                    tokio::spawn(async move {
+                                        let _ = stream_tx.send(Err(Status::internal("failed to send from_slot request"))).await;
+                                    });
+                                    break 'outer;
+                                }
 
-            while is_alive {
-                let message = match snapshot_rx.try_recv() {
-                    Ok(message) => {
-                        MESSAGE_QUEUE_SIZE.dec();
-                        match message {
-                            Some(message) => message,
-                            None => break,
-                        }
-                    }
-                    Err(crossbeam_channel::TryRecvError::Empty) => {
-                        sleep(Duration::from_millis(1)).await;
-                        continue;
-                    }
-                    Err(crossbeam_channel::TryRecvError::Disconnected) => {
-                        error!("client #{id}: snapshot channel disconnected");
-                        is_alive = false;
-                        break;
-                    }
-                };
+                                let messages = match rx.await {
+                                    Ok(Ok((messages, rx))) => {
+                                        messages_rx = rx;
+                                        messages
+                                    }
+                                    Ok(Err(oldest_slot)) => {
+                                        let message = match oldest_slot {
+                                            Some(slot) => format!("from_slot {from_slot} is not available, the oldest available slot is {slot}"),
+                                            None => format!("from_slot {from_slot} is not available, no slots stored yet"),
+                                        };
+                                        info!("client #{id}: {message}");
+                                        tokio::spawn(async move {
+                                            let _ = stream_tx.send(Err(Status::invalid_argument(message))).await;
+                                        });
+                                        break 'outer;
+                                    }
+                                    Err(_error) => {
+                                        error!("client #{id}: failed to get replayed messages");
+                                        tokio::spawn(async move {
+                                            let _ = stream_tx.send(Err(Status::internal("failed to get replayed messages"))).await;
+                                        });
+                                        break 'outer;
+                                    }
+                                };
 
-                for message in filter.get_update(&message, None) {
-                    if stream_tx.send(Ok(message)).await.is_err() {
-                        error!("client #{id}: stream closed");
-                        is_alive = false;
-                        break;
-                    }
-                }
-            }
-        }
+                                info!("client #{id}: replay {} messages from slot {from_slot}", messages.len());
+                                for message in messages.iter() {
+                                    for message in filter.get_update(message, Some(commitment)) {
+                                        if stream_tx.send(Ok(message)).await.is_err() {
+                                            error!("client #{id}: stream closed");
+                                            break 'outer;
+                                        }
+                                    }
+                                }
+                            }
 
-        if is_alive {
-            'outer: loop {
-                tokio::select! {
-                    message = client_rx.recv() => {
-                        match message {
-                            Some(Some(filter_new)) => {
-                                if let Some(msg) = filter_new.get_pong_msg() {
-                                    if stream_tx.send(Ok(msg)).await.is_err() {
+                            if filter.get_snapshot() && !snapshot_sent {
+                                let snapshot = match &snapshot {
+                                    Some(snapshot) => snapshot,
+                                    None => {
+                                        info!("client #{id}: snapshot is not available");
+                                        tokio::spawn(async move {
+                                            let _ = stream_tx.send(Err(Status::invalid_argument("snapshot is not available"))).await;
+                                        });
+                                        break 'outer;
+                                    }
+                                };
+
+                                info!("client #{id}: going to receive snapshot data");
+                                snapshot_sent = true;
+                                match snapshot.send(&filter, &stream_tx, tenant.name()).await {
+                                    Ok(Some(sent)) => info!("client #{id}: snapshot sent, {sent} updates"),
+                                    Ok(None) => {
                                         error!("client #{id}: stream closed");
                                         break 'outer;
                                     }
-                                    continue;
+                                    Err(status) => {
+                                        info!("client #{id}: {}", status.message());
+                                        tokio::spawn(async move {
+                                            let _ = stream_tx.send(Err(status)).await;
+                                        });
+                                        break 'outer;
+                                    }
                                 }
-
-                                prom::update_subscriptions(&endpoint, Some(&filter), Some(&filter_new));
-                                filter = filter_new;
-                                DebugClientMessage::maybe_send(&debug_client_tx, || DebugClientMessage::UpdateFilter { id, filter: Box::new(filter.clone()) });
-                                info!("client #{id}: filter updated");
                             }
-                            Some(None) => {
-                                break 'outer;
-                            },
-                            None => {
-                                break 'outer;
+
+                            if initial_state_changed && filter.has_initial_state() {
+                                let result = match &accounts {
+                                    Some(accounts) => accounts.get_initial_state(&filter).await,
+                                    None => Err(Status::invalid_argument("send_initial_state requires accounts cache")),
+                                };
+                                let messages = match result {
+                                    Ok(messages) => messages,
+                                    Err(status) => {
+                                        info!("client #{id}: failed to get initial state: {}", status.message());
+                                        tokio::spawn(async move {
+                                            let _ = stream_tx.send(Err(status)).await;
+                                        });
+                                        break 'outer;
+                                    }
+                                };
+
+                                for message in messages.iter() {
+                                    if let Message::Account(message) = message.as_ref() {
+                                        if let Some(update) = filter.get_initial_state_update(message) {
+                                            initial_state.insert(message.account.pubkey, message.account.write_version);
+                                            if stream_tx.send(Ok(update)).await.is_err() {
+                                                error!("client #{id}: stream closed");
+                                                break 'outer;
+                                            }
+                                        }
+                                    }
+                                }
+                                info!("client #{id}: initial state sent, {} accounts", initial_state.len());
                             }
                         }
+                        Some(None) => {
+                            break 'outer;
+                        },
+                        None => {
+                            break 'outer;
+                        }
                     }
-                    message = messages_rx.recv() => {
-                        let (commitment, messages) = match message {
-                            Ok((commitment, messages)) => (commitment, messages),
-                            Err(broadcast::error::RecvError::Closed) => {
-                                break 'outer;
-                            },
-                            Err(broadcast::error::RecvError::Lagged(_)) => {
-                                info!("client #{id}: lagged to receive geyser messages");
-                                tokio::spawn(async move {
-                                    let _ = stream_tx.send(Err(Status::internal("lagged"))).await;
-                                });
-                                break 'outer;
+                }
+                message = messages_rx.recv() => {
+                    let (commitment, messages) = match message {
+                        Ok((commitment, messages)) => (commitment, messages),
+                        Err(broadcast::error::RecvError::Closed) => {
+                            break 'outer;
+                        },
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            if filter.get_backpressure().unwrap_or(tenant.config().backpressure.policy.into()) == BackpressurePolicy::DropOldest {
+                                info!("client #{id}: lagged to receive geyser messages, skip");
+                                lagged = true;
+                                continue;
+                            }
+
+                            info!("client #{id}: lagged to receive geyser messages");
+                            tokio::spawn(async move {
+                                let _ = stream_tx.send(Err(Status::internal("lagged"))).await;
+                            });
+                            break 'outer;
+                        }
+                    };
+
+                    if commitment == filter.get_commitment_level() {
+                        let backpressure = filter.get_backpressure().unwrap_or(tenant.config().backpressure.policy.into());
+                        if lagged {
+                            lagged = false;
+                            let to_slot = messages.first().map(|message| message.get_slot()).unwrap_or_default();
+                            queue.add_gap(last_slot.unwrap_or(to_slot), to_slot);
+                        }
+
+                        for message in messages.iter() {
+                            if let Message::Account(msg) = message.as_ref() {
+                                if let Some(write_version) = initial_state.get(&msg.account.pubkey).copied() {
+                                    if msg.account.write_version <= write_version {
+                                        continue;
+                                    }
+                                    initial_state.remove(&msg.account.pubkey);
+                                }
                             }
-                        };
 
-                        if commitment == filter.get_commitment_level() {
-                            for message in messages.iter() {
-                                for message in filter.get_update(message, Some(commitment)) {
+                            let slot = message.get_slot();
+                            let is_slot = matches!(message.as_ref(), Message::Slot(_));
+                            let rollback_update = match message.as_ref() {
+                                Message::Slot(msg) if filter.get_rollback() => rollback.update_slot(msg),
+                                _ => None,
+                            };
+                            let mut is_sent = false;
+                            for message in filter.get_update(message, Some(commitment)).chain(rollback_update) {
+                                is_sent = true;
+                                let message = if queue.can_send(backpressure, is_slot) {
                                     match stream_tx.try_send(Ok(message)) {
-                                        Ok(()) => {}
-                                        Err(mpsc::error::TrySendError::Full(_)) => {
-                                            error!("client #{id}: lagged to send update");
-                                            tokio::spawn(async move {
-                                                let _ = stream_tx.send(Err(Status::internal("lagged"))).await;
-                                            });
-                                            break 'outer;
-                                        }
+                                        Ok(()) => continue,
+                                        Err(mpsc::error::TrySendError::Full(message)) => message,
                                         Err(mpsc::error::TrySendError::Closed(_)) => {
                                             error!("client #{id}: stream closed");
                                             break 'outer;
                                         }
                                     }
+                                } else {
+                                    Ok(message)
+                                };
+
+                                if !queue.push(backpressure, slot, is_slot, message) {
+                                    error!("client #{id}: lagged to send update");
+                                    tokio::spawn(async move {
+                                        let _ = stream_tx.send(Err(Status::internal("lagged"))).await;
+                                    });
+                                    break 'outer;
                                 }
                             }
+                            if is_sent && filter.get_rollback() {
+                                rollback.push(message);
+                            }
+                            last_slot = Some(slot);
                         }
+                    }
 
-                        if commitment == CommitmentLevel::Processed && debug_client_tx.is_some() {
-                            for message in messages.iter() {
-                                if let Message::Slot(slot_message) = message.as_ref() {
-                                    DebugClientMessage::maybe_send(&debug_client_tx, || DebugClientMessage::UpdateSlot { id, slot: slot_message.slot });
-                                }
+                    if commitment == CommitmentLevel::Processed && debug_client_tx.is_some() {
+                        for message in messages.iter() {
+                            if let Message::Slot(slot_message) = message.as_ref() {
+                                DebugClientMessage::maybe_send(&debug_client_tx, || DebugClientMessage::UpdateSlot { id, slot: slot_message.slot });
+                            }
+                        }
+                    }
+                }
+                permit = queue_stream_tx.reserve(), if !queue.is_empty() => {
+                    match permit {
+                        Ok(permit) => {
+                            if let Some(message) = queue.pop() {
+                                permit.send(message);
                             }
                         }
+                        Err(_error) => {
+                            error!("client #{id}: stream closed");
+                            break 'outer;
+                        }
                     }
                 }
             }
         }
 
-        CONNECTIONS_TOTAL.dec();
+        CONNECTIONS_TOTAL.with_label_values(&[tenant.name()]).dec();
         DebugClientMessage::maybe_send(&debug_client_tx, || DebugClientMessage::Removed { id });
-        prom::update_subscriptions(&endpoint, Some(&filter), None);
+        prom::update_subscriptions(&endpoint, tenant.name(), Some(&filter), None);
+        Self::update_blocks_fragments_subscriptions(
+            &blocks_fragments_subscriptions,
+            Some(&filter),
+            None,
+        );
         info!("client #{id}: removed");
         drop_client();
     }
@@ -1294,18 +2222,20 @@ impl GrpcService {
 
 #[tonic::async_trait]
 impl Geyser for GrpcService {
-    type SubscribeStream = ReceiverStream<TonicResult<SubscribeUpdate>>;
+    type SubscribeStream = ReceiverStream<TonicResult<FilteredUpdate>>;
 
     async fn subscribe(
         &self,
         mut request: Request<Streaming<SubscribeRequest>>,
     ) -> TonicResult<Response<Self::SubscribeStream>> {
+        let tenant = Tenant::from_request(&request)?;
+        self.tenants_usage.subscribe(&tenant)?;
+
         let id = self.subscribe_id.fetch_add(1, Ordering::Relaxed);
-        let snapshot_rx = self.snapshot_rx.lock().await.take();
-        let (stream_tx, stream_rx) = mpsc::channel(if snapshot_rx.is_some() {
-            self.config_snapshot_client_channel_capacity
+        let (stream_tx, stream_rx) = mpsc::channel(if self.snapshot.is_some() {
+            tenant.config().snapshot_client_channel_capacity
         } else {
-            self.config_channel_capacity
+            tenant.config().channel_capacity
         });
         let (client_tx, client_rx) = mpsc::unbounded_channel();
         let notify_exit1 = Arc::new(Notify::new());
@@ -1329,7 +2259,7 @@ impl Geyser for GrpcService {
                         break;
                     }
                     _ = sleep(Duration::from_secs(10)) => {
-                        match ping_stream_tx.try_send(Ok(ping_msg.clone())) {
+                        match ping_stream_tx.try_send(Ok(ping_msg.clone().into())) {
                             Ok(()) => {}
                             Err(mpsc::error::TrySendError::Full(_)) => {}
                             Err(mpsc::error::TrySendError::Closed(_)) => {
@@ -1342,13 +2272,19 @@ impl Geyser for GrpcService {
             }
         });
 
-        let endpoint = request
-            .metadata()
-            .get("x-endpoint")
-            .and_then(|h| h.to_str().ok().map(|s| s.to_string()))
-            .unwrap_or_else(|| "".to_owned());
+        // Identity of mutual TLS client is used instead of free-form `x-endpoint`
+        let endpoint = match tenant.identity() {
+            Some(identity) => identity.to_owned(),
+            None => request
+                .metadata()
+                .get("x-endpoint")
+                .and_then(|h| h.to_str().ok().map(|s| s.to_string()))
+                .unwrap_or_else(|| "".to_owned()),
+        };
 
-        let config_filters = Arc::clone(&self.config_filters);
+        let x_token = request.metadata().get("x-token").cloned();
+        let mut config_rx = self.config_rx.clone();
+        let mut incoming_tenant = tenant.clone();
         let incoming_stream_tx = stream_tx.clone();
         let incoming_client_tx = client_tx;
         let incoming_exit = Arc::clone(&notify_exit2);
@@ -1356,18 +2292,26 @@ impl Geyser for GrpcService {
             let exit = incoming_exit.notified();
             tokio::pin!(exit);
 
+            // last accepted request, re-checked on config reload
+            let mut last_request = None;
+            let mut is_request_open = true;
             loop {
                 tokio::select! {
                     _ = &mut exit => {
                         break;
                     }
-                    message = request.get_mut().message() => match message {
+                    message = request.get_mut().message(), if is_request_open => match message {
                         Ok(Some(request)) => {
-                            if let Err(error) = match Filter::new(&request, &config_filters) {
-                                Ok(filter) => match incoming_client_tx.send(Some(filter)) {
-                                    Ok(()) => Ok(()),
-                                    Err(error) => Err(error.to_string()),
-                                },
+                            if let Err(error) = match Self::create_filter(&incoming_tenant, &request) {
+                                Ok(filter) => {
+                                    if filter.get_pong_msg().is_none() {
+                                        last_request = Some(request);
+                                    }
+                                    match incoming_client_tx.send(Some(filter)) {
+                                        Ok(()) => Ok(()),
+                                        Err(error) => Err(error.to_string()),
+                                    }
+                                }
                                 Err(error) => Err(error.to_string()),
                             } {
                                 let err = Err(Status::invalid_argument(format!(
@@ -1379,27 +2323,68 @@ impl Geyser for GrpcService {
                             }
                         }
                         Ok(None) => {
-                            break;
+                            is_request_open = false;
                         }
                         Err(_error) => {
                             let _ = incoming_client_tx.send(None);
                             break;
                         }
+                    },
+                    result = config_rx.changed() => {
+                        if result.is_err() {
+                            break;
+                        }
+
+                        let config = Arc::clone(&config_rx.borrow_and_update());
+                        let identities = incoming_tenant.identities().to_vec();
+                        let status = match Tenant::resolve(&config, x_token.as_ref(), identities) {
+                            // subscription is accounted in `TenantsUsage` under the tenant of the connection
+                            Some(tenant) if tenant.name() != incoming_tenant.name() => Some(Status::unauthenticated(format!(
+                                "tenant changed after config reload: {} -> {}",
+                                incoming_tenant.name(),
+                                tenant.name()
+                            ))),
+                            Some(tenant) => {
+                                incoming_tenant = tenant;
+                                match last_request.as_ref().map(|request| Self::create_filter(&incoming_tenant, request)) {
+                                    Some(Err(error)) => Some(Status::invalid_argument(format!(
+                                        "filter is not allowed after config reload: {error}"
+                                    ))),
+                                    _ => None,
+                                }
+                            }
+                            None => Some(Status::unauthenticated("No valid auth token")),
+                        };
+
+                        if let Some(status) = status {
+                            info!("client #{id}: closed after config reload: {}", status.message());
+                            let stream_tx = incoming_stream_tx.clone();
+                            tokio::spawn(async move {
+                                let _ = stream_tx.send(Err(status)).await;
+                            });
+                            let _ = incoming_client_tx.send(None);
+                            break;
+                        }
                     }
                 }
             }
         });
 
+        let tenants_usage = Arc::clone(&self.tenants_usage);
         tokio::spawn(Self::client_loop(
             id,
             endpoint,
-            Arc::clone(&self.config_filters),
+            tenant.clone(),
             stream_tx,
             client_rx,
-            snapshot_rx,
+            self.snapshot.clone(),
+            self.accounts.clone(),
             self.broadcast_tx.subscribe(),
+            self.replay_stored_slots_tx.clone(),
             self.debug_clients_tx.clone(),
+            Arc::clone(&self.blocks_fragments_subscriptions),
             move || {
+                tenants_usage.unsubscribe(&tenant);
                 notify_exit1.notify_one();
                 notify_exit2.notify_one();
             },
@@ -1409,6 +2394,7 @@ impl Geyser for GrpcService {
     }
 
     async fn ping(&self, request: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+        self.check_unary(&request)?;
         let count = request.get_ref().count;
         let response = PongResponse { count };
         Ok(Response::new(response))
@@ -1418,6 +2404,8 @@ impl Geyser for GrpcService {
         &self,
         request: Request<GetLatestBlockhashRequest>,
     ) -> Result<Response<GetLatestBlockhashResponse>, Status> {
+        let tenant = self.check_unary(&request)?;
+        Self::check_unary_commitment(&tenant, request.get_ref().commitment)?;
         if let Some(blocks_meta) = &self.blocks_meta {
             blocks_meta
                 .get_block(
@@ -1443,6 +2431,8 @@ impl Geyser for GrpcService {
         &self,
         request: Request<GetBlockHeightRequest>,
     ) -> Result<Response<GetBlockHeightResponse>, Status> {
+        let tenant = self.check_unary(&request)?;
+        Self::check_unary_commitment(&tenant, request.get_ref().commitment)?;
         if let Some(blocks_meta) = &self.blocks_meta {
             blocks_meta
                 .get_block(
@@ -1463,6 +2453,8 @@ impl Geyser for GrpcService {
         &self,
         request: Request<GetSlotRequest>,
     ) -> Result<Response<GetSlotResponse>, Status> {
+        let tenant = self.check_unary(&request)?;
+        Self::check_unary_commitment(&tenant, request.get_ref().commitment)?;
         if let Some(blocks_meta) = &self.blocks_meta {
             blocks_meta
                 .get_block(
@@ -1479,8 +2471,10 @@ impl Geyser for GrpcService {
         &self,
         request: Request<IsBlockhashValidRequest>,
     ) -> Result<Response<IsBlockhashValidResponse>, Status> {
+        let tenant = self.check_unary(&request)?;
         if let Some(blocks_meta) = &self.blocks_meta {
             let req = request.get_ref();
+            Self::check_unary_commitment(&tenant, req.commitment)?;
             blocks_meta
                 .is_blockhash_valid(&req.blockhash, req.commitment)
                 .await
@@ -1491,10 +2485,499 @@ impl Geyser for GrpcService {
 
     async fn get_version(
         &self,
-        _request: Request<GetVersionRequest>,
+        request: Request<GetVersionRequest>,
     ) -> Result<Response<GetVersionResponse>, Status> {
+        self.check_unary(&request)?;
         Ok(Response::new(GetVersionResponse {
             version: serde_json::to_string(&GrpcVersionInfo::default()).unwrap(),
         }))
     }
+
+    async fn get_account_info(
+        &self,
+        request: Request<GetAccountInfoRequest>,
+    ) -> Result<Response<GetAccountInfoResponse>, Status> {
+        let tenant = self.check_unary(&request)?;
+        if let Some(accounts) = &self.accounts {
+            let req = request.get_ref();
+            let commitment = Self::check_unary_commitment(&tenant, req.commitment)?;
+            let (slot, mut values) = accounts
+                .get_accounts(slice::from_ref(&req.pubkey), commitment, &req.data_slice)
+                .await?;
+            Ok(Response::new(GetAccountInfoResponse {
+                slot,
+                account: values.pop().flatten(),
+            }))
+        } else {
+            Err(Status::unimplemented("method disabled"))
+        }
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        request: Request<GetMultipleAccountsRequest>,
+    ) -> Result<Response<GetMultipleAccountsResponse>, Status> {
+        let tenant = self.check_unary(&request)?;
+        if let Some(accounts) = &self.accounts {
+            let req = request.get_ref();
+            let commitment = Self::check_unary_commitment(&tenant, req.commitment)?;
+            let (slot, values) = accounts
+                .get_accounts(&req.pubkeys, commitment, &req.data_slice)
+                .await?;
+            Ok(Response::new(GetMultipleAccountsResponse {
+                slot,
+                accounts: values
+                    .into_iter()
+                    .map(|account| GetMultipleAccountsResponseAccount { account })
+                    .collect(),
+            }))
+        } else {
+            Err(Status::unimplemented("method disabled"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            ClientQueue, ClientRollback, GrpcService, Message, MessageAccount, MessageAccountInfo,
+            MessageBlockMeta, MessageBlockRef, MessageEntry, MessageSlot, ReplayStoredSlots,
+            SlotMessages, TonicResult,
+        },
+        crate::codec::{EncodedCache, FilteredUpdate},
+        solana_sdk::pubkey::Pubkey,
+        std::{collections::BTreeMap, sync::Arc},
+        yellowstone_grpc_proto::prelude::{
+            subscribe_update::UpdateOneof, BackpressurePolicy, CommitmentLevel, SlotStatus,
+            SubscribeUpdate, SubscribeUpdateEntry, SubscribeUpdateGap, SubscribeUpdateSlot,
+        },
+    };
+
+    fn create_slot(parent: u64, completed: bool) -> SlotMessages {
+        SlotMessages {
+            parent_slot: Some(parent),
+            block_meta: completed.then(|| MessageBlockMeta {
+                parent_slot: parent,
+                slot: 0,
+                parent_blockhash: String::new(),
+                blockhash: String::new(),
+                rewards: vec![],
+                block_time: None,
+                block_height: None,
+                executed_transaction_count: 0,
+                entries_count: 0,
+            }),
+            ..Default::default()
+        }
+    }
+
+    fn get_statuses(messages: Vec<Message>) -> Vec<(u64, SlotStatus)> {
+        messages
+            .into_iter()
+            .map(|message| match message {
+                Message::Slot(msg) => (msg.slot, msg.status),
+                _ => panic!("expected slot message"),
+            })
+            .collect()
+    }
+
+    fn create_slot_message(slot: u64) -> Arc<Message> {
+        Arc::new(Message::Slot(MessageSlot {
+            slot,
+            parent: slot.checked_sub(1),
+            status: SlotStatus::SlotProcessed,
+        }))
+    }
+
+    fn get_slots(messages: Vec<Arc<Message>>) -> Vec<u64> {
+        messages.iter().map(|message| message.get_slot()).collect()
+    }
+
+    fn create_account_message(slot: u64, data_len: usize) -> Message {
+        Message::Account(MessageAccount {
+            account: MessageAccountInfo {
+                pubkey: Pubkey::new_unique(),
+                lamports: 0,
+                owner: Pubkey::new_unique(),
+                executable: false,
+                rent_epoch: 0,
+                data: vec![0; data_len],
+                write_version: 0,
+                txn_signature: None,
+            },
+            slot,
+            is_startup: false,
+            encoded: EncodedCache::default(),
+        })
+    }
+
+    #[test]
+    fn test_replay_stored_slots() {
+        let mut stored = ReplayStoredSlots::new(3);
+        assert_eq!(
+            stored
+                .get_messages(CommitmentLevel::Processed, 0)
+                .unwrap_err(),
+            None
+        );
+
+        for slot in 10..15 {
+            stored.push(CommitmentLevel::Processed, &[create_slot_message(slot)]);
+        }
+        // only the last `max_slots` slots are kept
+        assert_eq!(
+            stored.processed.keys().copied().collect::<Vec<_>>(),
+            [12, 13, 14]
+        );
+        assert_eq!(
+            get_slots(stored.get_messages(CommitmentLevel::Processed, 13).unwrap()),
+            [13, 14]
+        );
+        // evicted and too old slots report the oldest stored slot
+        assert_eq!(
+            stored
+                .get_messages(CommitmentLevel::Processed, 11)
+                .unwrap_err(),
+            Some(12)
+        );
+        assert_eq!(
+            stored
+                .get_messages(CommitmentLevel::Processed, 0)
+                .unwrap_err(),
+            Some(12)
+        );
+        // slots after the newest stored slot are not an error
+        assert!(stored
+            .get_messages(CommitmentLevel::Processed, 20)
+            .unwrap()
+            .is_empty());
+        // commitment levels are stored separately
+        assert_eq!(
+            stored
+                .get_messages(CommitmentLevel::Confirmed, 13)
+                .unwrap_err(),
+            None
+        );
+
+        let mut stored = ReplayStoredSlots::new(0);
+        stored.push(CommitmentLevel::Processed, &[create_slot_message(10)]);
+        assert!(stored.processed.is_empty());
+    }
+
+    fn create_update(slot: u64, is_slot: bool) -> TonicResult<FilteredUpdate> {
+        let update_oneof = if is_slot {
+            UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot,
+                ..Default::default()
+            })
+        } else {
+            UpdateOneof::Entry(SubscribeUpdateEntry {
+                slot,
+                ..Default::default()
+            })
+        };
+        Ok(SubscribeUpdate {
+            filters: vec![],
+            update_oneof: Some(update_oneof),
+        }
+        .into())
+    }
+
+    // Pop all queued updates as (kind, slot)
+    fn drain_queue(queue: &mut ClientQueue) -> Vec<(&'static str, u64)> {
+        std::iter::from_fn(|| queue.pop())
+            .map(|update| match update {
+                Ok(FilteredUpdate::Update(SubscribeUpdate {
+                    update_oneof: Some(update_oneof),
+                    ..
+                })) => match update_oneof {
+                    UpdateOneof::Slot(msg) => ("slot", msg.slot),
+                    UpdateOneof::Entry(msg) => ("entry", msg.slot),
+                    UpdateOneof::Gap(SubscribeUpdateGap { from_slot, to_slot }) => {
+                        assert!(from_slot <= to_slot);
+                        ("gap", from_slot)
+                    }
+                    _ => panic!("unexpected update"),
+                },
+                _ => panic!("unexpected update"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_client_queue_disconnect() {
+        let mut queue = ClientQueue::new(2);
+        assert!(queue.can_send(BackpressurePolicy::Disconnect, false));
+        assert!(!queue.push(
+            BackpressurePolicy::Disconnect,
+            1,
+            false,
+            create_update(1, false)
+        ));
+    }
+
+    #[test]
+    fn test_client_queue_drop_oldest() {
+        let policy = BackpressurePolicy::DropOldest;
+        let mut queue = ClientQueue::new(2);
+        for slot in 1..=4 {
+            assert!(queue.push(policy, slot, false, create_update(slot, false)));
+        }
+        assert!(!queue.can_send(policy, false));
+        // slots 1 and 2 are dropped and reported as gap before queued updates
+        assert_eq!(
+            drain_queue(&mut queue),
+            [("gap", 1), ("entry", 3), ("entry", 4)]
+        );
+        assert!(queue.is_empty());
+
+        queue.add_gap(10, 12);
+        queue.add_gap(8, 9);
+        assert_eq!(
+            queue.pop().map(|update| match update {
+                Ok(FilteredUpdate::Update(SubscribeUpdate {
+                    update_oneof: Some(UpdateOneof::Gap(gap)),
+                    ..
+                })) => (gap.from_slot, gap.to_slot),
+                _ => panic!("expected gap"),
+            }),
+            Some((8, 12))
+        );
+    }
+
+    #[test]
+    fn test_client_queue_hold_processed() {
+        let policy = BackpressurePolicy::HoldProcessed;
+        let mut queue = ClientQueue::new(3);
+        assert!(queue.push(policy, 1, false, create_update(1, false)));
+        assert!(queue.push(policy, 1, false, create_update(1, false)));
+        // slot status is not blocked by queued updates
+        assert!(queue.can_send(policy, true));
+        assert!(!queue.can_send(policy, false));
+        assert!(queue.push(policy, 2, true, create_update(2, true)));
+        // slot status is sent ahead of queued updates
+        assert_eq!(
+            drain_queue(&mut queue),
+            [("slot", 2), ("entry", 1), ("entry", 1)]
+        );
+
+        for slot in 1..=3 {
+            assert!(queue.push(policy, slot, false, create_update(slot, false)));
+        }
+        // full queue drops the oldest accounts and transactions, slot status is kept
+        assert!(queue.push(policy, 4, true, create_update(4, true)));
+        assert!(queue.push(policy, 5, true, create_update(5, true)));
+        assert_eq!(
+            drain_queue(&mut queue),
+            [("gap", 1), ("slot", 4), ("slot", 5), ("entry", 3)]
+        );
+
+        // only slot statuses in the queue, the oldest is dropped
+        for slot in 6..=9 {
+            assert!(queue.push(policy, slot, true, create_update(slot, true)));
+        }
+        assert_eq!(
+            drain_queue(&mut queue),
+            [("gap", 6), ("slot", 7), ("slot", 8), ("slot", 9)]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn test_client_rollback() {
+        let dead_slot = |slot| MessageSlot {
+            slot,
+            parent: None,
+            status: SlotStatus::SlotDead,
+        };
+
+        let mut rollback = ClientRollback::default();
+        let slots_max = ClientRollback::SLOTS_MAX as u64;
+        for slot in 0..slots_max + 10 {
+            rollback.push(&create_account_message(slot, 0));
+        }
+        assert_eq!(rollback.slots.len(), ClientRollback::SLOTS_MAX);
+        assert!(rollback.update_slot(&dead_slot(9)).is_none());
+        match rollback.update_slot(&dead_slot(10)) {
+            Some(FilteredUpdate::Update(SubscribeUpdate {
+                update_oneof: Some(UpdateOneof::Rollback(msg)),
+                ..
+            })) => {
+                assert_eq!(msg.slot, 10);
+                assert_eq!(msg.status, SlotStatus::SlotDead as i32);
+                assert_eq!(msg.accounts.len(), 1);
+            }
+            update => panic!("expected rollback update: {update:?}"),
+        }
+
+        // finalized slot removes all previous slots
+        rollback.update_slot(&MessageSlot {
+            slot: slots_max,
+            parent: None,
+            status: SlotStatus::SlotFinalized,
+        });
+        assert_eq!(rollback.slots.len(), 9);
+    }
+
+    #[test]
+    fn test_slot_messages_evict() {
+        let mut slot_messages = create_slot(0, true);
+        slot_messages.push(Arc::new(Message::Account(MessageAccount {
+            account: MessageAccountInfo {
+                pubkey: Pubkey::new_unique(),
+                lamports: 0,
+                owner: Pubkey::new_unique(),
+                executable: false,
+                rent_epoch: 0,
+                data: vec![0; 1024],
+                write_version: 0,
+                txn_signature: None,
+            },
+            slot: 1,
+            is_startup: false,
+            encoded: EncodedCache::default(),
+        })));
+        assert!(slot_messages.bytes > 1024);
+
+        slot_messages.evict();
+        // messages are kept for confirmed and finalized commitment levels
+        assert_eq!(slot_messages.messages.len(), 1);
+        // block is not reconstructed from partial messages
+        assert!(slot_messages.try_fragments().is_empty());
+        assert!(slot_messages.try_seal(true).is_empty());
+    }
+
+    #[test]
+    fn test_slot_messages_fragments() {
+        let create_entry = |index, executed_transaction_count| MessageEntry {
+            slot: 1,
+            index,
+            num_hashes: 0,
+            hash: vec![],
+            executed_transaction_count,
+            starting_transaction_index: 0,
+        };
+        let get_entries = |messages: Vec<Arc<Message>>| {
+            messages
+                .iter()
+                .map(|message| match message.as_ref() {
+                    Message::BlockFragment(msg) => msg.entry.as_ref().map(|entry| entry.index),
+                    _ => panic!("expected block fragment"),
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut slot_messages = create_slot(0, false);
+        // entries are sent in order
+        slot_messages.entries.push(create_entry(1, 0));
+        assert!(slot_messages.try_fragments().is_empty());
+        slot_messages.entries.push(create_entry(0, 0));
+        assert_eq!(
+            get_entries(slot_messages.try_fragments()),
+            vec![Some(0), Some(1)]
+        );
+        // entry is not sent until all transactions are received
+        slot_messages.entries.push(create_entry(2, 1));
+        assert!(slot_messages.try_fragments().is_empty());
+        assert_eq!(slot_messages.fragments_sent, 2);
+
+        // final fragment is built only if fragments are requested
+        let get_kinds = |messages: Vec<Arc<Message>>| {
+            messages
+                .iter()
+                .map(|message| message.kind())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            get_kinds(create_slot(0, true).try_seal(true)),
+            vec!["BlockFragment", "Block"]
+        );
+        assert_eq!(
+            get_kinds(create_slot(0, true).try_seal(false)),
+            vec!["Block"]
+        );
+    }
+
+    #[test]
+    fn test_block_transactions_entry_index() {
+        let create_entry =
+            |index, starting_transaction_index, executed_transaction_count| MessageEntry {
+                slot: 1,
+                index,
+                num_hashes: 0,
+                hash: vec![],
+                executed_transaction_count,
+                starting_transaction_index,
+            };
+        let entries = vec![
+            create_entry(1, 2, 0),
+            create_entry(0, 0, 2),
+            create_entry(2, 2, 1),
+        ];
+        let blockhash = String::new();
+        let rewards = vec![];
+        let mut block = MessageBlockRef {
+            parent_slot: 0,
+            slot: 1,
+            parent_blockhash: &blockhash,
+            blockhash: &blockhash,
+            rewards: &rewards,
+            block_time: None,
+            block_height: None,
+            executed_transaction_count: 3,
+            transactions: vec![],
+            updated_account_count: 0,
+            accounts: vec![],
+            entries_count: 3,
+            entries: vec![],
+            transactions_entries: None,
+        };
+        assert!(block.get_transactions_entry_index().is_empty());
+
+        block.transactions_entries = Some(&entries);
+        let map = block.get_transactions_entry_index();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map.get(&0), Some(&0));
+        assert_eq!(map.get(&1), Some(&0));
+        assert_eq!(map.get(&2), Some(&2));
+    }
+
+    #[test]
+    fn test_abandoned_slots() {
+        // 10 <- 11 <- 13 <- 15
+        //    \- 12 (completed) <- 14 (dead)
+        let mut messages = BTreeMap::new();
+        messages.insert(10, create_slot(9, true));
+        messages.insert(11, create_slot(10, true));
+        messages.insert(12, create_slot(10, true));
+        messages.insert(13, create_slot(11, true));
+        messages.insert(14, create_slot(12, false));
+        messages.insert(15, create_slot(13, true));
+
+        let abandoned = GrpcService::get_abandoned_slots(&messages, 13, Some(10));
+        assert_eq!(
+            get_statuses(abandoned),
+            vec![(12, SlotStatus::SlotAbandoned)]
+        );
+
+        let abandoned = GrpcService::get_abandoned_slots(&messages, 15, Some(13));
+        assert_eq!(get_statuses(abandoned), vec![(14, SlotStatus::SlotDead)]);
+
+        // on startup only slots after the known part of the chain are checked
+        messages.remove(&11);
+        let abandoned = GrpcService::get_abandoned_slots(&messages, 15, None);
+        assert_eq!(
+            get_statuses(abandoned),
+            vec![(12, SlotStatus::SlotAbandoned), (14, SlotStatus::SlotDead)]
+        );
+        let abandoned = GrpcService::get_abandoned_slots(&messages, 13, None);
+        assert_eq!(
+            get_statuses(abandoned),
+            vec![(12, SlotStatus::SlotAbandoned)]
+        );
+        messages.insert(9, create_slot(8, true));
+        let abandoned = GrpcService::get_abandoned_slots(&messages, 10, None);
+        assert!(abandoned.is_empty());
+    }
 }
@@ -2,11 +2,17 @@
 #![deny(clippy::missing_const_for_fn)]
 #![deny(clippy::trivially_copy_pass_by_ref)]
 
+pub mod accounts;
+pub mod codec;
 pub mod config;
 pub mod filters;
 pub mod grpc;
+pub mod listener;
 pub mod plugin;
 pub mod prom;
+pub mod snapshot;
+pub mod tenant;
+pub mod tls;
 pub mod version;
 
 pub fn get_thread_name() -> String {
@@ -0,0 +1,202 @@
+use {
+    crate::config::ConfigGrpcListener,
+    anyhow::Context,
+    futures::stream::{self, Stream},
+    log::error,
+    std::{
+        fs, io,
+        os::unix::fs::{FileTypeExt, MetadataExt, PermissionsExt},
+        path::{Path, PathBuf},
+        pin::Pin,
+        task::{Context as TaskContext, Poll},
+    },
+    tokio::{
+        io::{AsyncRead, AsyncWrite, ReadBuf},
+        net::UnixListener,
+    },
+    tonic::transport::server::Connected,
+};
+
+/// Unix domain socket connection accepted by additional listener
+#[derive(Debug)]
+pub struct UnixStream(tokio::net::UnixStream);
+
+impl Connected for UnixStream {
+    type ConnectInfo = ();
+
+    fn connect_info(&self) -> Self::ConnectInfo {}
+}
+
+impl AsyncRead for UnixStream {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_read(cx, buf)
+    }
+}
+
+impl AsyncWrite for UnixStream {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut TaskContext<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut TaskContext<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+pub type ListenerIncoming = Pin<Box<dyn Stream<Item = io::Result<UnixStream>> + Send>>;
+
+/// Unix listener which removes own socket file on drop
+#[derive(Debug)]
+struct SocketListener {
+    listener: UnixListener,
+    path: PathBuf,
+    inode: (u64, u64),
+}
+
+impl Drop for SocketListener {
+    fn drop(&mut self) {
+        // Socket can be already replaced by a new listener with the same path
+        if let Ok(metadata) = fs::symlink_metadata(&self.path) {
+            if (metadata.dev(), metadata.ino()) == self.inode {
+                if let Err(error) = fs::remove_file(&self.path) {
+                    error!("failed to remove socket {:?}: {error}", self.path);
+                }
+            }
+        }
+    }
+}
+
+/// Bind additional listener, existing socket file is replaced, other files are not touched.
+/// Socket is created with a temporary name and renamed after permissions are set,
+/// so it is never reachable with default permissions.
+pub fn bind(config: &ConfigGrpcListener) -> anyhow::Result<ListenerIncoming> {
+    match config {
+        ConfigGrpcListener::Unix { path, permissions } => {
+            check_socket_path(path)?;
+
+            let file_name = path
+                .file_name()
+                .with_context(|| format!("invalid socket path {path:?}"))?
+                .to_string_lossy();
+            let tmp_path = path.with_file_name(format!(".{file_name}.{}.tmp", std::process::id()));
+            check_socket_path(&tmp_path)?;
+
+            let listener = UnixListener::bind(&tmp_path)
+                .with_context(|| format!("failed to bind socket {tmp_path:?}"))?;
+            let result = permissions
+                .map(|mode| {
+                    fs::set_permissions(&tmp_path, fs::Permissions::from_mode(mode))
+                        .with_context(|| format!("failed to set permissions for socket {path:?}"))
+                })
+                .transpose()
+                .and_then(|_| {
+                    fs::rename(&tmp_path, path)
+                        .with_context(|| format!("failed to move socket to {path:?}"))
+                })
+                .and_then(|()| {
+                    fs::symlink_metadata(path)
+                        .with_context(|| format!("failed to get metadata of socket {path:?}"))
+                });
+            let metadata = match result {
+                Ok(metadata) => metadata,
+                Err(error) => {
+                    let _ = fs::remove_file(&tmp_path);
+                    return Err(error);
+                }
+            };
+
+            let listener = SocketListener {
+                listener,
+                path: path.clone(),
+                inode: (metadata.dev(), metadata.ino()),
+            };
+            Ok(Box::pin(stream::unfold(listener, |listener| async move {
+                let result = listener
+                    .listener
+                    .accept()
+                    .await
+                    .map(|(stream, _addr)| UnixStream(stream));
+                Some((result, listener))
+            })))
+        }
+    }
+}
+
+// Only socket left from previous run can be replaced
+fn check_socket_path(path: &Path) -> anyhow::Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(metadata) => {
+            anyhow::ensure!(
+                metadata.file_type().is_socket(),
+                "failed to bind socket {path:?}: file exists and is not a socket"
+            );
+            fs::remove_file(path).with_context(|| format!("failed to remove socket {path:?}"))
+        }
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(()),
+        Err(error) => Err(error).with_context(|| format!("failed to check socket {path:?}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::bind,
+        crate::config::ConfigGrpcListener,
+        futures::StreamExt,
+        std::{fs, os::unix::fs::PermissionsExt},
+    };
+
+    #[tokio::test]
+    async fn test_unix_listener() {
+        let path = std::env::temp_dir().join(format!("geyser-{}.sock", std::process::id()));
+        let config: ConfigGrpcListener = serde_json::from_str(&format!(
+            r#"{{"type": "unix", "path": {path:?}, "permissions": "600"}}"#
+        ))
+        .unwrap();
+        assert_eq!(
+            config,
+            ConfigGrpcListener::Unix {
+                path: path.clone(),
+                permissions: Some(0o600)
+            }
+        );
+
+        let mut incoming = bind(&config).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let _client = tokio::net::UnixStream::connect(&path).await.unwrap();
+        assert!(incoming.next().await.unwrap().is_ok());
+
+        // socket is removed on shutdown
+        drop(incoming);
+        assert!(!path.exists());
+
+        // socket left from previous run is replaced
+        let listener = std::os::unix::net::UnixListener::bind(&path).unwrap();
+        drop(listener);
+        let incoming = bind(&config).unwrap();
+        let mode = fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        drop(incoming);
+        assert!(!path.exists());
+
+        // other files are not replaced
+        fs::write(&path, b"data").unwrap();
+        assert!(bind(&config).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"data");
+        fs::remove_file(&path).unwrap();
+    }
+}
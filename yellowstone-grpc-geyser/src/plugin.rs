@@ -1,7 +1,7 @@
 use {
     crate::{
-        config::Config,
-        grpc::{GrpcService, Message},
+        config::{Config, ConfigGrpc, ConfigGrpcAccountsCache},
+        grpc::{GrpcService, Message, MessageAccount},
         prom::{self, PrometheusService, MESSAGE_QUEUE_SIZE},
     },
     agave_geyser_plugin_interface::geyser_plugin_interface::{
@@ -9,10 +9,14 @@ use {
         ReplicaEntryInfoVersions, ReplicaTransactionInfoVersions, Result as PluginResult,
         SlotStatus,
     },
+    log::{error, info, warn},
     std::{concat, env, sync::Arc, time::Duration},
     tokio::{
+        fs,
         runtime::{Builder, Runtime},
-        sync::{mpsc, Notify},
+        sync::{mpsc, watch, Notify},
+        task::JoinHandle,
+        time::sleep,
     },
 };
 
@@ -22,6 +26,9 @@ pub struct PluginInner {
     snapshot_channel: Option<crossbeam_channel::Sender<Option<Message>>>,
     grpc_channel: mpsc::UnboundedSender<Arc<Message>>,
     grpc_shutdown: Arc<Notify>,
+    grpc_config: Arc<watch::Sender<Arc<ConfigGrpc>>>,
+    reload_task: Option<JoinHandle<()>>,
+    accounts_cache_seed: Option<ConfigGrpcAccountsCache>,
     prometheus: PrometheusService,
 }
 
@@ -39,6 +46,49 @@ pub struct Plugin {
 }
 
 impl Plugin {
+    async fn reload_config_loop(
+        config_file: String,
+        interval: Duration,
+        grpc_config: Arc<watch::Sender<Arc<ConfigGrpc>>>,
+    ) {
+        let mut modified = fs::metadata(&config_file)
+            .await
+            .and_then(|metadata| metadata.modified())
+            .ok();
+        loop {
+            sleep(interval).await;
+
+            let modified_new = match fs::metadata(&config_file)
+                .await
+                .and_then(|metadata| metadata.modified())
+            {
+                Ok(modified) => Some(modified),
+                Err(error) => {
+                    error!("failed to get config file modification time: {error}");
+                    continue;
+                }
+            };
+            if modified_new == modified {
+                continue;
+            }
+            modified = modified_new;
+
+            match Config::load_from_file(&config_file) {
+                Ok(config) => {
+                    let fields = grpc_config.borrow().restart_required_fields(&config.grpc);
+                    for field in fields {
+                        warn!(
+                            "config field `grpc.{field}` changed, restart is required to apply it"
+                        );
+                    }
+                    info!("config file changed, apply new grpc config");
+                    let _ = grpc_config.send(Arc::new(config.grpc));
+                }
+                Err(error) => error!("failed to reload config, keep current: {error}"),
+            }
+        }
+    }
+
     fn with_inner<F>(&self, f: F) -> PluginResult<()>
     where
         F: FnOnce(&PluginInner) -> PluginResult<()>,
@@ -66,17 +116,22 @@ impl GeyserPlugin for Plugin {
             .build()
             .map_err(|error| GeyserPluginError::Custom(Box::new(error)))?;
 
-        let (snapshot_channel, grpc_channel, grpc_shutdown, prometheus) =
-            runtime.block_on(async move {
+        let reload_interval = config.reload_interval_ms;
+        let accounts_cache_seed = config.grpc.accounts_cache.clone().filter(|accounts_cache| {
+            accounts_cache.seed_from_snapshot && !config.grpc.unary_disabled
+        });
+        let (snapshot_channel, grpc_channel, grpc_shutdown, grpc_config, prometheus) = runtime
+            .block_on(async move {
                 let (debug_client_tx, debug_client_rx) = mpsc::unbounded_channel();
-                let (snapshot_channel, grpc_channel, grpc_shutdown) = GrpcService::create(
-                    config.grpc,
-                    config.block_fail_action,
-                    config.debug_clients_http.then_some(debug_client_tx),
-                    is_reload,
-                )
-                .await
-                .map_err(|error| GeyserPluginError::Custom(format!("{error:?}").into()))?;
+                let (snapshot_channel, grpc_channel, grpc_shutdown, grpc_config) =
+                    GrpcService::create(
+                        config.grpc,
+                        config.block_fail_action,
+                        config.debug_clients_http.then_some(debug_client_tx),
+                        is_reload,
+                    )
+                    .await
+                    .map_err(|error| GeyserPluginError::Custom(format!("{error:?}").into()))?;
                 let prometheus = PrometheusService::new(
                     config.prometheus,
                     config.debug_clients_http.then_some(debug_client_rx),
@@ -86,15 +141,29 @@ impl GeyserPlugin for Plugin {
                     snapshot_channel,
                     grpc_channel,
                     grpc_shutdown,
+                    grpc_config,
                     prometheus,
                 ))
             })?;
 
+        // Watch config file for changes
+        let grpc_config = Arc::new(grpc_config);
+        let reload_task = reload_interval.map(|interval| {
+            runtime.spawn(Self::reload_config_loop(
+                config_file.to_owned(),
+                Duration::from_millis(interval as u64),
+                Arc::clone(&grpc_config),
+            ))
+        });
+
         self.inner = Some(PluginInner {
             runtime,
             snapshot_channel,
             grpc_channel,
             grpc_shutdown,
+            grpc_config,
+            reload_task,
+            accounts_cache_seed,
             prometheus,
         });
 
@@ -105,6 +174,11 @@ impl GeyserPlugin for Plugin {
         if let Some(inner) = self.inner.take() {
             inner.grpc_shutdown.notify_one();
             drop(inner.grpc_channel);
+            // reload task holds own copy of the config sender
+            if let Some(reload_task) = inner.reload_task {
+                reload_task.abort();
+            }
+            drop(inner.grpc_config);
             inner.prometheus.shutdown();
             inner.runtime.shutdown_timeout(Duration::from_secs(30));
         }
@@ -127,16 +201,21 @@ impl GeyserPlugin for Plugin {
                 ReplicaAccountInfoVersions::V0_0_3(info) => info,
             };
 
-            let message = Message::Account((account, slot, is_startup).into());
+            let account: MessageAccount = (account, slot, is_startup).into();
             if is_startup {
+                if let Some(accounts_cache) = &inner.accounts_cache_seed {
+                    if accounts_cache.is_match(&account.account.pubkey, &account.account.owner) {
+                        inner.send_message(Message::Account(account.clone()));
+                    }
+                }
                 if let Some(channel) = &inner.snapshot_channel {
-                    match channel.send(Some(message)) {
+                    match channel.send(Some(Message::Account(account))) {
                         Ok(()) => MESSAGE_QUEUE_SIZE.inc(),
                         Err(_) => panic!("failed to send message to startup queue: channel closed"),
                     }
                 }
             } else {
-                inner.send_message(message);
+                inner.send_message(Message::Account(account));
             }
 
             Ok(())
@@ -248,3 +327,70 @@ pub unsafe extern "C" fn _create_plugin() -> *mut dyn GeyserPlugin {
     let plugin: Box<dyn GeyserPlugin> = Box::new(plugin);
     Box::into_raw(plugin)
 }
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::Plugin,
+        crate::config::Config,
+        std::{
+            fs,
+            path::Path,
+            sync::Arc,
+            time::{Duration, SystemTime},
+        },
+        tokio::{
+            sync::watch,
+            time::{sleep, timeout},
+        },
+    };
+
+    fn write_config(path: &Path, config: &str, version: u64) {
+        fs::write(path, config).unwrap();
+        fs::File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(SystemTime::UNIX_EPOCH + Duration::from_secs(version))
+            .unwrap();
+    }
+
+    fn create_config(address: &str, x_token: &str) -> String {
+        format!(r#"{{"libpath": "", "grpc": {{"address": "{address}", "x_token": "{x_token}"}}}}"#)
+    }
+
+    #[tokio::test]
+    async fn test_reload_config() {
+        let path = std::env::temp_dir().join(format!("geyser-{}.json", std::process::id()));
+        write_config(&path, &create_config("127.0.0.1:10000", "a"), 1);
+        let config = Config::load_from_file(&path).unwrap();
+        let initial = Arc::new(config.grpc);
+        let (tx, mut rx) = watch::channel(Arc::clone(&initial));
+        let task = tokio::spawn(Plugin::reload_config_loop(
+            path.to_string_lossy().into_owned(),
+            Duration::from_millis(10),
+            Arc::new(tx),
+        ));
+
+        // invalid config is ignored
+        write_config(&path, "{", 2);
+        sleep(Duration::from_millis(50)).await;
+        assert!(!rx.has_changed().unwrap());
+
+        // address is not applied, but the rest of config is
+        write_config(&path, &create_config("127.0.0.1:10001", "b"), 3);
+        timeout(Duration::from_secs(1), rx.changed())
+            .await
+            .unwrap()
+            .unwrap();
+        let config = Arc::clone(&rx.borrow_and_update());
+        assert_eq!(config.x_token.as_deref(), Some("b"));
+        assert_eq!(initial.restart_required_fields(&config), ["address"]);
+
+        // sender is owned by the task, so receivers are notified when reload is stopped
+        task.abort();
+        assert!(task.await.unwrap_err().is_cancelled());
+        assert!(rx.changed().await.is_err());
+        fs::remove_file(&path).unwrap();
+    }
+}
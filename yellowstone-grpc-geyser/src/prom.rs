@@ -48,13 +48,61 @@ lazy_static::lazy_static! {
         "message_queue_size", "Size of geyser message queue"
     ).unwrap();
 
-    pub static ref CONNECTIONS_TOTAL: IntGauge = IntGauge::new(
-        "connections_total", "Total number of connections to gRPC service"
+    pub static ref CONNECTIONS_TOTAL: IntGaugeVec = IntGaugeVec::new(
+        Opts::new("connections_total", "Total number of connections to gRPC service"),
+        &["tenant"]
     ).unwrap();
 
     static ref SUBSCRIPTIONS_TOTAL: IntGaugeVec = IntGaugeVec::new(
         Opts::new("subscriptions_total", "Total number of subscriptions to gRPC service"),
-        &["endpoint", "subscription"]
+        &["endpoint", "tenant", "subscription"]
+    ).unwrap();
+
+    pub static ref BLOCK_RECONSTRUCTION_MESSAGES: IntGauge = IntGauge::new(
+        "block_reconstruction_messages", "Number of messages buffered for block reconstruction"
+    ).unwrap();
+
+    pub static ref BLOCK_RECONSTRUCTION_BYTES: IntGauge = IntGauge::new(
+        "block_reconstruction_bytes", "Approximate size of messages buffered for block reconstruction"
+    ).unwrap();
+
+    pub static ref SNAPSHOT_ACCOUNTS_RECEIVED: IntGauge = IntGauge::new(
+        "snapshot_accounts_received", "Number of startup accounts received from the validator"
+    ).unwrap();
+
+    pub static ref SNAPSHOT_FINISHED: IntGauge = IntGauge::new(
+        "snapshot_finished", "All startup accounts are received from the validator"
+    ).unwrap();
+
+    pub static ref SNAPSHOT_BYTES: IntGauge = IntGauge::new(
+        "snapshot_bytes", "Approximate size of startup accounts kept in memory"
+    ).unwrap();
+
+    pub static ref SNAPSHOT_OVERFLOWED: IntGauge = IntGauge::new(
+        "snapshot_overflowed", "Startup accounts are dropped after reaching snapshot_bytes_max"
+    ).unwrap();
+
+    pub static ref SNAPSHOT_CLIENTS: IntGauge = IntGauge::new(
+        "snapshot_clients", "Number of clients receiving snapshot"
+    ).unwrap();
+
+    pub static ref SNAPSHOT_ACCOUNTS_SENT_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("snapshot_accounts_sent_total", "Total number of startup accounts sent to clients"),
+        &["tenant"]
+    ).unwrap();
+
+    pub static ref TLS_CERTIFICATE_EXPIRY: IntGauge = IntGauge::new(
+        "tls_certificate_expiry_timestamp", "Expiration time of TLS server certificate (unix timestamp)"
+    ).unwrap();
+
+    static ref COMPRESSION_RAW_BYTES_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("compression_raw_bytes_total", "Total size of gRPC messages before compression"),
+        &["encoding"]
+    ).unwrap();
+
+    static ref COMPRESSION_SENT_BYTES_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("compression_sent_bytes_total", "Total size of gRPC messages after compression"),
+        &["encoding"]
     ).unwrap();
 }
 
@@ -187,6 +235,17 @@ impl PrometheusService {
             register!(MESSAGE_QUEUE_SIZE);
             register!(CONNECTIONS_TOTAL);
             register!(SUBSCRIPTIONS_TOTAL);
+            register!(BLOCK_RECONSTRUCTION_MESSAGES);
+            register!(BLOCK_RECONSTRUCTION_BYTES);
+            register!(SNAPSHOT_ACCOUNTS_RECEIVED);
+            register!(SNAPSHOT_FINISHED);
+            register!(SNAPSHOT_BYTES);
+            register!(SNAPSHOT_OVERFLOWED);
+            register!(SNAPSHOT_CLIENTS);
+            register!(SNAPSHOT_ACCOUNTS_SENT_TOTAL);
+            register!(TLS_CERTIFICATE_EXPIRY);
+            register!(COMPRESSION_RAW_BYTES_TOTAL);
+            register!(COMPRESSION_SENT_BYTES_TOTAL);
 
             VERSION
                 .with_label_values(&[
@@ -305,16 +364,30 @@ pub fn update_invalid_blocks(reason: impl AsRef<str>) {
     INVALID_FULL_BLOCKS.with_label_values(&["all"]).inc();
 }
 
-pub fn update_subscriptions(endpoint: &str, old: Option<&Filter>, new: Option<&Filter>) {
+pub fn update_compression(encoding: &'static str, raw: usize, sent: usize) {
+    COMPRESSION_RAW_BYTES_TOTAL
+        .with_label_values(&[encoding])
+        .inc_by(raw as u64);
+    COMPRESSION_SENT_BYTES_TOTAL
+        .with_label_values(&[encoding])
+        .inc_by(sent as u64);
+}
+
+pub fn update_subscriptions(
+    endpoint: &str,
+    tenant: &str,
+    old: Option<&Filter>,
+    new: Option<&Filter>,
+) {
     for (multiplier, filter) in [(-1, old), (1, new)] {
         if let Some(filter) = filter {
             SUBSCRIPTIONS_TOTAL
-                .with_label_values(&[endpoint, "grpc_total"])
+                .with_label_values(&[endpoint, tenant, "grpc_total"])
                 .add(multiplier);
 
             for (name, value) in filter.get_metrics() {
                 SUBSCRIPTIONS_TOTAL
-                    .with_label_values(&[endpoint, name])
+                    .with_label_values(&[endpoint, tenant, name])
                     .add((value as i64) * multiplier);
             }
         }
@@ -0,0 +1,409 @@
+use {
+    crate::{
+        codec::FilteredUpdate,
+        config::ConfigGrpcSnapshotAccounts,
+        filters::Filter,
+        grpc::Message,
+        prom::{
+            MESSAGE_QUEUE_SIZE, SNAPSHOT_ACCOUNTS_RECEIVED, SNAPSHOT_ACCOUNTS_SENT_TOTAL,
+            SNAPSHOT_BYTES, SNAPSHOT_CLIENTS, SNAPSHOT_FINISHED, SNAPSHOT_OVERFLOWED,
+        },
+    },
+    log::{error, info},
+    std::{
+        sync::{Arc, RwLock},
+        time::Duration,
+    },
+    tokio::{
+        sync::{mpsc, watch},
+        task::spawn_blocking,
+        time::sleep,
+    },
+    tonic::{Result as TonicResult, Status},
+};
+
+#[derive(Debug, Default)]
+struct SnapshotInner {
+    messages: Vec<Arc<Message>>,
+    bytes: usize,
+    finished: bool,
+    /// Messages are dropped on reaching `bytes_max`, readers are rejected
+    overflowed: bool,
+    /// Retention time after the end of startup is over
+    expired: bool,
+    /// Messages are dropped, new readers are rejected
+    released: bool,
+    readers: usize,
+}
+
+impl SnapshotInner {
+    fn try_release(&mut self) {
+        if self.expired && self.readers == 0 && !self.released {
+            info!("snapshot released: {} accounts", self.messages.len());
+            self.drop_messages();
+            self.released = true;
+        }
+    }
+
+    fn drop_messages(&mut self) {
+        self.messages = Vec::new();
+        self.bytes = 0;
+        SNAPSHOT_BYTES.set(0);
+    }
+}
+
+/// Active client, messages are kept in memory until all readers are dropped
+#[derive(Debug)]
+struct SnapshotReader<'a> {
+    snapshot: &'a Snapshot,
+}
+
+impl Drop for SnapshotReader<'_> {
+    fn drop(&mut self) {
+        let mut inner = self.snapshot.inner.write().expect("poisoned lock");
+        inner.readers -= 1;
+        inner.try_release();
+    }
+}
+
+/// Startup accounts received from the validator, shared between all clients
+#[derive(Debug)]
+pub struct Snapshot {
+    inner: RwLock<SnapshotInner>,
+    updated_tx: watch::Sender<()>,
+    bytes_max: usize,
+    accounts: Option<ConfigGrpcSnapshotAccounts>,
+}
+
+impl Snapshot {
+    const BATCH_SIZE: usize = 1024;
+
+    /// Received messages are kept for `retention` after the end of startup
+    /// and while at least one client receives them, only accounts matched by `accounts` are kept
+    pub fn new(
+        snapshot_rx: crossbeam_channel::Receiver<Option<Message>>,
+        retention: Duration,
+        bytes_max: usize,
+        accounts: Option<ConfigGrpcSnapshotAccounts>,
+    ) -> Arc<Self> {
+        let snapshot = Arc::new(Self {
+            inner: RwLock::new(SnapshotInner::default()),
+            updated_tx: watch::channel(()).0,
+            bytes_max,
+            accounts,
+        });
+
+        let receiver = Arc::clone(&snapshot);
+        let expiration = Arc::clone(&snapshot);
+        tokio::spawn(async move {
+            if spawn_blocking(move || receiver.receive_loop(snapshot_rx))
+                .await
+                .is_ok()
+            {
+                sleep(retention).await;
+                expiration.expire();
+            }
+        });
+
+        snapshot
+    }
+
+    fn expire(&self) {
+        let mut inner = self.inner.write().expect("poisoned lock");
+        inner.expired = true;
+        inner.try_release();
+    }
+
+    fn acquire(&self) -> TonicResult<SnapshotReader<'_>> {
+        let mut inner = self.inner.write().expect("poisoned lock");
+        Self::check(&inner)?;
+        if inner.released {
+            return Err(Status::unavailable(
+                "snapshot is released after the end of startup",
+            ));
+        }
+        inner.readers += 1;
+        Ok(SnapshotReader { snapshot: self })
+    }
+
+    fn check(inner: &SnapshotInner) -> TonicResult<()> {
+        if inner.overflowed {
+            return Err(Status::resource_exhausted(
+                "snapshot is dropped after reaching snapshot_bytes_max",
+            ));
+        }
+        Ok(())
+    }
+
+    fn is_match(&self, message: &Message) -> bool {
+        match (&self.accounts, message) {
+            (Some(accounts), Message::Account(message)) => {
+                accounts.is_match(&message.account.pubkey, &message.account.owner)
+            }
+            _ => true,
+        }
+    }
+
+    fn receive_loop(&self, snapshot_rx: crossbeam_channel::Receiver<Option<Message>>) {
+        let mut finished = false;
+        while !finished {
+            let mut batch = Vec::with_capacity(Self::BATCH_SIZE);
+            match snapshot_rx.recv() {
+                Ok(message) => batch.push(message),
+                Err(_error) => {
+                    error!("snapshot channel disconnected");
+                    finished = true;
+                }
+            }
+            batch.extend(snapshot_rx.try_iter().take(Self::BATCH_SIZE - 1));
+
+            let mut inner = self.inner.write().expect("poisoned lock");
+            for message in batch {
+                MESSAGE_QUEUE_SIZE.dec();
+                match message {
+                    Some(message) => {
+                        SNAPSHOT_ACCOUNTS_RECEIVED.inc();
+                        if inner.overflowed || !self.is_match(&message) {
+                            continue;
+                        }
+
+                        let size = message.size();
+                        if inner.bytes + size > self.bytes_max {
+                            error!(
+                                "snapshot dropped: snapshot_bytes_max {} reached after {} accounts",
+                                self.bytes_max,
+                                inner.messages.len()
+                            );
+                            inner.drop_messages();
+                            inner.overflowed = true;
+                            SNAPSHOT_OVERFLOWED.set(1);
+                            continue;
+                        }
+                        inner.bytes += size;
+                        inner.messages.push(Arc::new(message));
+                        SNAPSHOT_BYTES.add(size as i64);
+                    }
+                    None => {
+                        finished = true;
+                        break;
+                    }
+                }
+            }
+            if finished {
+                inner.finished = true;
+                SNAPSHOT_FINISHED.set(1);
+                info!("snapshot received: {} accounts", inner.messages.len());
+            }
+            drop(inner);
+
+            self.updated_tx.send_replace(());
+        }
+    }
+
+    // Messages starting from `index` and `true` if all messages are received,
+    // fails if messages are dropped on reaching `bytes_max`
+    fn get(&self, index: usize) -> TonicResult<(Vec<Arc<Message>>, bool)> {
+        let inner = self.inner.read().expect("poisoned lock");
+        Self::check(&inner)?;
+        let messages = inner.messages[index.min(inner.messages.len())..]
+            .iter()
+            .take(Self::BATCH_SIZE)
+            .cloned()
+            .collect::<Vec<_>>();
+        let finished = inner.finished && index + messages.len() == inner.messages.len();
+        Ok((messages, finished))
+    }
+
+    /// Send filtered snapshot to the client, wait new accounts until the end of startup.
+    /// Returns number of sent updates or `None` if stream is closed,
+    /// fails if snapshot is released or dropped on reaching `bytes_max`.
+    pub async fn send(
+        &self,
+        filter: &Filter,
+        stream_tx: &mpsc::Sender<TonicResult<FilteredUpdate>>,
+        tenant: &str,
+    ) -> TonicResult<Option<usize>> {
+        let _reader = self.acquire()?;
+
+        SNAPSHOT_CLIENTS.inc();
+        let mut updated_rx = self.updated_tx.subscribe();
+        let mut index = 0;
+        let mut sent = 0;
+        let result = loop {
+            updated_rx.borrow_and_update();
+            let (messages, finished) = match self.get(index) {
+                Ok(value) => value,
+                Err(status) => {
+                    SNAPSHOT_CLIENTS.dec();
+                    return Err(status);
+                }
+            };
+            if messages.is_empty() {
+                if finished || updated_rx.changed().await.is_err() {
+                    break Some(sent);
+                }
+                continue;
+            }
+
+            index += messages.len();
+            let mut is_closed = false;
+            for message in messages.iter() {
+                for update in filter.get_update(message, None) {
+                    if stream_tx.send(Ok(update)).await.is_err() {
+                        is_closed = true;
+                        break;
+                    }
+                    sent += 1;
+                    SNAPSHOT_ACCOUNTS_SENT_TOTAL
+                        .with_label_values(&[tenant])
+                        .inc();
+                }
+            }
+            if is_closed {
+                break None;
+            }
+        };
+        SNAPSHOT_CLIENTS.dec();
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::Snapshot,
+        crate::{
+            codec::EncodedCache,
+            config::ConfigGrpcSnapshotAccounts,
+            grpc::{Message, MessageAccount, MessageAccountInfo},
+        },
+        solana_sdk::pubkey::Pubkey,
+        std::sync::RwLock,
+        tokio::sync::watch,
+        tonic::Code,
+    };
+
+    fn create_snapshot(bytes_max: usize, accounts: Option<ConfigGrpcSnapshotAccounts>) -> Snapshot {
+        Snapshot {
+            inner: RwLock::default(),
+            updated_tx: watch::channel(()).0,
+            bytes_max,
+            accounts,
+        }
+    }
+
+    fn create_message(slot: u64) -> Message {
+        create_message_with_owner(slot, Pubkey::new_unique())
+    }
+
+    fn create_message_with_owner(slot: u64, owner: Pubkey) -> Message {
+        Message::Account(MessageAccount {
+            account: MessageAccountInfo {
+                pubkey: Pubkey::new_unique(),
+                lamports: 0,
+                owner,
+                executable: false,
+                rent_epoch: 0,
+                data: vec![],
+                write_version: 0,
+                txn_signature: None,
+            },
+            slot,
+            is_startup: true,
+            encoded: EncodedCache::default(),
+        })
+    }
+
+    #[test]
+    fn test_snapshot_shared() {
+        let snapshot = create_snapshot(usize::MAX, None);
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        for slot in 0..Snapshot::BATCH_SIZE as u64 + 10 {
+            tx.send(Some(create_message(slot))).unwrap();
+        }
+        tx.send(None).unwrap();
+        snapshot.receive_loop(rx);
+
+        // every reader receive all messages
+        for _ in 0..2 {
+            let (messages, finished) = snapshot.get(0).unwrap();
+            assert_eq!(messages.len(), Snapshot::BATCH_SIZE);
+            assert!(!finished);
+            let (messages, finished) = snapshot.get(Snapshot::BATCH_SIZE).unwrap();
+            assert_eq!(messages.len(), 10);
+            assert!(finished);
+            assert_eq!(messages[9].get_slot(), Snapshot::BATCH_SIZE as u64 + 9);
+        }
+    }
+
+    #[test]
+    fn test_snapshot_release() {
+        let snapshot = create_snapshot(usize::MAX, None);
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        tx.send(Some(create_message(0))).unwrap();
+        tx.send(None).unwrap();
+        snapshot.receive_loop(rx);
+
+        // active reader keeps messages after expiration
+        let reader = snapshot.acquire().unwrap();
+        snapshot.expire();
+        assert_eq!(snapshot.get(0).unwrap().0.len(), 1);
+        let second_reader = snapshot.acquire().unwrap();
+        drop(reader);
+        assert_eq!(snapshot.get(0).unwrap().0.len(), 1);
+
+        // released with the last reader
+        drop(second_reader);
+        assert!(snapshot.get(0).unwrap().0.is_empty());
+        let error = snapshot.acquire().unwrap_err();
+        assert_eq!(error.code(), Code::Unavailable);
+    }
+
+    #[test]
+    fn test_snapshot_overflow() {
+        let size = create_message(0).size();
+        let snapshot = create_snapshot(size * 2, None);
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        for slot in 0..3 {
+            tx.send(Some(create_message(slot))).unwrap();
+        }
+        tx.send(None).unwrap();
+        let reader = snapshot.acquire().unwrap();
+        snapshot.receive_loop(rx);
+
+        // messages are dropped, active and new readers are rejected
+        let error = snapshot.get(0).unwrap_err();
+        assert_eq!(error.code(), Code::ResourceExhausted);
+        drop(reader);
+        let error = snapshot.acquire().unwrap_err();
+        assert_eq!(error.code(), Code::ResourceExhausted);
+        assert_eq!(snapshot.inner.read().unwrap().bytes, 0);
+    }
+
+    #[test]
+    fn test_snapshot_accounts() {
+        let owner = Pubkey::new_unique();
+        let snapshot = create_snapshot(
+            usize::MAX,
+            Some(ConfigGrpcSnapshotAccounts {
+                owners: [owner].into_iter().collect(),
+                pubkeys: Default::default(),
+            }),
+        );
+
+        let (tx, rx) = crossbeam_channel::unbounded();
+        tx.send(Some(create_message(0))).unwrap();
+        tx.send(Some(create_message_with_owner(1, owner))).unwrap();
+        tx.send(None).unwrap();
+        snapshot.receive_loop(rx);
+
+        // only matched accounts are kept
+        let (messages, finished) = snapshot.get(0).unwrap();
+        assert!(finished);
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].get_slot(), 1);
+    }
+}
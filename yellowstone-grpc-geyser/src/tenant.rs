@@ -0,0 +1,312 @@
+use {
+    crate::config::{ConfigGrpc, ConfigGrpcFilters, ConfigGrpcTenant},
+    std::{
+        collections::HashMap,
+        sync::{Arc, Mutex},
+        time::{Duration, Instant},
+    },
+    tonic::{metadata::AsciiMetadataValue, Request, Status},
+    yellowstone_grpc_proto::prelude::CommitmentLevel,
+};
+
+/// Name of the tenant for connections authorized with `x_token` (or without token at all)
+pub const DEFAULT_TENANT: &str = "default";
+
+/// Tenant resolved from client identity (mutual TLS) or `x-token` header,
+/// added to request extensions by interceptor
+#[derive(Debug, Clone)]
+pub struct Tenant {
+    config: Arc<ConfigGrpc>,
+    index: Option<usize>,
+    identities: Vec<String>,
+    identity: Option<String>,
+}
+
+impl Tenant {
+    /// Client is mapped to the first tenant with any of client identities in `identities`,
+    /// otherwise tenant is resolved by `x-token`, unmatched clients are accounted as
+    /// `default` tenant if `x_token` is matched or if neither `x_token` nor tenants are set
+    pub fn resolve(
+        config: &Arc<ConfigGrpc>,
+        x_token: Option<&AsciiMetadataValue>,
+        identities: Vec<String>,
+    ) -> Option<Self> {
+        let matched = config
+            .tenants
+            .iter()
+            .enumerate()
+            .find_map(|(index, tenant)| {
+                identities
+                    .iter()
+                    .find(|identity| tenant.identities.contains(identity))
+                    .map(|identity| (index, identity.clone()))
+            });
+        let identity = matched
+            .as_ref()
+            .map(|(_index, identity)| identity.clone())
+            .or_else(|| identities.first().cloned());
+        let index = matched.map(|(index, _identity)| index).or_else(|| {
+            x_token.and_then(|x_token| {
+                config.tenants.iter().position(
+                    |tenant| matches!(&tenant.x_token, Some(expected) if expected == x_token),
+                )
+            })
+        });
+        if index.is_some() {
+            return Some(Self {
+                config: Arc::clone(config),
+                index,
+                identities,
+                identity,
+            });
+        }
+
+        // without `x_token` only unknown clients are rejected if tenants are configured
+        let is_valid = match (&config.x_token, x_token) {
+            (Some(expected), Some(x_token)) => expected == x_token,
+            (Some(_expected), None) => false,
+            (None, _) => config.tenants.is_empty(),
+        };
+        is_valid.then(|| Self {
+            config: Arc::clone(config),
+            index: None,
+            identities,
+            identity,
+        })
+    }
+
+    pub fn from_request<T>(request: &Request<T>) -> Result<Self, Status> {
+        request
+            .extensions()
+            .get::<Self>()
+            .cloned()
+            .ok_or_else(|| Status::internal("tenant is not resolved"))
+    }
+
+    fn get(&self) -> Option<&ConfigGrpcTenant> {
+        self.index.map(|index| &self.config.tenants[index])
+    }
+
+    pub const fn config(&self) -> &Arc<ConfigGrpc> {
+        &self.config
+    }
+
+    pub fn name(&self) -> &str {
+        self.get()
+            .map(|tenant| tenant.name.as_str())
+            .unwrap_or(DEFAULT_TENANT)
+    }
+
+    /// Identity of the client authenticated with mutual TLS: identity matched with the tenant
+    /// or the first one of client identities
+    pub fn identity(&self) -> Option<&str> {
+        self.identity.as_deref()
+    }
+
+    /// All identities of the client authenticated with mutual TLS
+    pub fn identities(&self) -> &[String] {
+        &self.identities
+    }
+
+    pub fn filters(&self) -> &ConfigGrpcFilters {
+        self.get()
+            .and_then(|tenant| tenant.filters.as_ref())
+            .unwrap_or(&self.config.filters)
+    }
+
+    fn subscriptions_max(&self) -> usize {
+        self.get()
+            .map(|tenant| tenant.subscriptions_max)
+            .unwrap_or(usize::MAX)
+    }
+
+    fn unary_rps_max(&self) -> usize {
+        self.get()
+            .map(|tenant| tenant.unary_rps_max)
+            .unwrap_or(usize::MAX)
+    }
+
+    pub fn is_commitment_allowed(&self, commitment: CommitmentLevel) -> bool {
+        self.get()
+            .map(|tenant| {
+                tenant
+                    .commitments
+                    .iter()
+                    .any(|allowed| CommitmentLevel::from(*allowed) == commitment)
+            })
+            .unwrap_or(true)
+    }
+}
+
+#[derive(Debug, Default)]
+struct TenantUsage {
+    subscriptions: usize,
+    unary_window: Option<Instant>,
+    unary_requests: usize,
+}
+
+/// Number of subscriptions and unary requests per tenant, kept across config reloads
+#[derive(Debug, Default)]
+pub struct TenantsUsage {
+    inner: Mutex<HashMap<String, TenantUsage>>,
+}
+
+impl TenantsUsage {
+    pub fn subscribe(&self, tenant: &Tenant) -> Result<(), Status> {
+        let mut inner = self.inner.lock().expect("poisoned mutex");
+        let usage = inner.entry(tenant.name().to_owned()).or_default();
+        let max = tenant.subscriptions_max();
+        if usage.subscriptions >= max {
+            return Err(Status::resource_exhausted(format!(
+                "max number of subscriptions reached: {max}"
+            )));
+        }
+
+        usage.subscriptions += 1;
+        Ok(())
+    }
+
+    pub fn unsubscribe(&self, tenant: &Tenant) {
+        let mut inner = self.inner.lock().expect("poisoned mutex");
+        if let Some(usage) = inner.get_mut(tenant.name()) {
+            usage.subscriptions = usage.subscriptions.saturating_sub(1);
+        }
+    }
+
+    pub fn unary(&self, tenant: &Tenant) -> Result<(), Status> {
+        let mut inner = self.inner.lock().expect("poisoned mutex");
+        let usage = inner.entry(tenant.name().to_owned()).or_default();
+
+        let now = Instant::now();
+        if !matches!(usage.unary_window, Some(start) if now.duration_since(start) < Duration::from_secs(1))
+        {
+            usage.unary_window = Some(now);
+            usage.unary_requests = 0;
+        }
+
+        let max = tenant.unary_rps_max();
+        if usage.unary_requests >= max {
+            return Err(Status::resource_exhausted(format!(
+                "max number of unary requests per second reached: {max}"
+            )));
+        }
+
+        usage.unary_requests += 1;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{Tenant, TenantsUsage, DEFAULT_TENANT},
+        crate::config::ConfigGrpc,
+        std::sync::Arc,
+        tonic::{metadata::AsciiMetadataValue, Code},
+        yellowstone_grpc_proto::prelude::CommitmentLevel,
+    };
+
+    fn create_config(x_token: Option<&str>) -> Arc<ConfigGrpc> {
+        let config = serde_json::json!({
+            "address": "127.0.0.1:10000",
+            "x_token": x_token,
+            "tenants": [{
+                "name": "tenant1",
+                "x_token": "token1",
+                "identities": ["client1"],
+                "filters": {
+                    "slots": {
+                        "max": 1
+                    }
+                },
+                "subscriptions_max": 1,
+                "unary_rps_max": 2,
+                "commitments": ["confirmed", "finalized"]
+            }]
+        });
+        Arc::new(serde_json::from_value(config).expect("valid config"))
+    }
+
+    fn token(value: &'static str) -> AsciiMetadataValue {
+        AsciiMetadataValue::from_static(value)
+    }
+
+    #[test]
+    fn test_tenant_resolve() {
+        let config = create_config(Some("token0"));
+        let tenant = Tenant::resolve(&config, Some(&token("token1")), vec![]).unwrap();
+        assert_eq!(tenant.name(), "tenant1");
+        assert_eq!(tenant.filters().slots.max, 1);
+        let tenant = Tenant::resolve(&config, Some(&token("token0")), vec![]).unwrap();
+        assert_eq!(tenant.name(), DEFAULT_TENANT);
+        assert_eq!(tenant.filters().slots.max, usize::MAX);
+        assert!(Tenant::resolve(&config, Some(&token("token2")), vec![]).is_none());
+        assert!(Tenant::resolve(&config, None, vec![]).is_none());
+
+        // with tenants and without `x_token` unknown clients are not allowed
+        let config = create_config(None);
+        let tenant = Tenant::resolve(&config, Some(&token("token1")), vec![]).unwrap();
+        assert_eq!(tenant.name(), "tenant1");
+        assert!(Tenant::resolve(&config, None, vec![]).is_none());
+        assert!(Tenant::resolve(&config, Some(&token("token2")), vec![]).is_none());
+        assert!(Tenant::resolve(&config, None, vec!["client2".to_owned()]).is_none());
+
+        let config = Arc::new(ConfigGrpc {
+            tenants: vec![],
+            ..(*config).clone()
+        });
+        let tenant = Tenant::resolve(&config, None, vec![]).unwrap();
+        assert_eq!(tenant.name(), DEFAULT_TENANT);
+        let tenant = Tenant::resolve(&config, Some(&token("token2")), vec![]).unwrap();
+        assert_eq!(tenant.name(), DEFAULT_TENANT);
+    }
+
+    #[test]
+    fn test_tenant_resolve_identity() {
+        let config = create_config(Some("token0"));
+        let identities = |values: &[&str]| values.iter().map(|value| value.to_string()).collect();
+
+        let tenant = Tenant::resolve(&config, None, identities(&["client1"])).unwrap();
+        assert_eq!(tenant.name(), "tenant1");
+        assert_eq!(tenant.identity(), Some("client1"));
+        // any of client identities is matched
+        let tenant = Tenant::resolve(&config, None, identities(&["client2", "client1"])).unwrap();
+        assert_eq!(tenant.name(), "tenant1");
+        assert_eq!(tenant.identity(), Some("client1"));
+        assert_eq!(tenant.identities(), ["client2", "client1"]);
+        // unknown identity is checked with `x-token`
+        let tenant = Tenant::resolve(
+            &config,
+            Some(&token("token0")),
+            identities(&["client2", "client3"]),
+        )
+        .unwrap();
+        assert_eq!(tenant.name(), DEFAULT_TENANT);
+        assert_eq!(tenant.identity(), Some("client2"));
+        assert!(Tenant::resolve(&config, None, identities(&["client2"])).is_none());
+    }
+
+    #[test]
+    fn test_tenant_limits() {
+        let config = create_config(Some("token0"));
+        let tenant = Tenant::resolve(&config, Some(&token("token1")), vec![]).unwrap();
+        let default = Tenant::resolve(&config, Some(&token("token0")), vec![]).unwrap();
+
+        assert!(!tenant.is_commitment_allowed(CommitmentLevel::Processed));
+        assert!(tenant.is_commitment_allowed(CommitmentLevel::Confirmed));
+        assert!(default.is_commitment_allowed(CommitmentLevel::Processed));
+
+        let usage = TenantsUsage::default();
+        assert!(usage.subscribe(&tenant).is_ok());
+        let error = usage.subscribe(&tenant).unwrap_err();
+        assert_eq!(error.code(), Code::ResourceExhausted);
+        assert!(usage.subscribe(&default).is_ok());
+        usage.unsubscribe(&tenant);
+        assert!(usage.subscribe(&tenant).is_ok());
+
+        assert!(usage.unary(&tenant).is_ok());
+        assert!(usage.unary(&tenant).is_ok());
+        assert!(usage.unary(&tenant).is_err());
+        assert!(usage.unary(&default).is_ok());
+    }
+}
@@ -0,0 +1,566 @@
+//! Compression of gRPC messages on the HTTP body level, used for encodings and compression
+//! levels which are not supported by tonic (zstd).
+
+use {
+    bytes::{BufMut, BytesMut},
+    flate2::{write::GzEncoder, Compression},
+    std::{
+        fmt,
+        future::Future,
+        io::{self, Read, Write},
+        pin::Pin,
+        str::FromStr,
+        sync::Arc,
+        task::{Context, Poll},
+    },
+    tonic::codegen::{
+        http::{header::HeaderValue, HeaderMap, Request, Response},
+        Body, Bytes, Service, StdError,
+    },
+    tower_layer::Layer,
+};
+
+const GRPC_ENCODING: &str = "grpc-encoding";
+const GRPC_ACCEPT_ENCODING: &str = "grpc-accept-encoding";
+const FRAME_HEADER_SIZE: usize = 5;
+/// Same as default limit of decoded messages in tonic
+pub const DEFAULT_MAX_DECODING_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CompressionEncoding {
+    Gzip,
+    Zstd,
+}
+
+impl CompressionEncoding {
+    pub const fn as_str(self) -> &'static str {
+        match self {
+            Self::Gzip => "gzip",
+            Self::Zstd => "zstd",
+        }
+    }
+
+    fn from_headers(headers: &HeaderMap, name: &str) -> Vec<Self> {
+        headers
+            .get_all(name)
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .flat_map(|value| value.split(','))
+            .filter_map(|value| value.trim().parse().ok())
+            .collect()
+    }
+}
+
+impl FromStr for CompressionEncoding {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "gzip" => Ok(Self::Gzip),
+            "zstd" => Ok(Self::Zstd),
+            value => Err(format!("Unknown compression format: {value}")),
+        }
+    }
+}
+
+impl fmt::Display for CompressionEncoding {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Called for every sent message with encoding (`identity` if not compressed),
+/// message size before and after compression
+pub type CompressionMetrics = fn(&'static str, usize, usize);
+
+#[derive(Debug, Clone)]
+pub struct SendCompressionConfig {
+    /// Enabled encodings, first encoding accepted by the client is used
+    pub encodings: Vec<CompressionEncoding>,
+    pub gzip_level: u32,
+    pub zstd_level: i32,
+    pub metrics: Option<CompressionMetrics>,
+}
+
+impl Default for SendCompressionConfig {
+    fn default() -> Self {
+        Self {
+            encodings: vec![CompressionEncoding::Gzip],
+            gzip_level: Compression::default().level(),
+            zstd_level: zstd::DEFAULT_COMPRESSION_LEVEL,
+            metrics: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum FrameTransform {
+    Compress(Option<CompressionEncoding>),
+    /// Decompress zstd messages, size of decompressed message is limited
+    Decompress(usize),
+    None,
+}
+
+/// Response body with transformed gRPC messages
+#[derive(Debug)]
+pub struct CompressionBody<B> {
+    inner: B,
+    buffer: BytesMut,
+    transform: FrameTransform,
+    config: Option<Arc<SendCompressionConfig>>,
+}
+
+impl<B> CompressionBody<B> {
+    fn new(
+        inner: B,
+        transform: FrameTransform,
+        config: Option<Arc<SendCompressionConfig>>,
+    ) -> Self {
+        Self {
+            inner,
+            buffer: BytesMut::new(),
+            transform,
+            config,
+        }
+    }
+
+    fn next_frame(&mut self) -> Option<io::Result<Bytes>> {
+        if self.buffer.len() < FRAME_HEADER_SIZE {
+            return None;
+        }
+        let size = u32::from_be_bytes(self.buffer[1..FRAME_HEADER_SIZE].try_into().ok()?) as usize;
+        if self.buffer.len() < FRAME_HEADER_SIZE + size {
+            return None;
+        }
+
+        let frame = self.buffer.split_to(FRAME_HEADER_SIZE + size).freeze();
+        Some(self.transform_frame(frame))
+    }
+
+    fn transform_frame(&self, frame: Bytes) -> io::Result<Bytes> {
+        let compressed = frame[0] == 1;
+        let message = &frame[FRAME_HEADER_SIZE..];
+        match (self.transform, compressed) {
+            (FrameTransform::Compress(encoding), false) => {
+                let config = self.config.as_deref().expect("defined for compression");
+                let (name, compressed) = match encoding {
+                    Some(CompressionEncoding::Gzip) => {
+                        let mut encoder =
+                            GzEncoder::new(Vec::new(), Compression::new(config.gzip_level));
+                        encoder.write_all(message)?;
+                        ("gzip", Some(encoder.finish()?))
+                    }
+                    Some(CompressionEncoding::Zstd) => (
+                        "zstd",
+                        Some(zstd::bulk::compress(message, config.zstd_level)?),
+                    ),
+                    None => ("identity", None),
+                };
+                if let Some(metrics) = config.metrics {
+                    let size = compressed.as_ref().map(Vec::len).unwrap_or(message.len());
+                    metrics(name, message.len(), size);
+                }
+                Ok(match compressed {
+                    Some(compressed) => create_frame(true, &compressed),
+                    None => frame,
+                })
+            }
+            (FrameTransform::Decompress(limit), true) => {
+                // Read one byte more than the limit to detect too large messages
+                let mut message_decoded = Vec::new();
+                zstd::stream::read::Decoder::new(message)?
+                    .take(limit as u64 + 1)
+                    .read_to_end(&mut message_decoded)?;
+                if message_decoded.len() > limit {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("decompressed message is larger than limit ({limit} bytes)"),
+                    ));
+                }
+                Ok(create_frame(false, &message_decoded))
+            }
+            _ => Ok(frame),
+        }
+    }
+}
+
+impl<B: Default> Default for CompressionBody<B> {
+    fn default() -> Self {
+        Self::new(B::default(), FrameTransform::None, None)
+    }
+}
+
+fn create_frame(compressed: bool, message: &[u8]) -> Bytes {
+    let mut frame = BytesMut::with_capacity(FRAME_HEADER_SIZE + message.len());
+    frame.put_u8(compressed as u8);
+    frame.put_u32(message.len() as u32);
+    frame.put_slice(message);
+    frame.freeze()
+}
+
+impl<B> Body for CompressionBody<B>
+where
+    B: Body<Data = Bytes> + Unpin,
+    B::Error: Into<StdError>,
+{
+    type Data = Bytes;
+    type Error = StdError;
+
+    fn poll_data(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+        loop {
+            if let Some(frame) = self.next_frame() {
+                return Poll::Ready(Some(frame.map_err(Into::into)));
+            }
+
+            match Pin::new(&mut self.inner).poll_data(cx) {
+                Poll::Ready(Some(Ok(chunk))) => self.buffer.extend_from_slice(&chunk),
+                Poll::Ready(Some(Err(error))) => return Poll::Ready(Some(Err(error.into()))),
+                Poll::Ready(None) if self.buffer.is_empty() => return Poll::Ready(None),
+                Poll::Ready(None) => {
+                    return Poll::Ready(Some(Err("incomplete gRPC message in the body".into())))
+                }
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+
+    fn poll_trailers(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+        Pin::new(&mut self.inner)
+            .poll_trailers(cx)
+            .map_err(Into::into)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.buffer.is_empty() && self.inner.is_end_stream()
+    }
+}
+
+type BoxFuture<T, E> = Pin<Box<dyn Future<Output = Result<T, E>> + Send>>;
+
+/// Server layer compressing responses with the first of configured encodings accepted by the client
+#[derive(Debug, Clone)]
+pub struct SendCompressionLayer {
+    config: Arc<SendCompressionConfig>,
+}
+
+impl SendCompressionLayer {
+    pub fn new(config: SendCompressionConfig) -> Self {
+        Self {
+            config: Arc::new(config),
+        }
+    }
+}
+
+impl<S> Layer<S> for SendCompressionLayer {
+    type Service = SendCompression<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SendCompression {
+            inner,
+            config: Arc::clone(&self.config),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SendCompression<S> {
+    inner: S,
+    config: Arc<SendCompressionConfig>,
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for SendCompression<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    ResBody: Body<Data = Bytes> + Unpin,
+{
+    type Response = Response<CompressionBody<ResBody>>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let accepted = CompressionEncoding::from_headers(request.headers(), GRPC_ACCEPT_ENCODING);
+        let encoding = self
+            .config
+            .encodings
+            .iter()
+            .find(|encoding| accepted.contains(encoding))
+            .copied();
+        // Messages are compressed here instead of tonic
+        request.headers_mut().remove(GRPC_ACCEPT_ENCODING);
+
+        let config = Arc::clone(&self.config);
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let mut response = future.await?;
+            if let Some(encoding) = encoding {
+                response
+                    .headers_mut()
+                    .insert(GRPC_ENCODING, HeaderValue::from_static(encoding.as_str()));
+            }
+            Ok(response.map(|body| {
+                CompressionBody::new(body, FrameTransform::Compress(encoding), Some(config))
+            }))
+        })
+    }
+}
+
+/// Client service adding `zstd` to accepted encodings and decompressing zstd responses,
+/// decompressed messages larger than `max_decoding_message_size` are rejected
+#[derive(Debug, Clone)]
+pub struct AcceptCompression<S> {
+    inner: S,
+    max_decoding_message_size: usize,
+}
+
+impl<S> AcceptCompression<S> {
+    pub const fn new(inner: S) -> Self {
+        Self {
+            inner,
+            max_decoding_message_size: DEFAULT_MAX_DECODING_MESSAGE_SIZE,
+        }
+    }
+
+    pub fn max_decoding_message_size(self, limit: usize) -> Self {
+        Self {
+            max_decoding_message_size: limit,
+            ..self
+        }
+    }
+}
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for AcceptCompression<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>>,
+    S::Future: Send + 'static,
+    ResBody: Body<Data = Bytes> + Unpin,
+{
+    type Response = Response<CompressionBody<ResBody>>;
+    type Error = S::Error;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, mut request: Request<ReqBody>) -> Self::Future {
+        let mut accepted =
+            CompressionEncoding::from_headers(request.headers(), GRPC_ACCEPT_ENCODING);
+        accepted.retain(|encoding| *encoding != CompressionEncoding::Zstd);
+        accepted.insert(0, CompressionEncoding::Zstd);
+        let value = accepted
+            .iter()
+            .map(|encoding| encoding.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        if let Ok(value) = HeaderValue::from_str(&value) {
+            request.headers_mut().insert(GRPC_ACCEPT_ENCODING, value);
+        }
+
+        let limit = self.max_decoding_message_size;
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let mut response = future.await?;
+            let transform =
+                match CompressionEncoding::from_headers(response.headers(), GRPC_ENCODING)[..] {
+                    [CompressionEncoding::Zstd] => {
+                        // Messages are passed to tonic decompressed
+                        response.headers_mut().remove(GRPC_ENCODING);
+                        FrameTransform::Decompress(limit)
+                    }
+                    _ => FrameTransform::None,
+                };
+            Ok(response.map(|body| CompressionBody::new(body, transform, None)))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            create_frame, AcceptCompression, CompressionBody, CompressionEncoding, FrameTransform,
+            SendCompressionConfig, SendCompressionLayer, GRPC_ACCEPT_ENCODING, GRPC_ENCODING,
+        },
+        std::{
+            collections::VecDeque,
+            convert::Infallible,
+            future::{poll_fn, ready, Ready},
+            pin::Pin,
+            sync::Arc,
+            task::{Context, Poll},
+        },
+        tonic::codegen::{
+            http::{HeaderMap, Request, Response},
+            Body, Bytes, Service,
+        },
+        tower_layer::Layer,
+    };
+
+    #[derive(Debug, Default)]
+    struct TestBody {
+        chunks: VecDeque<Bytes>,
+    }
+
+    impl Body for TestBody {
+        type Data = Bytes;
+        type Error = Infallible;
+
+        fn poll_data(
+            mut self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Option<Result<Self::Data, Self::Error>>> {
+            Poll::Ready(self.chunks.pop_front().map(Ok))
+        }
+
+        fn poll_trailers(
+            self: Pin<&mut Self>,
+            _cx: &mut Context<'_>,
+        ) -> Poll<Result<Option<HeaderMap>, Self::Error>> {
+            Poll::Ready(Ok(None))
+        }
+    }
+
+    // Responds with the message, `grpc-accept-encoding` received by the server is returned
+    // in `x-accept-encoding` header
+    #[derive(Debug, Clone)]
+    struct TestService {
+        frame: Bytes,
+    }
+
+    impl Service<Request<()>> for TestService {
+        type Response = Response<TestBody>;
+        type Error = Infallible;
+        type Future = Ready<Result<Self::Response, Self::Error>>;
+
+        fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, request: Request<()>) -> Self::Future {
+            let mut response = Response::new(TestBody {
+                chunks: VecDeque::from([self.frame.clone()]),
+            });
+            if let Some(value) = request.headers().get(GRPC_ACCEPT_ENCODING) {
+                response
+                    .headers_mut()
+                    .insert("x-accept-encoding", value.clone());
+            }
+            ready(Ok(response))
+        }
+    }
+
+    fn create_request(accept_encoding: Option<&'static str>) -> Request<()> {
+        let mut request = Request::new(());
+        if let Some(value) = accept_encoding {
+            request
+                .headers_mut()
+                .insert(GRPC_ACCEPT_ENCODING, value.parse().unwrap());
+        }
+        request
+    }
+
+    fn get_header<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+        headers.get(name).map(|value| value.to_str().unwrap())
+    }
+
+    async fn read_frame<B: Body<Data = Bytes> + Unpin>(mut body: B) -> Bytes
+    where
+        B::Error: std::fmt::Debug,
+    {
+        poll_fn(|cx| Pin::new(&mut body).poll_data(cx))
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_compression_frames() {
+        let message = vec![42; 4096];
+        let frame = create_frame(false, &message);
+        for encoding in [CompressionEncoding::Gzip, CompressionEncoding::Zstd] {
+            let mut body = CompressionBody::new(
+                (),
+                FrameTransform::Compress(Some(encoding)),
+                Some(Arc::new(SendCompressionConfig::default())),
+            );
+            // message split between chunks
+            body.buffer.extend_from_slice(&frame[..100]);
+            assert!(body.next_frame().is_none());
+            body.buffer.extend_from_slice(&frame[100..]);
+            let compressed = body.next_frame().unwrap().unwrap();
+            assert_eq!(compressed[0], 1);
+            assert!(compressed.len() < frame.len());
+            assert!(body.buffer.is_empty());
+
+            if encoding == CompressionEncoding::Zstd {
+                let mut body = CompressionBody::new((), FrameTransform::Decompress(4096), None);
+                body.buffer.extend_from_slice(&compressed);
+                assert_eq!(body.next_frame().unwrap().unwrap(), frame);
+
+                // decompressed message is limited
+                let mut body = CompressionBody::new((), FrameTransform::Decompress(4095), None);
+                body.buffer.extend_from_slice(&compressed);
+                assert!(body.next_frame().unwrap().is_err());
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_compression_headers() {
+        let frame = create_frame(false, &[42; 4096]);
+        let mut server = SendCompressionLayer::new(SendCompressionConfig {
+            encodings: vec![CompressionEncoding::Zstd, CompressionEncoding::Gzip],
+            ..Default::default()
+        })
+        .layer(TestService {
+            frame: frame.clone(),
+        });
+
+        // the first of server encodings accepted by the client is used,
+        // `grpc-accept-encoding` is not passed to tonic
+        let response = server.call(create_request(Some("gzip"))).await.unwrap();
+        assert_eq!(get_header(response.headers(), GRPC_ENCODING), Some("gzip"));
+        assert_eq!(get_header(response.headers(), "x-accept-encoding"), None);
+        assert_eq!(read_frame(response.into_body()).await[0], 1);
+
+        let response = server
+            .call(create_request(Some("gzip, zstd")))
+            .await
+            .unwrap();
+        assert_eq!(get_header(response.headers(), GRPC_ENCODING), Some("zstd"));
+
+        // client without compression receives messages as is
+        for accept_encoding in [None, Some("identity"), Some("deflate")] {
+            let response = server.call(create_request(accept_encoding)).await.unwrap();
+            assert_eq!(get_header(response.headers(), GRPC_ENCODING), None);
+            assert_eq!(read_frame(response.into_body()).await, frame);
+        }
+
+        // client accepting zstd receives decompressed messages
+        let mut client = AcceptCompression::new(server.clone());
+        let response = client.call(create_request(Some("gzip"))).await.unwrap();
+        assert_eq!(get_header(response.headers(), GRPC_ENCODING), None);
+        assert_eq!(read_frame(response.into_body()).await, frame);
+
+        let mut client = AcceptCompression::new(TestService {
+            frame: frame.clone(),
+        });
+        let response = client.call(create_request(Some("gzip"))).await.unwrap();
+        assert_eq!(
+            get_header(response.headers(), "x-accept-encoding"),
+            Some("zstd,gzip")
+        );
+        assert_eq!(read_frame(response.into_body()).await, frame);
+    }
+}
@@ -0,0 +1,334 @@
+use {
+    crate::prelude::{
+        subscribe_request_filter_accounts_filter::Filter as AccountsFilterDataOneof,
+        subscribe_request_filter_accounts_filter_lamports::Cmp as AccountsFilterLamportsCmp,
+        subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpOneof,
+        SubscribeRequestAccountsDataSlice, SubscribeRequestFilterAccountsFilter,
+        SubscribeRequestFilterTransactions,
+    },
+    base64::{engine::general_purpose::STANDARD as base64_engine, Engine},
+};
+
+/// Max size of decoded `memcmp` data
+pub const MEMCMP_DATA_SIZE_MAX: usize = 128;
+/// Max length of base58 encoded `memcmp` data, enough for `MEMCMP_DATA_SIZE_MAX` bytes
+pub const MEMCMP_DATA_BASE58_SIZE_MAX: usize = 175;
+/// Max length of base64 encoded `memcmp` data, enough for `MEMCMP_DATA_SIZE_MAX` bytes
+pub const MEMCMP_DATA_BASE64_SIZE_MAX: usize = 172;
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum AccountsFilterDataError {
+    #[error("too many data filters, max {0}")]
+    TooManyFilters(usize),
+    #[error("too many lamports filters, max {0}")]
+    TooManyLamportsFilters(usize),
+    #[error("data filter should be defined")]
+    FilterMissing,
+    #[error("data for memcmp should be defined")]
+    MemcmpDataMissing,
+    #[error("memcmp data too large")]
+    MemcmpDataTooLarge,
+    #[error("invalid base58 memcmp data")]
+    MemcmpInvalidBase58,
+    #[error("invalid base64 memcmp data")]
+    MemcmpInvalidBase64,
+    #[error("datasize used more than once")]
+    DuplicateDatasize,
+    #[error("executable used more than once")]
+    DuplicateExecutable,
+    #[error("token_account_state only allowed to be true")]
+    TokenAccountStateFalse,
+    #[error("cmp for lamports should be defined")]
+    LamportsCmpMissing,
+}
+
+pub type AccountsFilterDataResult<T> = Result<T, AccountsFilterDataError>;
+
+/// Validated data filters of the accounts filter, same rules in the plugin and in the client
+#[derive(Debug, Default, Clone)]
+pub struct AccountsFilterData {
+    pub memcmp: Vec<(usize, Vec<u8>)>,
+    pub datasize: Option<usize>,
+    pub token_account_state: bool,
+    pub lamports: Vec<AccountsFilterLamportsCmp>,
+    pub executable: Option<bool>,
+}
+
+impl AccountsFilterData {
+    pub fn new(
+        filters: &[SubscribeRequestFilterAccountsFilter],
+        filters_max: usize,
+        lamports_max: usize,
+    ) -> AccountsFilterDataResult<Self> {
+        if filters.len() > filters_max {
+            return Err(AccountsFilterDataError::TooManyFilters(filters_max));
+        }
+
+        let mut this = Self::default();
+        for filter in filters {
+            match &filter.filter {
+                Some(AccountsFilterDataOneof::Memcmp(memcmp)) => {
+                    let data = match &memcmp.data {
+                        Some(data) => decode_memcmp_data(data)?,
+                        None => return Err(AccountsFilterDataError::MemcmpDataMissing),
+                    };
+                    this.memcmp.push((memcmp.offset as usize, data));
+                }
+                Some(AccountsFilterDataOneof::Datasize(datasize)) => {
+                    if this.datasize.replace(*datasize as usize).is_some() {
+                        return Err(AccountsFilterDataError::DuplicateDatasize);
+                    }
+                }
+                Some(AccountsFilterDataOneof::TokenAccountState(value)) => {
+                    if !value {
+                        return Err(AccountsFilterDataError::TokenAccountStateFalse);
+                    }
+                    this.token_account_state = true;
+                }
+                Some(AccountsFilterDataOneof::Lamports(lamports)) => {
+                    if this.lamports.len() >= lamports_max {
+                        return Err(AccountsFilterDataError::TooManyLamportsFilters(
+                            lamports_max,
+                        ));
+                    }
+                    match &lamports.cmp {
+                        Some(cmp) => this.lamports.push(cmp.clone()),
+                        None => return Err(AccountsFilterDataError::LamportsCmpMissing),
+                    }
+                }
+                Some(AccountsFilterDataOneof::Executable(value)) => {
+                    if this.executable.replace(*value).is_some() {
+                        return Err(AccountsFilterDataError::DuplicateExecutable);
+                    }
+                }
+                None => return Err(AccountsFilterDataError::FilterMissing),
+            }
+        }
+        Ok(this)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum TransactionsFilterError {
+    #[error("log pattern should not be empty")]
+    EmptyLogPattern,
+    #[error("discriminator should not be empty")]
+    EmptyDiscriminator,
+}
+
+/// Rules of the transactions filter which do not depend on configured limits
+pub fn validate_transactions_filter(
+    filter: &SubscribeRequestFilterTransactions,
+) -> Result<(), TransactionsFilterError> {
+    if filter
+        .log_contains
+        .iter()
+        .chain(filter.log_prefix.iter())
+        .any(|pattern| pattern.is_empty())
+    {
+        return Err(TransactionsFilterError::EmptyLogPattern);
+    }
+    if filter
+        .program_invoked
+        .iter()
+        .flat_map(|program| program.discriminators.iter())
+        .any(|discriminator| discriminator.is_empty())
+    {
+        return Err(TransactionsFilterError::EmptyDiscriminator);
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum DataSlicesError {
+    #[error("data slices out of order")]
+    OutOfOrder,
+    #[error("data slices overlap")]
+    Overlap,
+}
+
+/// Data slices should be sorted by offset and should not overlap,
+/// end of the slice is saturated at `u64::MAX`
+pub fn validate_data_slices(
+    slices: &[SubscribeRequestAccountsDataSlice],
+) -> Result<(), DataSlicesError> {
+    for (i, slice_a) in slices.iter().enumerate() {
+        // check order
+        if slices[i + 1..]
+            .iter()
+            .any(|slice_b| slice_a.offset > slice_b.offset)
+        {
+            return Err(DataSlicesError::OutOfOrder);
+        }
+
+        // check overlap
+        if slices[0..i]
+            .iter()
+            .any(|slice_b| slice_a.offset < slice_b.offset.saturating_add(slice_b.length))
+        {
+            return Err(DataSlicesError::Overlap);
+        }
+    }
+    Ok(())
+}
+
+/// Decode `memcmp` data, encoded data is checked before decoding
+pub fn decode_memcmp_data(data: &AccountsFilterMemcmpOneof) -> AccountsFilterDataResult<Vec<u8>> {
+    let data = match data {
+        AccountsFilterMemcmpOneof::Bytes(data) => data.clone(),
+        AccountsFilterMemcmpOneof::Base58(data) => {
+            if data.len() > MEMCMP_DATA_BASE58_SIZE_MAX {
+                return Err(AccountsFilterDataError::MemcmpDataTooLarge);
+            }
+            bs58::decode(data)
+                .into_vec()
+                .map_err(|_| AccountsFilterDataError::MemcmpInvalidBase58)?
+        }
+        AccountsFilterMemcmpOneof::Base64(data) => {
+            if data.len() > MEMCMP_DATA_BASE64_SIZE_MAX {
+                return Err(AccountsFilterDataError::MemcmpDataTooLarge);
+            }
+            base64_engine
+                .decode(data)
+                .map_err(|_| AccountsFilterDataError::MemcmpInvalidBase64)?
+        }
+    };
+    if data.len() > MEMCMP_DATA_SIZE_MAX {
+        return Err(AccountsFilterDataError::MemcmpDataTooLarge);
+    }
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use {
+        super::{
+            decode_memcmp_data, validate_data_slices, validate_transactions_filter,
+            AccountsFilterData, AccountsFilterDataError, DataSlicesError, TransactionsFilterError,
+            MEMCMP_DATA_BASE58_SIZE_MAX, MEMCMP_DATA_BASE64_SIZE_MAX, MEMCMP_DATA_SIZE_MAX,
+        },
+        crate::prelude::{
+            subscribe_request_filter_accounts_filter::Filter as AccountsFilterDataOneof,
+            subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpOneof,
+            SubscribeRequestAccountsDataSlice, SubscribeRequestFilterAccountsFilter,
+            SubscribeRequestFilterTransactions, SubscribeRequestFilterTransactionsProgram,
+        },
+        base64::{engine::general_purpose::STANDARD as base64_engine, Engine},
+    };
+
+    #[test]
+    fn test_memcmp_data_size() {
+        let bytes = vec![u8::MAX; MEMCMP_DATA_SIZE_MAX];
+        let base58 = bs58::encode(&bytes).into_string();
+        let base64 = base64_engine.encode(&bytes);
+        assert!(base58.len() <= MEMCMP_DATA_BASE58_SIZE_MAX);
+        assert!(base64.len() <= MEMCMP_DATA_BASE64_SIZE_MAX);
+        for data in [
+            AccountsFilterMemcmpOneof::Bytes(bytes.clone()),
+            AccountsFilterMemcmpOneof::Base58(base58),
+            AccountsFilterMemcmpOneof::Base64(base64),
+        ] {
+            assert_eq!(decode_memcmp_data(&data), Ok(bytes.clone()));
+        }
+
+        let data = vec![u8::MAX; MEMCMP_DATA_SIZE_MAX + 1];
+        for data in [
+            AccountsFilterMemcmpOneof::Bytes(data.clone()),
+            AccountsFilterMemcmpOneof::Base58(bs58::encode(&data).into_string()),
+            AccountsFilterMemcmpOneof::Base64(base64_engine.encode(&data)),
+        ] {
+            assert_eq!(
+                decode_memcmp_data(&data),
+                Err(AccountsFilterDataError::MemcmpDataTooLarge)
+            );
+        }
+    }
+
+    #[test]
+    fn test_accounts_filter_data() {
+        let filter = |filter| SubscribeRequestFilterAccountsFilter {
+            filter: Some(filter),
+        };
+
+        let data = AccountsFilterData::new(
+            &[
+                filter(AccountsFilterDataOneof::Datasize(165)),
+                filter(AccountsFilterDataOneof::Executable(false)),
+            ],
+            4,
+            1,
+        )
+        .unwrap();
+        assert_eq!(data.datasize, Some(165));
+        assert_eq!(data.executable, Some(false));
+
+        let filters = [
+            filter(AccountsFilterDataOneof::Datasize(1)),
+            filter(AccountsFilterDataOneof::Datasize(2)),
+        ];
+        assert_eq!(
+            AccountsFilterData::new(&filters, 4, 1).unwrap_err(),
+            AccountsFilterDataError::DuplicateDatasize
+        );
+        assert_eq!(
+            AccountsFilterData::new(&filters, 1, 1).unwrap_err(),
+            AccountsFilterDataError::TooManyFilters(1)
+        );
+        assert_eq!(
+            AccountsFilterData::new(
+                &[SubscribeRequestFilterAccountsFilter { filter: None }],
+                4,
+                1
+            )
+            .unwrap_err(),
+            AccountsFilterDataError::FilterMissing
+        );
+    }
+
+    #[test]
+    fn test_data_slices() {
+        let slice = |offset, length| SubscribeRequestAccountsDataSlice { offset, length };
+
+        assert_eq!(validate_data_slices(&[slice(0, 2), slice(2, 2)]), Ok(()));
+        assert_eq!(
+            validate_data_slices(&[slice(2, 2), slice(0, 2)]),
+            Err(DataSlicesError::OutOfOrder)
+        );
+        assert_eq!(
+            validate_data_slices(&[slice(0, 3), slice(2, 2)]),
+            Err(DataSlicesError::Overlap)
+        );
+        // end of the slice does not overflow
+        assert_eq!(
+            validate_data_slices(&[slice(2, u64::MAX), slice(u64::MAX - 1, 1)]),
+            Err(DataSlicesError::Overlap)
+        );
+    }
+
+    #[test]
+    fn test_transactions_filter() {
+        let filter = SubscribeRequestFilterTransactions {
+            log_contains: vec!["Program log".to_owned()],
+            program_invoked: vec![SubscribeRequestFilterTransactionsProgram {
+                discriminators: vec![vec![1]],
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        assert_eq!(validate_transactions_filter(&filter), Ok(()));
+
+        let mut invalid = filter.clone();
+        invalid.log_prefix.push(String::new());
+        assert_eq!(
+            validate_transactions_filter(&invalid),
+            Err(TransactionsFilterError::EmptyLogPattern)
+        );
+
+        let mut invalid = filter;
+        invalid.program_invoked[0].discriminators.push(vec![]);
+        assert_eq!(
+            validate_transactions_filter(&invalid),
+            Err(TransactionsFilterError::EmptyDiscriminator)
+        );
+    }
+}
@@ -2,6 +2,28 @@
 
 pub mod geyser {
     tonic::include_proto!("geyser");
+
+    impl From<CommitmentLevel> for SlotStatus {
+        fn from(commitment: CommitmentLevel) -> Self {
+            match commitment {
+                CommitmentLevel::Processed => Self::SlotProcessed,
+                CommitmentLevel::Confirmed => Self::SlotConfirmed,
+                CommitmentLevel::Finalized => Self::SlotFinalized,
+            }
+        }
+    }
+
+    impl SlotStatus {
+        /// Commitment level of the slot, `None` for dead and abandoned slots
+        pub const fn commitment(self) -> Option<CommitmentLevel> {
+            match self {
+                Self::SlotProcessed => Some(CommitmentLevel::Processed),
+                Self::SlotConfirmed => Some(CommitmentLevel::Confirmed),
+                Self::SlotFinalized => Some(CommitmentLevel::Finalized),
+                Self::SlotDead | Self::SlotAbandoned => None,
+            }
+        }
+    }
 }
 
 pub mod solana {
@@ -18,6 +40,12 @@ pub mod prelude {
 
 pub use {prost, tonic};
 
+#[cfg(feature = "compression")]
+pub mod compression;
+
+#[cfg(feature = "filter")]
+pub mod filter;
+
 #[cfg(feature = "convert")]
 pub mod convert_to {
     use {
@@ -117,7 +145,52 @@ pub mod convert_to {
         }
     }
 
+    /// Transaction meta fields skipped by `create_transaction_meta_omit`
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct TransactionMetaOmit {
+        pub log_messages: bool,
+        pub inner_instructions: bool,
+        pub token_balances: bool,
+        pub rewards: bool,
+        pub return_data: bool,
+        pub loaded_addresses: bool,
+    }
+
+    impl TransactionMetaOmit {
+        /// Field omitted only if it omitted in both
+        pub const fn intersection(self, other: Self) -> Self {
+            Self {
+                log_messages: self.log_messages && other.log_messages,
+                inner_instructions: self.inner_instructions && other.inner_instructions,
+                token_balances: self.token_balances && other.token_balances,
+                rewards: self.rewards && other.rewards,
+                return_data: self.return_data && other.return_data,
+                loaded_addresses: self.loaded_addresses && other.loaded_addresses,
+            }
+        }
+    }
+
+    impl From<&proto::SubscribeRequestFilterTransactionsOmit> for TransactionMetaOmit {
+        fn from(omit: &proto::SubscribeRequestFilterTransactionsOmit) -> Self {
+            Self {
+                log_messages: omit.log_messages,
+                inner_instructions: omit.inner_instructions,
+                token_balances: omit.token_balances,
+                rewards: omit.rewards,
+                return_data: omit.return_data,
+                loaded_addresses: omit.loaded_addresses,
+            }
+        }
+    }
+
     pub fn create_transaction_meta(meta: &TransactionStatusMeta) -> proto::TransactionStatusMeta {
+        create_transaction_meta_omit(meta, TransactionMetaOmit::default())
+    }
+
+    pub fn create_transaction_meta_omit(
+        meta: &TransactionStatusMeta,
+        omit: TransactionMetaOmit,
+    ) -> proto::TransactionStatusMeta {
         let TransactionStatusMeta {
             status,
             fee,
@@ -138,24 +211,44 @@ pub mod convert_to {
                 err: bincode::serialize(&err).expect("transaction error to serialize to bytes"),
             }),
         };
-        let inner_instructions_none = inner_instructions.is_none();
         let inner_instructions = inner_instructions
             .as_deref()
+            .filter(|_| !omit.inner_instructions);
+        let inner_instructions_none = inner_instructions.is_none();
+        let inner_instructions = inner_instructions
             .map(create_inner_instructions_vec)
             .unwrap_or_default();
+        let log_messages = log_messages.as_ref().filter(|_| !omit.log_messages);
         let log_messages_none = log_messages.is_none();
-        let log_messages = log_messages.clone().unwrap_or_default();
-        let pre_token_balances = pre_token_balances
-            .as_deref()
-            .map(create_token_balances)
-            .unwrap_or_default();
-        let post_token_balances = post_token_balances
+        let log_messages = log_messages.cloned().unwrap_or_default();
+        let (pre_token_balances, post_token_balances) = if omit.token_balances {
+            (vec![], vec![])
+        } else {
+            (
+                pre_token_balances
+                    .as_deref()
+                    .map(create_token_balances)
+                    .unwrap_or_default(),
+                post_token_balances
+                    .as_deref()
+                    .map(create_token_balances)
+                    .unwrap_or_default(),
+            )
+        };
+        let rewards = rewards
             .as_deref()
-            .map(create_token_balances)
+            .filter(|_| !omit.rewards)
+            .map(create_rewards)
             .unwrap_or_default();
-        let rewards = rewards.as_deref().map(create_rewards).unwrap_or_default();
-        let loaded_writable_addresses = create_pubkeys(&loaded_addresses.writable);
-        let loaded_readonly_addresses = create_pubkeys(&loaded_addresses.readonly);
+        let (loaded_writable_addresses, loaded_readonly_addresses) = if omit.loaded_addresses {
+            (vec![], vec![])
+        } else {
+            (
+                create_pubkeys(&loaded_addresses.writable),
+                create_pubkeys(&loaded_addresses.readonly),
+            )
+        };
+        let return_data = return_data.as_ref().filter(|_| !omit.return_data);
 
         proto::TransactionStatusMeta {
             err,
@@ -171,7 +264,7 @@ pub mod convert_to {
             rewards,
             loaded_writable_addresses,
             loaded_readonly_addresses,
-            return_data: return_data.as_ref().map(create_return_data),
+            return_data: return_data.map(create_return_data),
             return_data_none: return_data.is_none(),
             compute_units_consumed: *compute_units_consumed,
         }
@@ -289,25 +382,60 @@ pub mod convert_from {
             TransactionStatusMeta, TransactionTokenBalance, TransactionWithStatusMeta,
             VersionedTransactionWithStatusMeta,
         },
+        std::borrow::Cow,
     };
 
-    fn ensure_some<T>(maybe_value: Option<T>, message: impl Into<String>) -> Result<T, String> {
-        match maybe_value {
-            Some(value) => Ok(value),
-            None => Err(message.into()),
+    /// Path of the field is relative to the converted message, e.g. `meta.return_data.program_id`
+    #[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+    pub enum ConvertError {
+        #[error("failed to get {0}")]
+        MissingField(Cow<'static, str>),
+        #[error("failed to parse {0}")]
+        InvalidField(Cow<'static, str>),
+    }
+
+    impl ConvertError {
+        pub const fn missing(field: &'static str) -> Self {
+            Self::MissingField(Cow::Borrowed(field))
+        }
+
+        pub const fn invalid(field: &'static str) -> Self {
+            Self::InvalidField(Cow::Borrowed(field))
+        }
+
+        /// Field path of the error
+        pub fn field(&self) -> &str {
+            match self {
+                Self::MissingField(field) | Self::InvalidField(field) => field,
+            }
+        }
+
+        /// Prepends name of the parent field to the path
+        pub fn in_field(self, parent: &str) -> Self {
+            match self {
+                Self::MissingField(field) => Self::MissingField(format!("{parent}.{field}").into()),
+                Self::InvalidField(field) => Self::InvalidField(format!("{parent}.{field}").into()),
+            }
         }
     }
 
-    pub fn create_block(block: proto::SubscribeUpdateBlock) -> Result<ConfirmedBlock, String> {
+    pub type ConvertResult<T> = Result<T, ConvertError>;
+
+    fn ensure_some<T>(maybe_value: Option<T>, error: ConvertError) -> ConvertResult<T> {
+        maybe_value.ok_or(error)
+    }
+
+    pub fn create_block(block: proto::SubscribeUpdateBlock) -> ConvertResult<ConfirmedBlock> {
         let mut transactions = vec![];
         for tx in block.transactions {
-            transactions.push(create_tx_with_meta(tx)?);
+            transactions
+                .push(create_tx_with_meta(tx).map_err(|error| error.in_field("transactions"))?);
         }
 
-        let block_rewards = ensure_some(block.rewards, "failed to get rewards")?;
+        let block_rewards = ensure_some(block.rewards, ConvertError::missing("rewards"))?;
         let mut rewards = vec![];
         for reward in block_rewards.rewards {
-            rewards.push(create_reward(reward)?);
+            rewards.push(create_reward(reward).map_err(|error| error.in_field("rewards.rewards"))?);
         }
 
         Ok(ConfirmedBlock {
@@ -319,72 +447,71 @@ pub mod convert_from {
             num_partitions: block_rewards.num_partitions.map(|msg| msg.num_partitions),
             block_time: Some(ensure_some(
                 block.block_time.map(|wrapper| wrapper.timestamp),
-                "failed to get block_time",
+                ConvertError::missing("block_time"),
             )?),
             block_height: Some(ensure_some(
                 block.block_height.map(|wrapper| wrapper.block_height),
-                "failed to get block_height",
+                ConvertError::missing("block_height"),
             )?),
         })
     }
 
     pub fn create_tx_with_meta(
         tx: proto::SubscribeUpdateTransactionInfo,
-    ) -> Result<TransactionWithStatusMeta, String> {
-        let meta = ensure_some(tx.meta, "failed to get transaction meta")?;
-        let tx = ensure_some(tx.transaction, "failed to get transaction transaction")?;
+    ) -> ConvertResult<TransactionWithStatusMeta> {
+        let meta = ensure_some(tx.meta, ConvertError::missing("meta"))?;
+        let tx = ensure_some(tx.transaction, ConvertError::missing("transaction"))?;
 
         Ok(TransactionWithStatusMeta::Complete(
             VersionedTransactionWithStatusMeta {
-                transaction: create_tx_versioned(tx)?,
-                meta: create_tx_meta(meta)?,
+                transaction: create_tx_versioned(tx)
+                    .map_err(|error| error.in_field("transaction"))?,
+                meta: create_tx_meta(meta).map_err(|error| error.in_field("meta"))?,
             },
         ))
     }
 
-    pub fn create_tx_versioned(tx: proto::Transaction) -> Result<VersionedTransaction, String> {
+    pub fn create_tx_versioned(tx: proto::Transaction) -> ConvertResult<VersionedTransaction> {
         let mut signatures = Vec::with_capacity(tx.signatures.len());
         for signature in tx.signatures {
-            signatures.push(match Signature::try_from(signature.as_slice()) {
-                Ok(signature) => signature,
-                Err(_error) => return Err("failed to parse Signature".to_owned()),
-            });
+            signatures.push(parse_signature(&signature, "signatures")?);
         }
 
         Ok(VersionedTransaction {
             signatures,
-            message: create_message(ensure_some(tx.message, "failed to get message")?)?,
+            message: create_message(ensure_some(tx.message, ConvertError::missing("message"))?)
+                .map_err(|error| error.in_field("message"))?,
         })
     }
 
-    pub fn create_message(message: proto::Message) -> Result<VersionedMessage, String> {
-        let header = ensure_some(message.header, "failed to get MessageHeader")?;
+    pub fn create_message(message: proto::Message) -> ConvertResult<VersionedMessage> {
+        let header = ensure_some(message.header, ConvertError::missing("header"))?;
         let header = MessageHeader {
             num_required_signatures: ensure_some(
                 header.num_required_signatures.try_into().ok(),
-                "failed to parse num_required_signatures",
+                ConvertError::invalid("header.num_required_signatures"),
             )?,
             num_readonly_signed_accounts: ensure_some(
                 header.num_readonly_signed_accounts.try_into().ok(),
-                "failed to parse num_readonly_signed_accounts",
+                ConvertError::invalid("header.num_readonly_signed_accounts"),
             )?,
             num_readonly_unsigned_accounts: ensure_some(
                 header.num_readonly_unsigned_accounts.try_into().ok(),
-                "failed to parse num_readonly_unsigned_accounts",
+                ConvertError::invalid("header.num_readonly_unsigned_accounts"),
             )?,
         };
 
         if message.recent_blockhash.len() != HASH_BYTES {
-            return Err("failed to parse hash".to_owned());
+            return Err(ConvertError::invalid("recent_blockhash"));
         }
 
         Ok(if message.versioned {
             let mut address_table_lookups = Vec::with_capacity(message.address_table_lookups.len());
             for table in message.address_table_lookups {
                 address_table_lookups.push(MessageAddressTableLookup {
-                    account_key: ensure_some(
-                        Pubkey::try_from(table.account_key.as_slice()).ok(),
-                        "failed to parse Pubkey",
+                    account_key: parse_pubkey(
+                        &table.account_key,
+                        "address_table_lookups.account_key",
                     )?,
                     writable_indexes: table.writable_indexes,
                     readonly_indexes: table.readonly_indexes,
@@ -393,34 +520,36 @@ pub mod convert_from {
 
             VersionedMessage::V0(MessageV0 {
                 header,
-                account_keys: create_pubkey_vec(message.account_keys)?,
+                account_keys: parse_pubkey_vec(message.account_keys, "account_keys")?,
                 recent_blockhash: Hash::new(message.recent_blockhash.as_slice()),
-                instructions: create_message_instructions(message.instructions)?,
+                instructions: create_message_instructions(message.instructions)
+                    .map_err(|error| error.in_field("instructions"))?,
                 address_table_lookups,
             })
         } else {
             VersionedMessage::Legacy(Message {
                 header,
-                account_keys: create_pubkey_vec(message.account_keys)?,
+                account_keys: parse_pubkey_vec(message.account_keys, "account_keys")?,
                 recent_blockhash: Hash::new(message.recent_blockhash.as_slice()),
-                instructions: create_message_instructions(message.instructions)?,
+                instructions: create_message_instructions(message.instructions)
+                    .map_err(|error| error.in_field("instructions"))?,
             })
         })
     }
 
     pub fn create_message_instructions(
         ixs: Vec<proto::CompiledInstruction>,
-    ) -> Result<Vec<CompiledInstruction>, String> {
+    ) -> ConvertResult<Vec<CompiledInstruction>> {
         ixs.into_iter().map(create_message_instruction).collect()
     }
 
     pub fn create_message_instruction(
         ix: proto::CompiledInstruction,
-    ) -> Result<CompiledInstruction, String> {
+    ) -> ConvertResult<CompiledInstruction> {
         Ok(CompiledInstruction {
             program_id_index: ensure_some(
                 ix.program_id_index.try_into().ok(),
-                "failed to decode CompiledInstruction.program_id_index)",
+                ConvertError::invalid("program_id_index"),
             )?,
             accounts: ix.accounts,
             data: ix.data,
@@ -429,7 +558,7 @@ pub mod convert_from {
 
     pub fn create_tx_meta(
         meta: proto::TransactionStatusMeta,
-    ) -> Result<TransactionStatusMeta, String> {
+    ) -> ConvertResult<TransactionStatusMeta> {
         let meta_status = match create_tx_error(meta.err.as_ref())? {
             Some(err) => Err(err),
             None => Ok(()),
@@ -438,31 +567,44 @@ pub mod convert_from {
             .rewards
             .into_iter()
             .map(create_reward)
-            .collect::<Result<Vec<_>, _>>()?;
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|error| error.in_field("rewards"))?;
 
         Ok(TransactionStatusMeta {
             status: meta_status,
             fee: meta.fee,
             pre_balances: meta.pre_balances,
             post_balances: meta.post_balances,
-            inner_instructions: Some(create_meta_inner_instructions(meta.inner_instructions)?),
+            inner_instructions: Some(
+                create_meta_inner_instructions(meta.inner_instructions)
+                    .map_err(|error| error.in_field("inner_instructions"))?,
+            ),
             log_messages: Some(meta.log_messages),
-            pre_token_balances: Some(create_token_balances(meta.pre_token_balances)?),
-            post_token_balances: Some(create_token_balances(meta.post_token_balances)?),
+            pre_token_balances: Some(
+                create_token_balances(meta.pre_token_balances)
+                    .map_err(|error| error.in_field("pre_token_balances"))?,
+            ),
+            post_token_balances: Some(
+                create_token_balances(meta.post_token_balances)
+                    .map_err(|error| error.in_field("post_token_balances"))?,
+            ),
             rewards: Some(meta_rewards),
-            loaded_addresses: create_loaded_addresses(
-                meta.loaded_writable_addresses,
-                meta.loaded_readonly_addresses,
-            )?,
+            loaded_addresses: LoadedAddresses {
+                writable: parse_pubkey_vec(
+                    meta.loaded_writable_addresses,
+                    "loaded_writable_addresses",
+                )?,
+                readonly: parse_pubkey_vec(
+                    meta.loaded_readonly_addresses,
+                    "loaded_readonly_addresses",
+                )?,
+            },
             return_data: if meta.return_data_none {
                 None
             } else {
-                let data = ensure_some(meta.return_data, "failed to get return_data")?;
+                let data = ensure_some(meta.return_data, ConvertError::missing("return_data"))?;
                 Some(TransactionReturnData {
-                    program_id: ensure_some(
-                        Pubkey::try_from(data.program_id.as_slice()).ok(),
-                        "failed to parse program_id",
-                    )?,
+                    program_id: parse_pubkey(&data.program_id, "return_data.program_id")?,
                     data: data.data,
                 })
             },
@@ -472,31 +614,31 @@ pub mod convert_from {
 
     pub fn create_tx_error(
         err: Option<&proto::TransactionError>,
-    ) -> Result<Option<TransactionError>, String> {
+    ) -> ConvertResult<Option<TransactionError>> {
         ensure_some(
             err.map(|err| bincode::deserialize::<TransactionError>(&err.err))
                 .transpose()
                 .ok(),
-            "failed to decode TransactionError",
+            ConvertError::invalid("err"),
         )
     }
 
     pub fn create_meta_inner_instructions(
         ixs: Vec<proto::InnerInstructions>,
-    ) -> Result<Vec<InnerInstructions>, String> {
+    ) -> ConvertResult<Vec<InnerInstructions>> {
         ixs.into_iter().map(create_meta_inner_instruction).collect()
     }
 
     pub fn create_meta_inner_instruction(
         ix: proto::InnerInstructions,
-    ) -> Result<InnerInstructions, String> {
+    ) -> ConvertResult<InnerInstructions> {
         let mut instructions = vec![];
         for ix in ix.instructions {
             instructions.push(InnerInstruction {
                 instruction: CompiledInstruction {
                     program_id_index: ensure_some(
                         ix.program_id_index.try_into().ok(),
-                        "failed to decode CompiledInstruction.program_id_index)",
+                        ConvertError::invalid("instructions.program_id_index"),
                     )?,
                     accounts: ix.accounts,
                     data: ix.data,
@@ -505,22 +647,19 @@ pub mod convert_from {
             });
         }
         Ok(InnerInstructions {
-            index: ensure_some(
-                ix.index.try_into().ok(),
-                "failed to decode InnerInstructions.index",
-            )?,
+            index: ensure_some(ix.index.try_into().ok(), ConvertError::invalid("index"))?,
             instructions,
         })
     }
 
-    pub fn create_reward(reward: proto::Reward) -> Result<Reward, String> {
+    pub fn create_reward(reward: proto::Reward) -> ConvertResult<Reward> {
         Ok(Reward {
             pubkey: reward.pubkey,
             lamports: reward.lamports,
             post_balance: reward.post_balance,
             reward_type: match ensure_some(
                 proto::RewardType::try_from(reward.reward_type).ok(),
-                "failed to parse reward_type",
+                ConvertError::invalid("reward_type"),
             )? {
                 proto::RewardType::Unspecified => None,
                 proto::RewardType::Fee => Some(RewardType::Fee),
@@ -533,7 +672,7 @@ pub mod convert_from {
             } else {
                 Some(ensure_some(
                     reward.commission.parse().ok(),
-                    "failed to parse reward commission",
+                    ConvertError::invalid("commission"),
                 )?)
             },
         })
@@ -541,64 +680,102 @@ pub mod convert_from {
 
     pub fn create_token_balances(
         balances: Vec<proto::TokenBalance>,
-    ) -> Result<Vec<TransactionTokenBalance>, String> {
+    ) -> ConvertResult<Vec<TransactionTokenBalance>> {
         let mut vec = Vec::with_capacity(balances.len());
         for balance in balances {
-            let ui_amount = ensure_some(balance.ui_token_amount, "failed to get ui_token_amount")?;
-            vec.push(TransactionTokenBalance {
-                account_index: ensure_some(
-                    balance.account_index.try_into().ok(),
-                    "failed to parse account_index",
-                )?,
-                mint: balance.mint,
-                ui_token_amount: UiTokenAmount {
-                    ui_amount: Some(ui_amount.ui_amount),
-                    decimals: ensure_some(
-                        ui_amount.decimals.try_into().ok(),
-                        "failed to parse decimals",
-                    )?,
-                    amount: ui_amount.amount,
-                    ui_amount_string: ui_amount.ui_amount_string,
-                },
-                owner: balance.owner,
-                program_id: balance.program_id,
-            });
+            vec.push(create_token_balance(balance)?);
         }
         Ok(vec)
     }
 
+    fn create_token_balance(
+        balance: proto::TokenBalance,
+    ) -> ConvertResult<TransactionTokenBalance> {
+        let ui_amount = ensure_some(
+            balance.ui_token_amount,
+            ConvertError::missing("ui_token_amount"),
+        )?;
+        Ok(TransactionTokenBalance {
+            account_index: ensure_some(
+                balance.account_index.try_into().ok(),
+                ConvertError::invalid("account_index"),
+            )?,
+            mint: balance.mint,
+            ui_token_amount: UiTokenAmount {
+                ui_amount: Some(ui_amount.ui_amount),
+                decimals: ensure_some(
+                    ui_amount.decimals.try_into().ok(),
+                    ConvertError::invalid("ui_token_amount.decimals"),
+                )?,
+                amount: ui_amount.amount,
+                ui_amount_string: ui_amount.ui_amount_string,
+            },
+            owner: balance.owner,
+            program_id: balance.program_id,
+        })
+    }
+
     pub fn create_loaded_addresses(
         writable: Vec<Vec<u8>>,
         readonly: Vec<Vec<u8>>,
-    ) -> Result<LoadedAddresses, String> {
+    ) -> ConvertResult<LoadedAddresses> {
         Ok(LoadedAddresses {
-            writable: create_pubkey_vec(writable)?,
-            readonly: create_pubkey_vec(readonly)?,
+            writable: parse_pubkey_vec(writable, "writable")?,
+            readonly: parse_pubkey_vec(readonly, "readonly")?,
         })
     }
 
-    pub fn create_pubkey_vec(pubkeys: Vec<Vec<u8>>) -> Result<Vec<Pubkey>, String> {
+    pub fn create_pubkey_vec(pubkeys: Vec<Vec<u8>>) -> ConvertResult<Vec<Pubkey>> {
+        parse_pubkey_vec(pubkeys, "pubkey")
+    }
+
+    pub fn create_pubkey(pubkey: &[u8]) -> ConvertResult<Pubkey> {
+        parse_pubkey(pubkey, "pubkey")
+    }
+
+    pub fn create_signature(signature: &[u8]) -> ConvertResult<Signature> {
+        parse_signature(signature, "signature")
+    }
+
+    fn parse_pubkey_vec(pubkeys: Vec<Vec<u8>>, field: &'static str) -> ConvertResult<Vec<Pubkey>> {
         pubkeys
             .iter()
-            .map(|pubkey| create_pubkey(pubkey.as_slice()))
+            .map(|pubkey| parse_pubkey(pubkey, field))
             .collect()
     }
 
-    pub fn create_pubkey(pubkey: &[u8]) -> Result<Pubkey, String> {
-        ensure_some(Pubkey::try_from(pubkey).ok(), "failed to parse Pubkey")
+    fn parse_pubkey(pubkey: &[u8], field: &'static str) -> ConvertResult<Pubkey> {
+        ensure_some(Pubkey::try_from(pubkey).ok(), ConvertError::invalid(field))
+    }
+
+    fn parse_signature(signature: &[u8], field: &'static str) -> ConvertResult<Signature> {
+        ensure_some(
+            Signature::try_from(signature).ok(),
+            ConvertError::invalid(field),
+        )
     }
 
     pub fn create_account(
         account: proto::SubscribeUpdateAccountInfo,
-    ) -> Result<(Pubkey, Account), String> {
-        let pubkey = create_pubkey(&account.pubkey)?;
+    ) -> ConvertResult<(Pubkey, Account)> {
+        let pubkey = parse_pubkey(&account.pubkey, "pubkey")?;
         let account = Account {
             lamports: account.lamports,
             data: account.data,
-            owner: create_pubkey(&account.owner)?,
+            owner: parse_pubkey(&account.owner, "owner")?,
             executable: account.executable,
             rent_epoch: account.rent_epoch,
         };
         Ok((pubkey, account))
     }
+
+    pub fn create_account_txn_signature(
+        account: &proto::SubscribeUpdateAccountInfo,
+    ) -> ConvertResult<Option<Signature>> {
+        account
+            .txn_signature
+            .as_deref()
+            .map(|signature| parse_signature(signature, "txn_signature"))
+            .transpose()
+    }
 }
@@ -12,7 +12,7 @@ use {
     tracing::{debug, error, info, warn},
     yellowstone_grpc_client::GeyserGrpcClient,
     yellowstone_grpc_proto::{
-        prelude::{subscribe_update::UpdateOneof, CommitmentLevel, SubscribeUpdate},
+        prelude::{subscribe_update::UpdateOneof, SubscribeUpdate},
         prost::Message as _,
     },
     yellowstone_grpc_tools::{
@@ -23,7 +23,7 @@ use {
             prom,
         },
         prom::{run_server as prometheus_run_server, GprcMessageKind},
-        setup_tracing,
+        setup_tracing, subscribe_with_reconnect,
     },
 };
 
@@ -117,14 +117,15 @@ impl ArgsAction {
         let publisher = topic.new_publisher(Some(config.publisher.get_publisher_config()));
 
         // Create gRPC client & subscribe
-        let mut client = GeyserGrpcClient::build_from_shared(config.endpoint)?
+        let client = GeyserGrpcClient::build_from_shared(config.endpoint)?
             .x_token(config.x_token)?
             .connect_timeout(Duration::from_secs(10))
             .timeout(Duration::from_secs(5))
             .max_decoding_message_size(config.max_message_size)
             .connect()
             .await?;
-        let mut geyser = client.subscribe_once(config.request.to_proto()).await?;
+        let geyser = subscribe_with_reconnect(&client, config.request.to_proto());
+        tokio::pin!(geyser);
 
         // Receive-send loop
         let mut send_tasks = JoinSet::new();
@@ -181,10 +182,12 @@ impl ArgsAction {
                             SubscribeUpdate { filters: _, update_oneof: Some(UpdateOneof::Pong(_)) } => prom::recv_inc(GprcMessageKind::Pong),
                             SubscribeUpdate { filters: _, update_oneof: Some(value) } => {
                                 if let UpdateOneof::Slot(slot) = value {
-                                    prom::set_slot_tip(
-                                        CommitmentLevel::try_from(slot.status).expect("valid commitment"),
-                                        slot.slot.try_into().expect("valid i64 slot"),
-                                    );
+                                    if let Some(commitment) = slot.status().commitment() {
+                                        prom::set_slot_tip(
+                                            commitment,
+                                            slot.slot.try_into().expect("valid i64 slot"),
+                                        );
+                                    }
                                 }
 
                                 let message = PubsubMessage {
@@ -282,6 +285,11 @@ impl ArgsAction {
                     Some(UpdateOneof::Pong(_)) => {}
                     Some(UpdateOneof::BlockMeta(msg)) => info!("#{}, blockmeta", msg.slot),
                     Some(UpdateOneof::Entry(msg)) => info!("#{}, entry", msg.slot),
+                    Some(UpdateOneof::Gap(msg)) => {
+                        info!("#{}..{}, gap", msg.from_slot, msg.to_slot)
+                    }
+                    Some(UpdateOneof::Rollback(msg)) => info!("#{}, rollback", msg.slot),
+                    Some(UpdateOneof::BlockFragment(msg)) => info!("#{}, block fragment", msg.slot),
                     None => {}
                 },
                 Err(error) => {
@@ -22,7 +22,7 @@ use {
             prom,
         },
         prom::{run_server as prometheus_run_server, GprcMessageKind},
-        setup_tracing,
+        setup_tracing, subscribe_with_reconnect,
     },
 };
 
@@ -226,13 +226,14 @@ impl ArgsAction {
         tokio::pin!(kafka_error_rx);
 
         // Create gRPC client & subscribe
-        let mut client = GeyserGrpcClient::build_from_shared(config.endpoint)?
+        let client = GeyserGrpcClient::build_from_shared(config.endpoint)?
             .x_token(config.x_token)?
             .connect_timeout(Duration::from_secs(10))
             .timeout(Duration::from_secs(5))
             .connect()
             .await?;
-        let mut geyser = client.subscribe_once(config.request.to_proto()).await?;
+        let geyser = subscribe_with_reconnect(&client, config.request.to_proto());
+        tokio::pin!(geyser);
 
         // Receive-send loop
         let mut send_tasks = JoinSet::new();
@@ -278,6 +279,9 @@ impl ArgsAction {
                         UpdateOneof::Pong(_) => continue,
                         UpdateOneof::BlockMeta(msg) => msg.slot,
                         UpdateOneof::Entry(msg) => msg.slot,
+                        UpdateOneof::Gap(msg) => msg.to_slot,
+                        UpdateOneof::Rollback(msg) => msg.slot,
+                        UpdateOneof::BlockFragment(msg) => msg.slot,
                     };
                     let hash = Sha256::digest(&payload);
                     let key = format!("{slot}_{}", const_hex::encode(hash));
@@ -342,7 +346,8 @@ impl ArgsAction {
             kafka_config.set(key, value);
         }
 
-        let (grpc_tx, grpc_shutdown) = GrpcService::run(config.listen, config.channel_capacity)?;
+        let (grpc_tx, grpc_shutdown) =
+            GrpcService::run(config.listen, config.channel_capacity, &config.compression)?;
 
         let (consumer, kafka_error_rx) = prom::StatsContext::create_stream_consumer(&kafka_config)
             .context("failed to create kafka consumer")?;
@@ -9,11 +9,14 @@ use {
     tokio::fs,
     yellowstone_grpc_proto::prelude::{
         subscribe_request_filter_accounts_filter::Filter as AccountsFilterDataOneof,
+        subscribe_request_filter_accounts_filter_lamports::Cmp as AccountsFilterLamportsCmp,
         subscribe_request_filter_accounts_filter_memcmp::Data as AccountsFilterMemcmpOneof,
         CommitmentLevel, SubscribeRequest, SubscribeRequestAccountsDataSlice,
         SubscribeRequestFilterAccounts, SubscribeRequestFilterAccountsFilter,
-        SubscribeRequestFilterAccountsFilterMemcmp, SubscribeRequestFilterBlocks,
-        SubscribeRequestFilterSlots, SubscribeRequestFilterTransactions,
+        SubscribeRequestFilterAccountsFilterLamports, SubscribeRequestFilterAccountsFilterMemcmp,
+        SubscribeRequestFilterBlocks, SubscribeRequestFilterSlots,
+        SubscribeRequestFilterTransactions, SubscribeRequestFilterTransactionsOmit,
+        SubscribeRequestFilterTransactionsProgram,
     },
 };
 
@@ -79,6 +82,10 @@ impl GrpcRequestToProto<SubscribeRequest> for ConfigGrpcRequest {
             commitment: self.commitment.map(|v| v.to_proto() as i32),
             accounts_data_slice: ConfigGrpcRequest::vec_to_proto(self.accounts_data_slice),
             ping: None,
+            from_slot: None,
+            backpressure: None,
+            snapshot: false,
+            rollback: false,
         }
     }
 }
@@ -87,12 +94,14 @@ impl GrpcRequestToProto<SubscribeRequest> for ConfigGrpcRequest {
 #[serde(default)]
 pub struct ConfigGrpcRequestSlots {
     filter_by_commitment: Option<bool>,
+    include_dead: Option<bool>,
 }
 
 impl GrpcRequestToProto<SubscribeRequestFilterSlots> for ConfigGrpcRequestSlots {
     fn to_proto(self) -> SubscribeRequestFilterSlots {
         SubscribeRequestFilterSlots {
             filter_by_commitment: self.filter_by_commitment,
+            include_dead: self.include_dead,
         }
     }
 }
@@ -103,6 +112,7 @@ pub struct ConfigGrpcRequestAccounts {
     account: Vec<String>,
     owner: Vec<String>,
     filters: Vec<ConfigGrpcRequestAccountsFilter>,
+    send_initial_state: bool,
 }
 
 impl GrpcRequestToProto<SubscribeRequestFilterAccounts> for ConfigGrpcRequestAccounts {
@@ -111,6 +121,7 @@ impl GrpcRequestToProto<SubscribeRequestFilterAccounts> for ConfigGrpcRequestAcc
             account: self.account,
             owner: self.owner,
             filters: self.filters.into_iter().map(|f| f.to_proto()).collect(),
+            send_initial_state: self.send_initial_state,
         }
     }
 }
@@ -120,6 +131,8 @@ pub enum ConfigGrpcRequestAccountsFilter {
     Memcmp { offset: u64, base58: String },
     DataSize(u64),
     TokenAccountState,
+    Lamports(ConfigGrpcRequestAccountsFilterLamports),
+    Executable(bool),
 }
 
 impl GrpcRequestToProto<SubscribeRequestFilterAccountsFilter> for ConfigGrpcRequestAccountsFilter {
@@ -138,11 +151,42 @@ impl GrpcRequestToProto<SubscribeRequestFilterAccountsFilter> for ConfigGrpcRequ
                 ConfigGrpcRequestAccountsFilter::TokenAccountState => {
                     AccountsFilterDataOneof::TokenAccountState(true)
                 }
+                ConfigGrpcRequestAccountsFilter::Lamports(cmp) => {
+                    AccountsFilterDataOneof::Lamports(
+                        SubscribeRequestFilterAccountsFilterLamports {
+                            cmp: Some(match cmp {
+                                ConfigGrpcRequestAccountsFilterLamports::Eq(value) => {
+                                    AccountsFilterLamportsCmp::Eq(value)
+                                }
+                                ConfigGrpcRequestAccountsFilterLamports::Ne(value) => {
+                                    AccountsFilterLamportsCmp::Ne(value)
+                                }
+                                ConfigGrpcRequestAccountsFilterLamports::Lt(value) => {
+                                    AccountsFilterLamportsCmp::Lt(value)
+                                }
+                                ConfigGrpcRequestAccountsFilterLamports::Gt(value) => {
+                                    AccountsFilterLamportsCmp::Gt(value)
+                                }
+                            }),
+                        },
+                    )
+                }
+                ConfigGrpcRequestAccountsFilter::Executable(value) => {
+                    AccountsFilterDataOneof::Executable(value)
+                }
             }),
         }
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ConfigGrpcRequestAccountsFilterLamports {
+    Eq(u64),
+    Ne(u64),
+    Lt(u64),
+    Gt(u64),
+}
+
 #[derive(Debug, Default, Clone, Deserialize, Serialize)]
 #[serde(default)]
 pub struct ConfigGrpcRequestTransactions {
@@ -152,6 +196,11 @@ pub struct ConfigGrpcRequestTransactions {
     pub account_include: Vec<String>,
     pub account_exclude: Vec<String>,
     pub account_required: Vec<String>,
+    pub program_invoked: Vec<ConfigGrpcRequestTransactionsProgram>,
+    pub log_contains: Vec<String>,
+    pub log_prefix: Vec<String>,
+    pub strip_logs: Option<bool>,
+    pub omit: Option<ConfigGrpcRequestTransactionsOmit>,
 }
 
 impl GrpcRequestToProto<SubscribeRequestFilterTransactions> for ConfigGrpcRequestTransactions {
@@ -163,6 +212,55 @@ impl GrpcRequestToProto<SubscribeRequestFilterTransactions> for ConfigGrpcReques
             account_include: self.account_include,
             account_exclude: self.account_exclude,
             account_required: self.account_required,
+            program_invoked: ConfigGrpcRequest::vec_to_proto(self.program_invoked),
+            log_contains: self.log_contains,
+            log_prefix: self.log_prefix,
+            strip_logs: self.strip_logs,
+            omit: self.omit.map(|omit| omit.to_proto()),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigGrpcRequestTransactionsOmit {
+    pub log_messages: bool,
+    pub inner_instructions: bool,
+    pub token_balances: bool,
+    pub rewards: bool,
+    pub return_data: bool,
+    pub loaded_addresses: bool,
+}
+
+impl GrpcRequestToProto<SubscribeRequestFilterTransactionsOmit>
+    for ConfigGrpcRequestTransactionsOmit
+{
+    fn to_proto(self) -> SubscribeRequestFilterTransactionsOmit {
+        SubscribeRequestFilterTransactionsOmit {
+            log_messages: self.log_messages,
+            inner_instructions: self.inner_instructions,
+            token_balances: self.token_balances,
+            rewards: self.rewards,
+            return_data: self.return_data,
+            loaded_addresses: self.loaded_addresses,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct ConfigGrpcRequestTransactionsProgram {
+    pub program_id: String,
+    pub discriminators: Vec<Vec<u8>>,
+}
+
+impl GrpcRequestToProto<SubscribeRequestFilterTransactionsProgram>
+    for ConfigGrpcRequestTransactionsProgram
+{
+    fn to_proto(self) -> SubscribeRequestFilterTransactionsProgram {
+        SubscribeRequestFilterTransactionsProgram {
+            program_id: self.program_id,
+            discriminators: self.discriminators,
         }
     }
 }
@@ -174,6 +272,8 @@ pub struct ConfigGrpcRequestBlocks {
     pub include_transactions: Option<bool>,
     pub include_accounts: Option<bool>,
     pub include_entries: Option<bool>,
+    pub fragments: Option<bool>,
+    pub include_entry_index: Option<bool>,
 }
 
 impl GrpcRequestToProto<SubscribeRequestFilterBlocks> for ConfigGrpcRequestBlocks {
@@ -183,6 +283,8 @@ impl GrpcRequestToProto<SubscribeRequestFilterBlocks> for ConfigGrpcRequestBlock
             include_transactions: self.include_transactions,
             include_accounts: self.include_accounts,
             include_entries: self.include_entries,
+            fragments: self.fragments,
+            include_entry_index: self.include_entry_index,
         }
     }
 }
@@ -250,7 +352,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::ConfigGrpcRequestAccountsFilter;
+    use super::{ConfigGrpcRequestAccountsFilter, ConfigGrpcRequestAccountsFilterLamports};
 
     #[test]
     fn grpc_config_accounts_filter_memcmp() {
@@ -284,4 +386,16 @@ mod tests {
             filter
         );
     }
+
+    #[test]
+    fn grpc_config_accounts_filter_lamports() {
+        let filter = ConfigGrpcRequestAccountsFilter::Lamports(
+            ConfigGrpcRequestAccountsFilterLamports::Gt(42),
+        );
+        let text = serde_json::to_string(&filter).unwrap();
+        assert_eq!(
+            serde_json::from_str::<ConfigGrpcRequestAccountsFilter>(&text).unwrap(),
+            filter
+        );
+    }
 }
@@ -1,8 +1,9 @@
 use {
     super::dedup::{KafkaDedup, KafkaDedupMemory},
     crate::config::{deserialize_usize_str, ConfigGrpcRequest},
-    serde::Deserialize,
+    serde::{de, Deserialize, Deserializer},
     std::{collections::HashMap, net::SocketAddr},
+    yellowstone_grpc_proto::compression::CompressionEncoding,
 };
 
 #[derive(Debug, Default, Deserialize)]
@@ -72,6 +73,8 @@ pub struct ConfigKafka2Grpc {
     pub listen: SocketAddr,
     #[serde(default = "ConfigKafka2Grpc::channel_capacity_default")]
     pub channel_capacity: usize,
+    #[serde(default)]
+    pub compression: ConfigKafka2GrpcCompression,
 }
 
 impl ConfigKafka2Grpc {
@@ -79,3 +82,35 @@ impl ConfigKafka2Grpc {
         250_000
     }
 }
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+pub struct ConfigKafka2GrpcCompression {
+    /// Encodings in order of preference, first accepted by the client is used
+    #[serde(deserialize_with = "ConfigKafka2GrpcCompression::deserialize_send")]
+    pub send: Vec<CompressionEncoding>,
+    pub gzip_level: u32,
+    pub zstd_level: i32,
+}
+
+impl Default for ConfigKafka2GrpcCompression {
+    fn default() -> Self {
+        Self {
+            send: vec![CompressionEncoding::Gzip],
+            gzip_level: 6,
+            zstd_level: 3,
+        }
+    }
+}
+
+impl ConfigKafka2GrpcCompression {
+    fn deserialize_send<'de, D>(deserializer: D) -> Result<Vec<CompressionEncoding>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        Vec::<String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|value| value.parse().map_err(de::Error::custom))
+            .collect()
+    }
+}
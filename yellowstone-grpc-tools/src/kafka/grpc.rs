@@ -1,5 +1,8 @@
 use {
-    crate::version::VERSION,
+    crate::{
+        kafka::{config::ConfigKafka2GrpcCompression, prom},
+        version::VERSION,
+    },
     futures::future::{BoxFuture, FutureExt},
     std::{
         net::SocketAddr,
@@ -24,13 +27,18 @@ use {
     },
     tonic_health::server::health_reporter,
     tracing::{error, info},
-    yellowstone_grpc_proto::prelude::{
-        geyser_server::{Geyser, GeyserServer},
-        subscribe_update::UpdateOneof,
-        GetBlockHeightRequest, GetBlockHeightResponse, GetLatestBlockhashRequest,
-        GetLatestBlockhashResponse, GetSlotRequest, GetSlotResponse, GetVersionRequest,
-        GetVersionResponse, IsBlockhashValidRequest, IsBlockhashValidResponse, PingRequest,
-        PongResponse, SubscribeRequest, SubscribeUpdate, SubscribeUpdatePing,
+    yellowstone_grpc_proto::{
+        compression::{SendCompressionConfig, SendCompressionLayer},
+        prelude::{
+            geyser_server::{Geyser, GeyserServer},
+            subscribe_update::UpdateOneof,
+            GetAccountInfoRequest, GetAccountInfoResponse, GetBlockHeightRequest,
+            GetBlockHeightResponse, GetLatestBlockhashRequest, GetLatestBlockhashResponse,
+            GetMultipleAccountsRequest, GetMultipleAccountsResponse, GetSlotRequest,
+            GetSlotResponse, GetVersionRequest, GetVersionResponse, IsBlockhashValidRequest,
+            IsBlockhashValidResponse, PingRequest, PongResponse, SubscribeRequest, SubscribeUpdate,
+            SubscribeUpdatePing,
+        },
     },
 };
 
@@ -46,6 +54,7 @@ impl GrpcService {
     pub fn run(
         listen: SocketAddr,
         channel_capacity: usize,
+        compression: &ConfigKafka2GrpcCompression,
     ) -> anyhow::Result<(
         broadcast::Sender<SubscribeUpdate>,
         BoxFuture<'static, Result<Result<(), TransportError>, JoinError>>,
@@ -67,8 +76,14 @@ impl GrpcService {
             channel_capacity,
             broadcast_tx: broadcast_tx.clone(),
         })
-        .accept_compressed(CompressionEncoding::Gzip)
-        .send_compressed(CompressionEncoding::Gzip);
+        // tonic decompresses requests only with gzip, zstd is used only for sent messages
+        .accept_compressed(CompressionEncoding::Gzip);
+        let compression_layer = SendCompressionLayer::new(SendCompressionConfig {
+            encodings: compression.send.clone(),
+            gzip_level: compression.gzip_level,
+            zstd_level: compression.zstd_level,
+            metrics: Some(prom::grpc_compression_inc),
+        });
 
         let shutdown = Arc::new(Notify::new());
         let shutdown_grpc = Arc::clone(&shutdown);
@@ -80,6 +95,7 @@ impl GrpcService {
 
             Server::builder()
                 .http2_keepalive_interval(Some(Duration::from_secs(5)))
+                .layer(compression_layer)
                 .add_service(health_service)
                 .add_service(service)
                 .serve_with_incoming_shutdown(incoming, shutdown_grpc.notified())
@@ -245,4 +261,18 @@ impl Geyser for GrpcService {
             version: serde_json::to_string(&VERSION).unwrap(),
         }))
     }
+
+    async fn get_account_info(
+        &self,
+        _request: Request<GetAccountInfoRequest>,
+    ) -> Result<Response<GetAccountInfoResponse>, Status> {
+        Err(Status::unimplemented("not implemented in kafka reader"))
+    }
+
+    async fn get_multiple_accounts(
+        &self,
+        _request: Request<GetMultipleAccountsRequest>,
+    ) -> Result<Response<GetMultipleAccountsResponse>, Status> {
+        Err(Status::unimplemented("not implemented in kafka reader"))
+    }
 }
@@ -31,6 +31,16 @@ lazy_static::lazy_static! {
         Opts::new("kafka_sent_total", "Total number of uploaded messages by type"),
         &["kind"]
     ).unwrap();
+
+    pub(crate) static ref GRPC_COMPRESSION_RAW_BYTES_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("grpc_compression_raw_bytes_total", "Total size of gRPC messages before compression"),
+        &["encoding"]
+    ).unwrap();
+
+    pub(crate) static ref GRPC_COMPRESSION_SENT_BYTES_TOTAL: IntCounterVec = IntCounterVec::new(
+        Opts::new("grpc_compression_sent_bytes_total", "Total size of gRPC messages after compression"),
+        &["encoding"]
+    ).unwrap();
 }
 
 #[derive(Debug)]
@@ -164,3 +174,12 @@ pub fn recv_inc() {
 pub fn sent_inc(kind: GprcMessageKind) {
     KAFKA_SENT_TOTAL.with_label_values(&[kind.as_str()]).inc()
 }
+
+pub fn grpc_compression_inc(encoding: &'static str, raw: usize, sent: usize) {
+    GRPC_COMPRESSION_RAW_BYTES_TOTAL
+        .with_label_values(&[encoding])
+        .inc_by(raw as u64);
+    GRPC_COMPRESSION_SENT_BYTES_TOTAL
+        .with_label_values(&[encoding])
+        .inc_by(sent as u64);
+}
@@ -12,13 +12,23 @@ pub mod prom;
 pub mod version;
 
 use {
-    futures::future::{BoxFuture, FutureExt},
+    backoff::ExponentialBackoff,
+    futures::{
+        future::{self, BoxFuture, FutureExt},
+        stream::{Stream, StreamExt},
+    },
     tokio::signal::unix::{signal, SignalKind},
+    tracing::{info, warn},
     tracing_subscriber::{
         filter::{EnvFilter, LevelFilter},
         layer::SubscriberExt,
         util::SubscriberInitExt,
     },
+    yellowstone_grpc_client::{
+        GeyserGrpcClient, GeyserGrpcClientError, Interceptor, ManagedSubscriptionConfig,
+        ManagedSubscriptionUpdate,
+    },
+    yellowstone_grpc_proto::prelude::{SubscribeRequest, SubscribeUpdate},
 };
 
 pub fn setup_tracing() -> anyhow::Result<()> {
@@ -45,3 +55,49 @@ pub fn create_shutdown() -> anyhow::Result<BoxFuture<'static, ()>> {
     }
     .boxed())
 }
+
+pub fn subscribe_with_reconnect<F>(
+    client: &GeyserGrpcClient<F>,
+    request: SubscribeRequest,
+) -> impl Stream<Item = Result<SubscribeUpdate, GeyserGrpcClientError>>
+where
+    F: Interceptor + Clone + Send + 'static,
+{
+    // retry until the service is stopped, only permanent errors finish the stream
+    let config = ManagedSubscriptionConfig {
+        backoff: ExponentialBackoff {
+            max_elapsed_time: None,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let (_subscribe_tx, stream) = client.subscribe_managed(request, config);
+    stream.filter_map(|update| {
+        future::ready(match update {
+            Ok(ManagedSubscriptionUpdate::Update(update)) => Some(Ok(update)),
+            Ok(ManagedSubscriptionUpdate::Pong { .. }) => None,
+            Ok(ManagedSubscriptionUpdate::Disconnected {
+                error,
+                last_slot,
+                delay,
+            }) => {
+                warn!(
+                    "gRPC stream disconnected (last slot: {last_slot:?}), \
+                    reconnect in {delay:?}: {error}"
+                );
+                None
+            }
+            Ok(ManagedSubscriptionUpdate::Reconnected {
+                attempts,
+                last_slot,
+            }) => {
+                info!(
+                    "gRPC stream reconnected after {attempts} attempts \
+                    (last slot: {last_slot:?})"
+                );
+                None
+            }
+            Err(error) => Some(Err(error)),
+        })
+    })
+}
@@ -5,7 +5,10 @@ use crate::google_pubsub::prom::{
     GOOGLE_PUBSUB_SLOT_TIP,
 };
 #[cfg(feature = "kafka")]
-use crate::kafka::prom::{KAFKA_DEDUP_TOTAL, KAFKA_RECV_TOTAL, KAFKA_SENT_TOTAL, KAFKA_STATS};
+use crate::kafka::prom::{
+    GRPC_COMPRESSION_RAW_BYTES_TOTAL, GRPC_COMPRESSION_SENT_BYTES_TOTAL, KAFKA_DEDUP_TOTAL,
+    KAFKA_RECV_TOTAL, KAFKA_SENT_TOTAL, KAFKA_STATS,
+};
 use {
     crate::version::VERSION as VERSION_INFO,
     hyper::{
@@ -55,6 +58,8 @@ pub fn run_server(address: SocketAddr) -> anyhow::Result<()> {
             register!(KAFKA_DEDUP_TOTAL);
             register!(KAFKA_RECV_TOTAL);
             register!(KAFKA_SENT_TOTAL);
+            register!(GRPC_COMPRESSION_RAW_BYTES_TOTAL);
+            register!(GRPC_COMPRESSION_SENT_BYTES_TOTAL);
         }
 
         VERSION
@@ -118,6 +123,9 @@ pub enum GprcMessageKind {
     Pong,
     BlockMeta,
     Entry,
+    Gap,
+    Rollback,
+    BlockFragment,
     Unknown,
 }
 
@@ -133,6 +141,9 @@ impl From<&UpdateOneof> for GprcMessageKind {
             UpdateOneof::Pong(_) => Self::Pong,
             UpdateOneof::BlockMeta(_) => Self::BlockMeta,
             UpdateOneof::Entry(_) => Self::Entry,
+            UpdateOneof::Gap(_) => Self::Gap,
+            UpdateOneof::Rollback(_) => Self::Rollback,
+            UpdateOneof::BlockFragment(_) => Self::BlockFragment,
         }
     }
 }
@@ -149,6 +160,9 @@ impl GprcMessageKind {
             GprcMessageKind::Pong => "pong",
             GprcMessageKind::BlockMeta => "blockmeta",
             GprcMessageKind::Entry => "entry",
+            GprcMessageKind::Gap => "gap",
+            GprcMessageKind::Rollback => "rollback",
+            GprcMessageKind::BlockFragment => "blockfragment",
             GprcMessageKind::Unknown => "unknown",
         }
     }